@@ -104,6 +104,9 @@ function loadConfig() {
 // Windows专用配置
 const CONFIG = loadConfig();
 
+// Rust 发布命令，负责生成 latest.json 并上传到WebDAV
+const RELEASE_COMMAND = 'cargo run --quiet --manifest-path src-tauri/Cargo.toml --bin workhelper-release --';
+
 class VersionManager {
   constructor() {
     this.git = simpleGit();
@@ -227,6 +230,20 @@ class VersionManager {
     }
   }
 
+  // 检查嵌入程序的只读账号是否与发布账号不同
+  checkReadOnlyAccount() {
+    const value = (name) => process.env[name] || '';
+    const sameUsername = value('WEBDAV_READ_USERNAME') !== '' && value('WEBDAV_READ_USERNAME') === value('WEBDAV_USERNAME');
+    const samePassword = value('WEBDAV_READ_PASSWORD') !== '' && value('WEBDAV_READ_PASSWORD') === value('WEBDAV_PASSWORD');
+
+    if (sameUsername || samePassword) {
+      console.error('❌ WEBDAV_READ_USERNAME/WEBDAV_READ_PASSWORD 与发布账号 WEBDAV_USERNAME/WEBDAV_PASSWORD 相同');
+      console.error('只读账号会被编译进程序，请为更新目录创建单独的只读账号（或留空匿名读取）后重新构建');
+      return false;
+    }
+    return true;
+  }
+
   // 构建应用
   async buildApp() {
     console.log('🔨 开始构建应用...');
@@ -234,9 +251,19 @@ class VersionManager {
       // 加载环境变量到构建进程
       this.loadEnvForBuild();
 
+      // 只读账号会被编译进程序，与发布账号相同时写权限凭据会随安装包泄露，拒绝构建
+      if (!this.checkReadOnlyAccount()) {
+        return false;
+      }
+
+      // 发布账号只用于上传，不传给构建进程，避免被编译进程序
+      const buildEnv = { ...process.env };
+      delete buildEnv.WEBDAV_USERNAME;
+      delete buildEnv.WEBDAV_PASSWORD;
+
       execSync(CONFIG.build.command, {
         stdio: 'inherit',
-        env: { ...process.env } // 确保环境变量传递给子进程
+        env: buildEnv // 确保环境变量传递给子进程
       });
 
       console.log('\n' + '='.repeat(60));
@@ -308,7 +335,7 @@ class VersionManager {
       }
     });
 
-    // 检查必要的环境变量
+    // 检查必要的环境变量（发布账号在上传时使用）
     const requiredVars = ['WEBDAV_URL', 'WEBDAV_USERNAME', 'WEBDAV_PASSWORD', 'WEBDAV_BASE_URL'];
     const missingVars = requiredVars.filter(varName => !process.env[varName]);
 
@@ -388,15 +415,6 @@ class VersionManager {
     return files;
   }
 
-  // 格式化文件大小
-  formatFileSize(bytes) {
-    if (bytes === 0) return '0 Bytes';
-    const k = 1024;
-    const sizes = ['Bytes', 'KB', 'MB', 'GB'];
-    const i = Math.floor(Math.log(bytes) / Math.log(k));
-    return parseFloat((bytes / Math.pow(k, i)).toFixed(2)) + ' ' + sizes[i];
-  }
-
   // 查找Windows构建文件
   findBuildFiles(targetVersion = null) {
     const files = [];
@@ -464,337 +482,18 @@ class VersionManager {
     return files;
   }
 
-  // 上传Windows构建文件到WebDAV服务器
-  async uploadToWebDAV(version, changelog) {
-    console.log('☁️ 上传Windows构建文件到WebDAV服务器...');
-    console.log(`🔧 WebDAV配置:`);
-    console.log(`  URL: ${CONFIG.webdav.url}`);
-    console.log(`  用户名: ${CONFIG.webdav.username}`);
-    console.log(`  远程路径: ${CONFIG.webdav.remotePath}`);
-    console.log(`  基础URL: ${CONFIG.webdav.baseUrl}`);
+  // 调用 Rust 发布命令上传构建产物并更新 latest.json
+  publishRelease(version, changelog, extraArgs = []) {
+    const changelogFile = path.join(process.cwd(), 'temp-changelog.md');
+    fs.writeFileSync(changelogFile, changelog);
 
     try {
-      // 查找Windows构建产物（只查找当前版本）
-      const buildFiles = this.findBuildFiles(version);
-
-      if (buildFiles.length === 0) {
-        throw new Error(`未找到版本 ${version} 的构建文件，请先执行构建命令`);
-      }
-
-      const uploadResults = [];
-
-      for (const file of buildFiles) {
-        const fileName = file.name;
-        const remotePath = `${CONFIG.webdav.remotePath}/v${version}/windows/${fileName}`;
-        const downloadUrl = `${CONFIG.webdav.baseUrl}/v${version}/windows/${fileName}`;
-
-        console.log(`📤 上传文件: ${file.name} -> ${remotePath}`);
-
-        // 使用WebDAV上传文件
-        const success = await this.uploadFileToWebDAV(file.path, remotePath);
-
-        if (success) {
-          console.log(`✅ ${file.name} 上传成功`);
-          uploadResults.push({
-            platform: 'windows',
-            type: file.type,
-            name: file.name,
-            url: downloadUrl,
-            size: fs.statSync(file.path).size,
-            sizeFormatted: this.formatFileSize(fs.statSync(file.path).size)
-          });
-        } else {
-          console.error(`❌ 上传失败详情:`);
-          console.error(`  文件: ${file.name}`);
-          console.error(`  本地路径: ${file.path}`);
-          console.error(`  远程路径: ${remotePath}`);
-          console.error(`  WebDAV URL: ${CONFIG.webdav.url}${remotePath}`);
-          throw new Error(`上传 ${file.name} 失败，请检查WebDAV服务器配置和网络连接`);
-        }
-      }
-
-      // 创建Windows版本信息文件
-      const versionInfo = {
-        version,
-        platform: 'windows',
-        releaseDate: new Date().toISOString(),
-        changelog,
-        downloads: uploadResults,
-        mandatory: false,
-        minVersion: '1.0.0' // 最低支持版本
-      };
-
-      // 上传版本信息文件
-      await this.uploadVersionInfo(versionInfo);
-
-      console.log('✅ 所有文件上传完成');
-      return uploadResults;
-
-    } catch (error) {
-      console.error('❌ 上传失败:', error);
-      throw error;
-    }
-  }
-
-  // WebDAV文件上传方法
-  async uploadFileToWebDAV(localPath, remotePath) {
-    try {
-      // 确保目录存在
-      const dirPath = path.dirname(remotePath);
-      await this.ensureWebDAVDirectory(dirPath);
-
-      const fileContent = fs.readFileSync(localPath);
-      const auth = Buffer.from(`${CONFIG.webdav.username}:${CONFIG.webdav.password}`).toString('base64');
-
-      console.log(`🔗 上传到: ${CONFIG.webdav.url}${remotePath}`);
-
-      const response = await fetch(`${CONFIG.webdav.url}${remotePath}`, {
-        method: 'PUT',
-        headers: {
-          'Authorization': `Basic ${auth}`,
-          'Content-Type': 'application/octet-stream',
-          'Content-Length': fileContent.length.toString()
-        },
-        body: fileContent
-      });
-
-      if (!response.ok) {
-        const errorText = await response.text();
-        console.error(`❌ WebDAV上传失败: ${response.status} ${response.statusText}`);
-        console.error(`错误详情: ${errorText}`);
-        return false;
-      }
-
-      console.log(`✅ 文件上传成功: ${response.status} ${response.statusText}`);
-
-      // 验证上传并强制刷新缓存
-      await this.verifyUploadAndRefresh(remotePath);
-
-      return true;
-    } catch (error) {
-      console.error(`❌ WebDAV上传异常: ${error.message}`);
-      console.error(`错误堆栈: ${error.stack}`);
-      return false;
-    }
-  }
-
-  // 创建WebDAV目录
-  async createWebDAVDirectory(remotePath) {
-    try {
-      const auth = Buffer.from(`${CONFIG.webdav.username}:${CONFIG.webdav.password}`).toString('base64');
-      const fullUrl = `${CONFIG.webdav.url}${remotePath}`;
-
-      console.log(`🔗 创建目录请求: ${fullUrl}`);
-
-      const response = await fetch(fullUrl, {
-        method: 'MKCOL',
-        headers: {
-          'Authorization': `Basic ${auth}`,
-          'User-Agent': 'VersionManager/1.0'
-        }
-      });
-
-      console.log(`📁 目录创建响应: ${response.status} ${response.statusText}`);
-
-      if (response.ok || response.status === 405) {
-        // 创建成功后，等待一下让 alist 更新索引
-        await new Promise(resolve => setTimeout(resolve, 500));
-
-        // 尝试触发目录刷新
-        await this.refreshDirectoryIndex(remotePath);
-
-        return true;
-      } else {
-        const errorText = await response.text();
-        console.warn(`⚠️ 创建目录失败: ${response.status} ${response.statusText}`);
-        console.warn(`错误详情: ${errorText}`);
-        return false;
-      }
-    } catch (error) {
-      console.error(`创建WebDAV目录失败: ${error.message}`);
-      return false;
-    }
-  }
-
-  // 刷新目录索引（触发 alist 更新缓存）
-  async refreshDirectoryIndex(remotePath) {
-    try {
-      const auth = Buffer.from(`${CONFIG.webdav.username}:${CONFIG.webdav.password}`).toString('base64');
-      const fullUrl = `${CONFIG.webdav.url}${remotePath}`;
-
-      console.log(`🔄 刷新目录索引: ${fullUrl}`);
-
-      // 方法1: 使用 PROPFIND 请求触发目录扫描
-      const propfindResponse = await fetch(fullUrl, {
-        method: 'PROPFIND',
-        headers: {
-          'Authorization': `Basic ${auth}`,
-          'Depth': '1',
-          'Content-Type': 'application/xml',
-          'User-Agent': 'VersionManager/1.0',
-          'Cache-Control': 'no-cache',
-          'Pragma': 'no-cache'
-        }
-      });
-
-      if (propfindResponse.ok) {
-        console.log(`✅ PROPFIND 刷新成功`);
-      }
-
-      // 方法2: 使用 HEAD 请求触发缓存更新
-      const headResponse = await fetch(fullUrl, {
-        method: 'HEAD',
-        headers: {
-          'Authorization': `Basic ${auth}`,
-          'Cache-Control': 'no-cache',
-          'Pragma': 'no-cache'
-        }
-      });
-
-      if (headResponse.ok) {
-        console.log(`✅ HEAD 刷新成功`);
-      }
-
-      // 方法3: 尝试访问父目录触发级联刷新
-      const parentPath = remotePath.substring(0, remotePath.lastIndexOf('/'));
-      if (parentPath && parentPath !== remotePath) {
-        const parentResponse = await fetch(`${CONFIG.webdav.url}${parentPath}`, {
-          method: 'PROPFIND',
-          headers: {
-            'Authorization': `Basic ${auth}`,
-            'Depth': '1',
-            'Cache-Control': 'no-cache'
-          }
-        });
-
-        if (parentResponse.ok) {
-          console.log(`✅ 父目录刷新成功`);
-        }
-      }
-
-    } catch (error) {
-      console.warn(`⚠️ 目录索引刷新异常: ${error.message}`);
-    }
-  }
-
-  // 验证上传并强制刷新缓存
-  async verifyUploadAndRefresh(remotePath) {
-    try {
-      console.log(`🔍 验证上传并刷新缓存: ${remotePath}`);
-
-      const auth = Buffer.from(`${CONFIG.webdav.username}:${CONFIG.webdav.password}`).toString('base64');
-      const fullUrl = `${CONFIG.webdav.url}${remotePath}`;
-
-      // 1. 验证文件是否存在
-      const headResponse = await fetch(fullUrl, {
-        method: 'HEAD',
-        headers: {
-          'Authorization': `Basic ${auth}`,
-          'Cache-Control': 'no-cache'
-        }
-      });
-
-      if (headResponse.ok) {
-        const size = headResponse.headers.get('content-length');
-        console.log(`✅ 文件验证成功，大小: ${size} 字节`);
-      } else {
-        console.warn(`⚠️ 文件验证失败: ${headResponse.status}`);
-        return false;
-      }
-
-      // 2. 强制刷新所有相关目录
-      const dirPath = remotePath.substring(0, remotePath.lastIndexOf('/'));
-      await this.refreshDirectoryIndex(dirPath);
-
-      // 3. 等待更长时间确保 alist 处理完成
-      console.log(`⏳ 等待 alist 更新索引...`);
-      await new Promise(resolve => setTimeout(resolve, 2000));
-
-      // 4. 再次验证目录列表
-      const listResponse = await fetch(`${CONFIG.webdav.url}${dirPath}`, {
-        method: 'PROPFIND',
-        headers: {
-          'Authorization': `Basic ${auth}`,
-          'Depth': '1',
-          'Cache-Control': 'no-cache',
-          'Pragma': 'no-cache'
-        }
-      });
-
-      if (listResponse.ok) {
-        const xmlText = await listResponse.text();
-        const fileName = remotePath.split('/').pop();
-        const fileExists = xmlText.includes(fileName);
-
-        if (fileExists) {
-          console.log(`✅ 文件在目录列表中可见`);
-        } else {
-          console.warn(`⚠️ 文件在目录列表中不可见，但可以直接访问`);
-          console.warn(`💡 这是 alist 缓存问题，文件已成功上传`);
-          console.warn(`🔧 解决方案:`);
-          console.warn(`   1. 手动刷新 alist 管理界面`);
-          console.warn(`   2. 重启 alist 服务: sudo systemctl restart alist`);
-          console.warn(`   3. 文件仍然可以正常下载和使用`);
-        }
-      }
-
-      return true;
-    } catch (error) {
-      console.error(`❌ 验证上传异常: ${error.message}`);
-      return false;
-    }
-  }
-
-  // 确保WebDAV目录存在（递归创建）
-  async ensureWebDAVDirectory(remotePath) {
-    try {
-      // 规范化路径
-      const normalizedPath = remotePath.replace(/\\/g, '/');
-      const pathParts = normalizedPath.split('/').filter(part => part.length > 0);
-
-      // 递归创建每一级目录
-      let currentPath = '';
-      for (const part of pathParts) {
-        currentPath += '/' + part;
-
-        // 尝试创建目录
-        const success = await this.createWebDAVDirectory(currentPath);
-        if (!success) {
-          console.warn(`⚠️ 创建目录失败: ${currentPath}`);
-        }
-      }
-
-      return true;
-    } catch (error) {
-      console.error(`确保WebDAV目录存在失败: ${error.message}`);
-      return false;
-    }
-  }
-
-  // 上传版本信息到WebDAV
-  async uploadVersionInfo(versionInfo) {
-    try {
-      const remotePath = `${CONFIG.webdav.remotePath}/latest.json`;
-
-      // 创建临时文件
-      const tempFile = path.join(process.cwd(), 'temp-version.json');
-      fs.writeFileSync(tempFile, JSON.stringify(versionInfo, null, 2));
-
-      // 上传到WebDAV
-      const success = await this.uploadFileToWebDAV(tempFile, remotePath);
-
-      // 清理临时文件
-      fs.unlinkSync(tempFile);
-
-      if (!success) {
-        throw new Error('版本信息上传失败');
-      }
-
-      console.log('✅ 版本信息上传成功');
-
-    } catch (error) {
-      console.error('❌ 版本信息上传失败:', error);
-      throw error;
+      execSync(
+        `${RELEASE_COMMAND} publish --version ${version} --changelog "${changelogFile}" ${extraArgs.join(' ')}`,
+        { stdio: 'inherit', env: { ...process.env } }
+      );
+    } finally {
+      fs.unlinkSync(changelogFile);
     }
   }
 
@@ -878,7 +577,7 @@ class VersionManager {
 
       // 6. 删除WebDAV文件（如果配置了）
       if (CONFIG.webdav.username !== 'your-username') {
-        await this.deleteWebDAVFiles(currentVersion);
+        this.deleteWebDAVFiles(currentVersion);
       } else {
         console.log('⚠️ 跳过WebDAV文件删除（未配置用户名密码）');
       }
@@ -992,30 +691,16 @@ class VersionManager {
     }
   }
 
-  // 删除WebDAV文件
-  async deleteWebDAVFiles(version) {
+  // 调用 Rust 发布命令撤销WebDAV上的发布
+  deleteWebDAVFiles(version) {
     try {
-      console.log(`☁️ 删除WebDAV文件: v${version}`);
-
-      const auth = Buffer.from(`${CONFIG.webdav.username}:${CONFIG.webdav.password}`).toString('base64');
-      const versionPath = `${CONFIG.webdav.remotePath}/v${version}`;
-
-      // 删除整个版本目录
-      const response = await fetch(`${CONFIG.webdav.url}${versionPath}`, {
-        method: 'DELETE',
-        headers: {
-          'Authorization': `Basic ${auth}`
-        }
+      console.log(`☁️ 撤销WebDAV上的发布: v${version}`);
+      execSync(`${RELEASE_COMMAND} rollback --version ${version}`, {
+        stdio: 'inherit',
+        env: { ...process.env }
       });
-
-      if (response.ok) {
-        console.log(`✅ 已删除WebDAV版本目录: v${version}`);
-      } else {
-        console.warn(`⚠️ 删除WebDAV目录失败: ${response.status} ${response.statusText}`);
-      }
-
     } catch (error) {
-      console.warn('⚠️ 删除WebDAV文件失败:', error.message);
+      console.warn('⚠️ 撤销WebDAV发布失败:', error.message);
     }
   }
 
@@ -1055,12 +740,7 @@ class VersionManager {
 
       if (CONFIG.webdav.username !== 'your-username') {
         console.log('☁️ 开始WebDAV上传流程...');
-
-        // 先创建必要的目录结构
-        await this.createWebDAVDirectory(`${CONFIG.webdav.remotePath}/v${newVersion}`);
-        await this.createWebDAVDirectory(`${CONFIG.webdav.remotePath}/v${newVersion}/windows`);
-
-        await this.uploadToWebDAV(newVersion, changelog);
+        this.publishRelease(newVersion, changelog);
       } else {
         console.log('⚠️ 跳过WebDAV上传（未配置用户名密码）');
         console.log('💡 请配置 .env 文件中的WebDAV设置以启用自动上传');
@@ -1092,6 +772,8 @@ if (require.main === module) {
     console.log('WEBDAV_URL:', process.env.WEBDAV_URL || '未配置');
     console.log('WEBDAV_USERNAME:', process.env.WEBDAV_USERNAME ? '已配置' : '未配置');
     console.log('WEBDAV_PASSWORD:', process.env.WEBDAV_PASSWORD ? '已配置' : '未配置');
+    console.log('WEBDAV_READ_USERNAME:', process.env.WEBDAV_READ_USERNAME ? '已配置' : '未配置（匿名读取）');
+    console.log('WEBDAV_READ_PASSWORD:', process.env.WEBDAV_READ_PASSWORD ? '已配置' : '未配置（匿名读取）');
     console.log('WEBDAV_REMOTE_PATH:', process.env.WEBDAV_REMOTE_PATH || '未配置');
     console.log('WEBDAV_BASE_URL:', process.env.WEBDAV_BASE_URL || '未配置');
     process.exit(0);
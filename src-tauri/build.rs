@@ -3,9 +3,23 @@ fn main() {
     println!("cargo:rerun-if-env-changed=WEBDAV_URL");
     println!("cargo:rerun-if-env-changed=WEBDAV_USERNAME");
     println!("cargo:rerun-if-env-changed=WEBDAV_PASSWORD");
+    println!("cargo:rerun-if-env-changed=WEBDAV_READ_USERNAME");
+    println!("cargo:rerun-if-env-changed=WEBDAV_READ_PASSWORD");
     println!("cargo:rerun-if-env-changed=WEBDAV_REMOTE_PATH");
     println!("cargo:rerun-if-env-changed=WEBDAV_BASE_URL");
 
+    // 发布账号（写权限凭据）不能出现在构建环境中，否则可能被编译进程序随安装包泄露
+    if let Some(name) = write_credential_in_env() {
+        panic!("构建环境中设置了发布账号 {}，写权限凭据不能参与构建。请只设置只读账号 WEBDAV_READ_USERNAME/WEBDAV_READ_PASSWORD（或留空匿名读取），发布账号仅在运行 workhelper-release 时从 .env 读取", name);
+    }
+
     // Tauri 构建
     tauri_build::build()
 }
+
+/// 构建环境中设置了的发布账号变量
+fn write_credential_in_env() -> Option<&'static str> {
+    ["WEBDAV_USERNAME", "WEBDAV_PASSWORD"]
+        .into_iter()
+        .find(|name| std::env::var(name).is_ok_and(|value| !value.is_empty()))
+}
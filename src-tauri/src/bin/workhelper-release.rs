@@ -0,0 +1,8 @@
+// 发布命令：上传构建产物并更新 latest.json，用法见 workhelper-release help
+
+fn main() {
+    if let Err(e) = app_lib::release::run(std::env::args().skip(1).collect()) {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,248 @@
+/*!
+ * 配置包模块
+ * 将可以在团队内共享的设置（日报周报模板、大模型接口、OA 地址）导出为不含凭据和个人账号的配置包，
+ * 导入他人分享的配置包，以及订阅团队共享的配置包地址并定期刷新，使团队使用统一的模板。
+ * 订阅只自动更新模板和模型，不修改接口地址：本机保存的 OA 密码和大模型令牌会发送到这些地址
+ */
+
+use crate::config::load_config;
+use crate::http_client;
+use crate::profiles;
+use crate::settings::{self, GeneralSettings, SettingsError};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use tokio::time::{sleep, Duration};
+
+/// 配置包格式标识
+const BUNDLE_FORMAT: &str = "workhelper-config-bundle";
+
+/// 当前配置包格式版本
+const BUNDLE_VERSION: u32 = 1;
+
+/// 配置包包含的设置项，令牌、密码、账号等个人设置不会导出，导入时也会忽略
+const BUNDLE_FIELDS: [&str; 5] = ["dailyTemplate", "weeklyTemplate", "llmBaseUrl", "llmModel", "oaUrl"];
+
+/// 订阅刷新时不修改的接口地址，修改需由用户手动导入配置包确认
+const ENDPOINT_FIELDS: [&str; 2] = ["llmBaseUrl", "oaUrl"];
+
+/// 订阅状态文件，保存在当前档案的数据目录中
+const SUBSCRIPTION_STATE_FILE: &str = "subscription_state.json";
+
+/// 启动后延迟刷新订阅的时间，避免与界面初始化争抢资源
+const STARTUP_DELAY: Duration = Duration::from_secs(15);
+
+/// 定期刷新订阅的间隔
+const REFRESH_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 订阅的配置包有更新并已应用时发送的事件，携带订阅地址
+pub const SUBSCRIPTION_APPLIED_EVENT: &str = "config-subscription-applied";
+
+/// 配置包文件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigBundle {
+    pub format: String,
+    pub version: u32,
+    /// 导出时间（Unix时间戳，秒）
+    #[serde(default)]
+    pub exported_at: u64,
+    /// 导出配置包的应用版本
+    #[serde(default)]
+    pub app_version: String,
+    pub settings: Map<String, Value>,
+}
+
+/// 订阅状态：最近一次应用的配置包，内容未变化时不重复应用，避免覆盖用户在本机的修改
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SubscriptionState {
+    source: String,
+    hash: String,
+    applied_at: u64,
+}
+
+/// 导出当前设置中可共享的部分
+pub fn export_bundle(path: &Path) -> Result<(), String> {
+    let current = serde_json::to_value(load_config().general).map_err(|e| format!("序列化设置失败: {}", e))?;
+    let settings = BUNDLE_FIELDS
+        .iter()
+        .filter_map(|field| Some((field.to_string(), current.get(*field)?.clone())))
+        .collect();
+
+    let bundle = ConfigBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        exported_at: now(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        settings,
+    };
+    let content = serde_json::to_string_pretty(&bundle).map_err(|e| format!("序列化配置包失败: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("写入配置包失败: {}", e))?;
+
+    log::info!("配置包已导出: {}", path.display());
+    Ok(())
+}
+
+/// 导入配置包文件，返回导入后的设置以及设置是否有变化
+pub fn import_bundle(path: &Path) -> Result<(GeneralSettings, bool), SettingsError> {
+    let content = fs::read_to_string(path).map_err(|e| format!("读取配置包失败: {}", e))?;
+    let bundle = parse_bundle(&content)?;
+    let result = settings::update_settings(&bundle_patch(&bundle))?;
+
+    log::info!("已导入配置包: {}", path.display());
+    Ok(result)
+}
+
+/// 刷新订阅的配置包，配置包有更新时应用并返回新的设置
+pub async fn refresh_subscription() -> Result<Option<GeneralSettings>, String> {
+    let source = load_config().general.bundle_subscription;
+    if source.is_empty() {
+        return Ok(None);
+    }
+
+    let content = fetch(&source).await?;
+    let bundle = parse_bundle(&content)?;
+    let mut patch = bundle_patch(&bundle);
+
+    let current = serde_json::to_value(load_config().general).unwrap_or_default();
+    for field in ENDPOINT_FIELDS {
+        if let Some(value) = patch.remove(field) {
+            if current.get(field) != Some(&value) {
+                log::warn!("订阅的共享配置修改了 {}，接口地址不会自动更新，如需使用请手动导入配置包", field);
+            }
+        }
+    }
+    let hash = hash_patch(&patch);
+
+    let state = load_state();
+    if state.source == source && state.hash == hash {
+        return Ok(None);
+    }
+
+    let (settings, _) = tokio::task::spawn_blocking(move || settings::update_settings(&patch))
+        .await
+        .map_err(|e| format!("应用共享配置失败: {}", e))?
+        .map_err(|e| format!("共享配置无效: {}", e))?;
+
+    save_state(&SubscriptionState {
+        source: source.clone(),
+        hash,
+        applied_at: now(),
+    });
+
+    log::info!("已应用订阅的共享配置: {}", source);
+    Ok(Some(settings))
+}
+
+/// 启动定期刷新订阅的后台任务
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        sleep(STARTUP_DELAY).await;
+        loop {
+            // 失败时已记录日志，下次定期刷新时重试
+            let _ = refresh_and_notify(&app).await;
+            sleep(REFRESH_INTERVAL).await;
+        }
+    });
+}
+
+/// 刷新订阅，配置包有更新时通知界面，返回是否应用了新的配置
+pub async fn refresh_and_notify(app: &AppHandle) -> Result<bool, String> {
+    match refresh_subscription().await {
+        Ok(Some(settings)) => {
+            if let Err(e) = app.emit(settings::SETTINGS_CHANGED_EVENT, &settings) {
+                log::warn!("发送设置修改事件失败: {}", e);
+            }
+            if let Err(e) = app.emit(SUBSCRIPTION_APPLIED_EVENT, &settings.bundle_subscription) {
+                log::warn!("发送共享配置更新事件失败: {}", e);
+            }
+            Ok(true)
+        }
+        Ok(None) => Ok(false),
+        Err(e) => {
+            log::warn!("刷新订阅的共享配置失败: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// 读取订阅的配置包，支持 http(s) 地址和本机文件路径（含共享目录）
+async fn fetch(source: &str) -> Result<String, String> {
+    if !source.starts_with("http://") && !source.starts_with("https://") {
+        return tokio::fs::read_to_string(source)
+            .await
+            .map_err(|e| format!("读取共享配置 {} 失败: {}", source, e));
+    }
+
+    let http = http_client::shared();
+    let response = http
+        .send(http.client().get(source))
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("下载共享配置失败: {}", e))?;
+    response.text().await.map_err(|e| format!("下载共享配置失败: {}", e))
+}
+
+fn parse_bundle(content: &str) -> Result<ConfigBundle, String> {
+    let bundle: ConfigBundle = serde_json::from_str(content).map_err(|e| format!("配置包格式无效: {}", e))?;
+    if bundle.format != BUNDLE_FORMAT {
+        return Err("不是 WorkHelper 配置包".to_string());
+    }
+    if bundle.version > BUNDLE_VERSION {
+        return Err(format!("配置包版本 {} 高于当前应用支持的版本 {}，请升级应用", bundle.version, BUNDLE_VERSION));
+    }
+    Ok(bundle)
+}
+
+/// 配置包中可以导入的设置项
+fn bundle_patch(bundle: &ConfigBundle) -> Map<String, Value> {
+    let mut patch = Map::new();
+    for (field, value) in &bundle.settings {
+        if BUNDLE_FIELDS.contains(&field.as_str()) {
+            patch.insert(field.clone(), value.clone());
+        } else {
+            log::warn!("忽略配置包中不可导入的设置项: {}", field);
+        }
+    }
+    patch
+}
+
+fn hash_patch(patch: &Map<String, Value>) -> String {
+    let content = serde_json::to_vec(patch).unwrap_or_default();
+    format!("{:x}", Sha256::digest(content))
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn state_path() -> Option<PathBuf> {
+    Some(profiles::profile_dir().ok()?.join(SUBSCRIPTION_STATE_FILE))
+}
+
+fn load_state() -> SubscriptionState {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &SubscriptionState) {
+    let Some(path) = state_path() else {
+        return;
+    };
+    match serde_json::to_string_pretty(state) {
+        Ok(content) => {
+            if let Err(e) = fs::write(&path, content) {
+                log::warn!("保存订阅状态失败: {}", e);
+            }
+        }
+        Err(e) => log::warn!("序列化订阅状态失败: {}", e),
+    }
+}
@@ -1,44 +1,356 @@
-/**
+/*!
  * 应用配置管理模块
- * 负责读取和管理应用的配置信息，包括WebDAV服务器配置等
+ * 负责读取和管理应用的配置信息，包括WebDAV服务器配置、更新通道、网络、设置同步、基础设置与配置档案等。
+ * 密码与令牌保存在系统钥匙串中，配置文件里只保存凭据引用
  */
 
+use crate::http_client::NetworkConfig;
+use crate::layers::{self, ConfigLayer, Layer, ValueSource};
+use crate::profiles::{self, Profile};
+use crate::secrets;
+use crate::settings::GeneralSettings;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 /// WebDAV配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebDAVConfig {
     pub url: String,
+    /// 用户名与密码都为空时匿名访问
     pub username: String,
     pub password: String,
     pub remote_path: String,
     pub base_url: String,
 }
 
+impl WebDAVConfig {
+    /// Basic 认证的 Authorization 请求头，未设置账号时返回 None
+    pub fn authorization(&self) -> Option<String> {
+        if self.username.is_empty() && self.password.is_empty() {
+            None
+        } else {
+            Some(crate::webdav::basic_authorization(&self.username, &self.password))
+        }
+    }
+}
+
+/// 更新通道，越靠后的通道接收越新的预发布版本
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    /// 仅正式版
+    #[default]
+    Stable,
+    /// 正式版及 beta、rc 等测试版
+    Beta,
+    /// 包括 alpha、nightly 在内的全部版本
+    Nightly,
+}
+
+impl UpdateChannel {
+    pub const ALL: [UpdateChannel; 3] = [UpdateChannel::Stable, UpdateChannel::Beta, UpdateChannel::Nightly];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+            UpdateChannel::Nightly => "nightly",
+        }
+    }
+
+    /// 当前通道是否接收指定通道的版本
+    pub fn accepts(&self, other: UpdateChannel) -> bool {
+        other <= *self
+    }
+}
+
+/// 更新源配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum UpdateSourceConfig {
+    /// 使用 webdav 配置中的WebDAV服务器
+    #[default]
+    Webdav,
+    /// 静态HTTPS目录，目录下需提供 latest.json
+    Static { url: String },
+    /// GitHub Releases
+    Github {
+        #[serde(default = "default_github_api_url")]
+        api_url: String,
+        owner: String,
+        repo: String,
+        #[serde(default)]
+        token: Option<String>,
+    },
+    /// Gitea Releases
+    Gitea {
+        url: String,
+        owner: String,
+        repo: String,
+        #[serde(default)]
+        token: Option<String>,
+    },
+    /// 本地目录或SMB共享路径，目录下需提供 latest.json
+    Local { path: String },
+}
+
+fn default_github_api_url() -> String {
+    "https://api.github.com".to_string()
+}
+
+/// 更新配置结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateConfig {
+    #[serde(default)]
+    pub channel: UpdateChannel,
+    /// 更新源，默认为WebDAV服务器
+    #[serde(default)]
+    pub source: UpdateSourceConfig,
+    /// 保留最近安装的几个版本的安装包，用于回滚
+    #[serde(default = "default_retain_installers")]
+    pub retain_installers: usize,
+    /// 启动时在后台检查更新
+    #[serde(default = "default_true")]
+    pub check_on_startup: bool,
+    /// 后台定时检查更新的间隔（小时），0 表示不定时检查
+    #[serde(default = "default_check_interval_hours")]
+    pub check_interval_hours: u64,
+    /// 发现新版本后在后台预先下载
+    #[serde(default)]
+    pub background_download: bool,
+    /// 后台下载限速（KB/s），0 表示不限速
+    #[serde(default = "default_background_download_rate_kb")]
+    pub background_download_rate_kb: u64,
+    /// 用户选择跳过的版本，后台检查不再提示该版本
+    #[serde(default)]
+    pub skipped_version: Option<String>,
+    /// 稍后提醒：在该时间（Unix时间戳，秒）之前后台检查不再提示
+    #[serde(default)]
+    pub remind_after: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_retain_installers() -> usize {
+    3
+}
+
+fn default_check_interval_hours() -> u64 {
+    6
+}
+
+fn default_background_download_rate_kb() -> u64 {
+    512
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::default(),
+            source: UpdateSourceConfig::default(),
+            retain_installers: default_retain_installers(),
+            check_on_startup: true,
+            check_interval_hours: default_check_interval_hours(),
+            background_download: false,
+            background_download_rate_kb: default_background_download_rate_kb(),
+            skipped_version: None,
+            remind_after: None,
+        }
+    }
+}
+
+/// 设置同步配置，同步到用户自己的WebDAV目录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConfig {
+    /// 同步目录的完整WebDAV地址，为空表示未启用同步
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    /// 本机名称，用于提示同步数据来自哪台设备
+    #[serde(default)]
+    pub device_name: String,
+}
+
 /// 应用配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
+    /// 配置文件结构版本，见 [`CURRENT_SCHEMA_VERSION`]
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
     pub webdav: WebDAVConfig,
+    #[serde(default)]
+    pub update: UpdateConfig,
+    /// 网络设置（超时、重试、代理与CA证书）
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub sync: SyncConfig,
+    /// 基础设置（令牌、账号与模板）
+    #[serde(default)]
+    pub general: GeneralSettings,
+    /// 当前使用的配置档案，sync 与 general 即该档案的设置
+    #[serde(default = "default_profile_name")]
+    pub profile: String,
+    /// 其他配置档案
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
 }
 
-impl Default for WebDAVConfig {
+impl Default for AppConfig {
     fn default() -> Self {
         Self {
-            url: "https://your-server.com/webdav".to_string(),
-            username: "your-username".to_string(),
-            password: "your-password".to_string(),
-            remote_path: "/releases".to_string(),
-            base_url: "https://your-server.com/releases".to_string(),
+            schema_version: CURRENT_SCHEMA_VERSION,
+            webdav: WebDAVConfig::default(),
+            update: UpdateConfig::default(),
+            network: NetworkConfig::default(),
+            sync: SyncConfig::default(),
+            general: GeneralSettings::default(),
+            profile: default_profile_name(),
+            profiles: BTreeMap::new(),
         }
     }
 }
 
-impl Default for AppConfig {
+fn default_profile_name() -> String {
+    profiles::DEFAULT_PROFILE.to_string()
+}
+
+fn default_schema_version() -> u32 {
+    CURRENT_SCHEMA_VERSION
+}
+
+/// 配置中档案以外的敏感字段（JSON Pointer）及对应的凭据名称，档案中的敏感字段见 [`profiles::SECRET_FIELDS`]
+const SECRET_FIELDS: [(&str, &str); 3] = [
+    ("/webdav/password", "webdav.password"),
+    ("/update/source/token", "update.token"),
+    ("/network/proxy/password", "network.proxy_password"),
+];
+
+/// 列出配置中所有敏感字段及对应的凭据名称
+fn secret_fields(config: &Value) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = SECRET_FIELDS
+        .iter()
+        .chain(profiles::SECRET_FIELDS.iter())
+        .map(|(pointer, name)| (pointer.to_string(), name.to_string()))
+        .collect();
+
+    if let Some(stored) = config.get("profiles").and_then(Value::as_object) {
+        for profile in stored.keys() {
+            for (pointer, name) in profiles::SECRET_FIELDS {
+                fields.push((
+                    format!("/profiles/{}{}", profile, pointer),
+                    profiles::secret_name(profile, name),
+                ));
+            }
+        }
+    }
+    fields
+}
+
+/// 默认配置中的占位密码，不作为凭据保存
+const PLACEHOLDER_PASSWORD: &str = "your-password";
+
+/// 配置文件中是否还有未迁移的明文凭据
+fn has_plaintext_secrets(config: &Value) -> bool {
+    secret_fields(config).iter().any(|(pointer, _)| {
+        config
+            .pointer(pointer)
+            .and_then(Value::as_str)
+            .is_some_and(|value| !value.is_empty() && value != PLACEHOLDER_PASSWORD && secrets::parse_reference(value).is_none())
+    })
+}
+
+/// 将敏感字段保存到钥匙串，并替换为凭据引用
+///
+/// 仍是凭据引用的字段（包括读取失败而保留引用的字段）不会写入或删除钥匙串；
+/// 只有用户清空字段时才删除对应的凭据
+fn seal_secrets(config: &mut Value) -> Result<(), String> {
+    let fields = secret_fields(config);
+    // 其他字段仍在引用的凭据（如切换档案时未能读取、原样移动的引用）不能被覆盖或删除
+    let referenced: BTreeSet<String> = fields
+        .iter()
+        .filter_map(|(pointer, _)| config.pointer(pointer).and_then(Value::as_str))
+        .filter_map(secrets::parse_reference)
+        .map(str::to_string)
+        .collect();
+
+    for (pointer, name) in fields {
+        let Some(Value::String(value)) = config.pointer_mut(&pointer) else {
+            continue;
+        };
+        if value == PLACEHOLDER_PASSWORD || secrets::parse_reference(value).is_some() {
+            continue;
+        }
+        if referenced.contains(&name) {
+            if value.is_empty() {
+                continue;
+            }
+            return Err(format!("凭据 {} 暂时无法读取，为避免覆盖已保存的凭据，已取消保存", name));
+        }
+        secrets::set_secret(&name, value)?;
+        if !value.is_empty() {
+            *value = secrets::reference(&name);
+        }
+    }
+    Ok(())
+}
+
+/// 最近一次加载配置时无法读取的凭据名称
+fn unresolved_slot() -> &'static Mutex<BTreeSet<String>> {
+    static UNRESOLVED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+    &UNRESOLVED
+}
+
+/// 凭据在最近一次加载配置时是否无法读取，无法读取的凭据仍以引用保留在配置中
+pub fn is_unresolved_secret(name: &str) -> bool {
+    unresolved_slot().lock().unwrap().contains(name)
+}
+
+/// 将凭据引用替换为钥匙串中保存的值
+///
+/// 钥匙串读取失败或凭据不存在时保留引用，保存配置时原样写回，不会因一次读取失败而删除凭据
+fn resolve_secrets(config: &mut Value) {
+    let mut unresolved = BTreeSet::new();
+    for (pointer, _) in secret_fields(config) {
+        let Some(Value::String(value)) = config.pointer_mut(&pointer) else {
+            continue;
+        };
+        let Some(name) = secrets::parse_reference(value) else {
+            continue;
+        };
+        match secrets::get_secret(name) {
+            Ok(Some(secret)) => *value = secret,
+            Ok(None) => {
+                log::warn!("凭据 {} 不存在，请重新填写", name);
+                unresolved.insert(name.to_string());
+            }
+            Err(e) => {
+                log::warn!("{}", e);
+                unresolved.insert(name.to_string());
+            }
+        }
+    }
+    *unresolved_slot().lock().unwrap() = unresolved;
+}
+
+impl Default for WebDAVConfig {
     fn default() -> Self {
         Self {
-            webdav: WebDAVConfig::default(),
+            url: "https://your-server.com/webdav".to_string(),
+            username: "your-username".to_string(),
+            password: "your-password".to_string(),
+            remote_path: "/releases".to_string(),
+            base_url: "https://your-server.com/releases".to_string(),
         }
     }
 }
@@ -59,119 +371,822 @@ fn get_config_path() -> Result<PathBuf, String> {
     Ok(config_dir.join("config.json"))
 }
 
+// ==================== 结构版本与迁移 ====================
+
+/// 当前配置文件结构版本
+///
+/// 修改配置结构且旧文件需要转换时加 1，并在 [`MIGRATIONS`] 末尾添加对应的迁移函数
+pub const CURRENT_SCHEMA_VERSION: u32 = 3;
+
+/// 迁移函数，将配置从上一版本升级到下一版本
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// 按顺序排列的迁移函数，`MIGRATIONS[i]` 将版本 `i + 1` 升级到版本 `i + 2`
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2, migrate_v2_to_v3];
+
+const _: () = assert!(MIGRATIONS.len() as u32 + 1 == CURRENT_SCHEMA_VERSION);
+
+/// 版本 1：没有 schema_version 字段，早期文件只有 webdav 段，补全更新与同步设置
+fn migrate_v1_to_v2(config: &mut Value) -> Result<(), String> {
+    let object = config.as_object_mut().ok_or("配置文件不是JSON对象")?;
+    if !object.contains_key("update") {
+        let update = serde_json::to_value(UpdateConfig::default()).map_err(|e| e.to_string())?;
+        object.insert("update".to_string(), update);
+    }
+    if !object.contains_key("sync") {
+        let sync = serde_json::to_value(SyncConfig::default()).map_err(|e| e.to_string())?;
+        object.insert("sync".to_string(), sync);
+    }
+    Ok(())
+}
+
+/// 版本 2：保存时写入了完整配置。配置分层后用户文件只保存修改过的字段，
+/// 删除与内置默认值（含构建时嵌入的值）相同的字段，使这些字段跟随系统配置文件
+fn migrate_v2_to_v3(config: &mut Value) -> Result<(), String> {
+    let defaults = default_layer().value;
+    let new = config.clone();
+    *config = layers::user_overrides(&new, &Value::Object(Default::default()), &defaults, &Value::Null);
+    Ok(())
+}
+
+/// 依次执行迁移，返回迁移前的版本
+fn migrate(config: &mut Value) -> Result<u32, ConfigLoadError> {
+    let version = config
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map_or(1, |version| version as u32);
+
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(ConfigLoadError::TooNew {
+            version,
+            supported: CURRENT_SCHEMA_VERSION,
+        });
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version.max(1) as usize - 1) {
+        let from = index as u32 + 1;
+        migration(config).map_err(|message| ConfigLoadError::Migration { from, message })?;
+        config["schema_version"] = Value::from(from + 1);
+    }
+    Ok(version)
+}
+
+// ==================== 加载与保存 ====================
+
+/// 配置文件加载错误
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ConfigLoadError {
+    /// 无法读取配置文件
+    Unreadable { message: String },
+    /// 配置文件内容无效
+    Invalid { message: String },
+    /// 配置文件来自更新版本的应用
+    TooNew { version: u32, supported: u32 },
+    /// 迁移旧版本配置失败
+    Migration { from: u32, message: String },
+}
+
+impl std::fmt::Display for ConfigLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigLoadError::Unreadable { message } => write!(f, "读取配置文件失败: {}", message),
+            ConfigLoadError::Invalid { message } => write!(f, "解析配置文件失败: {}", message),
+            ConfigLoadError::TooNew { version, supported } => write!(
+                f,
+                "配置文件版本 {} 高于当前应用支持的版本 {}，请升级应用",
+                version, supported
+            ),
+            ConfigLoadError::Migration { from, message } => {
+                write!(f, "从版本 {} 迁移配置文件失败: {}", from, message)
+            }
+        }
+    }
+}
+
+impl From<ConfigLoadError> for String {
+    fn from(error: ConfigLoadError) -> Self {
+        error.to_string()
+    }
+}
+
+/// 最近一次加载配置的错误，存在时拒绝覆盖配置文件
+fn load_error_slot() -> &'static Mutex<Option<ConfigLoadError>> {
+    static LOAD_ERROR: Mutex<Option<ConfigLoadError>> = Mutex::new(None);
+    &LOAD_ERROR
+}
+
+/// 最近一次加载配置的错误
+pub fn load_error() -> Option<ConfigLoadError> {
+    load_error_slot().lock().unwrap().clone()
+}
+
+/// 最近一次成功加载或保存的配置，即当前生效的配置；配置文件被改坏时继续使用
+fn last_good_slot() -> &'static Mutex<Option<AppConfig>> {
+    static LAST_GOOD: Mutex<Option<AppConfig>> = Mutex::new(None);
+    &LAST_GOOD
+}
+
 /// 加载应用配置
+///
+/// 返回缓存的生效配置，只在首次使用、保存配置和配置文件被修改后重新读取各层配置。
+/// 配置文件无法使用时继续使用上次成功加载的配置（启动时为默认配置），并记录错误供界面提示；
+/// 在错误解决前不会覆盖配置文件
 pub fn load_config() -> AppConfig {
-    match get_config_path() {
-        Ok(config_path) => {
-            if config_path.exists() {
-                match fs::read_to_string(&config_path) {
-                    Ok(content) => {
-                        match serde_json::from_str::<AppConfig>(&content) {
-                            Ok(config) => {
-                                log::info!("配置文件加载成功: {}", config_path.display());
-                                return config;
-                            }
-                            Err(e) => {
-                                log::warn!("解析配置文件失败: {} - 使用默认配置", e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        log::warn!("读取配置文件失败: {} - 使用默认配置", e);
-                    }
-                }
-            } else {
-                log::info!("配置文件不存在，将创建默认配置: {}", config_path.display());
-                
-                // 创建默认配置文件
-                let default_config = AppConfig::default();
-                if let Err(e) = save_config(&default_config) {
-                    log::warn!("保存默认配置失败: {}", e);
-                }
-                
-                return default_config;
+    if let Some(config) = last_good_slot().lock().unwrap().clone() {
+        return config;
+    }
+    refresh_config()
+}
+
+/// 重新读取各层配置并更新缓存
+fn refresh_config() -> AppConfig {
+    let result = try_load_config();
+    let mut last_good = last_good_slot().lock().unwrap();
+    let mut load_error = load_error_slot().lock().unwrap();
+    keep_last_good(result, &mut last_good, &mut load_error)
+}
+
+/// 记录加载结果，返回生效的配置：成功时替换生效的配置并清除错误，
+/// 失败时记录错误并继续使用上次成功加载的配置（没有时为默认配置）
+fn keep_last_good(
+    result: Result<AppConfig, ConfigLoadError>,
+    last_good: &mut Option<AppConfig>,
+    load_error: &mut Option<ConfigLoadError>,
+) -> AppConfig {
+    match result {
+        Ok(config) => {
+            *load_error = None;
+            *last_good = Some(config.clone());
+            config
+        }
+        Err(e) => {
+            log::error!("{} - 暂时使用上次成功加载的配置，配置文件不会被覆盖", e);
+            *load_error = Some(e);
+            last_good.clone().unwrap_or_default()
+        }
+    }
+}
+
+/// 配置文件被修改后重新加载，生效的配置有变化时返回新的配置
+///
+/// 修改无效（包括在用户配置文件中填写了无效的基础设置）时返回错误，继续使用修改前的配置。
+/// 系统配置文件、环境变量和命令行参数中的无效设置只记录警告，用户无法在界面上修改这些值
+pub fn reload_config() -> Result<Option<AppConfig>, ConfigLoadError> {
+    let previous = last_good_slot().lock().unwrap().clone();
+    let had_error = load_error().is_some();
+
+    let reloaded = load_merged().and_then(|(config, ignored)| {
+        let known = ignored_slot().lock().unwrap().clone();
+        let messages: Vec<String> = ignored
+            .iter()
+            .filter(|field| !known.iter().any(|(pointer, value)| *pointer == field.pointer && *value == field.value))
+            .map(|field| format!("{}: {}", field.pointer.trim_start_matches('/').replace('/', "."), field.message))
+            .collect();
+        if !messages.is_empty() {
+            return Err(ConfigLoadError::Invalid {
+                message: messages.join("；"),
+            });
+        }
+
+        *ignored_slot().lock().unwrap() = ignored.into_iter().map(|field| (field.pointer, field.value)).collect();
+        Ok(config)
+    });
+    let config = reloaded.inspect_err(|e| {
+        *load_error_slot().lock().unwrap() = Some(e.clone());
+    })?;
+
+    let changed = had_error
+        || previous.map(|previous| serde_json::to_value(previous).ok()) != Some(serde_json::to_value(&config).ok());
+    *load_error_slot().lock().unwrap() = None;
+    *last_good_slot().lock().unwrap() = Some(config.clone());
+    Ok(changed.then_some(config))
+}
+
+/// 加载应用配置，逐字段合并各层配置
+///
+/// 基础设置中校验不通过的字段回退到更低优先级层的值并记录警告，不影响其他设置
+pub fn try_load_config() -> Result<AppConfig, ConfigLoadError> {
+    let (config, ignored) = load_merged()?;
+    *ignored_slot().lock().unwrap() = ignored.into_iter().map(|field| (field.pointer, field.value)).collect();
+    Ok(config)
+}
+
+/// 用户配置文件中校验不通过、加载时被忽略的基础设置字段
+struct IgnoredField {
+    pointer: String,
+    value: Value,
+    message: String,
+}
+
+/// 最近一次加载配置时忽略的用户配置文件字段及其值，重新加载时这些字段不算新的无效修改
+fn ignored_slot() -> &'static Mutex<Vec<(String, Value)>> {
+    static IGNORED: Mutex<Vec<(String, Value)>> = Mutex::new(Vec::new());
+    &IGNORED
+}
+
+/// 合并各层配置，基础设置中无效的字段从提供该值的层中移除后重新合并，
+/// 返回生效的配置及被忽略的用户配置文件字段
+fn load_merged() -> Result<(AppConfig, Vec<IgnoredField>), ConfigLoadError> {
+    let mut layers = load_layers()?;
+    let mut ignored = Vec::new();
+
+    loop {
+        let (mut merged, sources) = layers::merge(&layers);
+        resolve_secrets(&mut merged);
+        let mut config: AppConfig = serde_json::from_value(merged).map_err(|e| ConfigLoadError::Invalid {
+            message: e.to_string(),
+        })?;
+        drop_invalid_profiles(&mut config);
+
+        let Err(fields) = config.general.validate() else {
+            return Ok((config, ignored));
+        };
+
+        let mut removed = false;
+        for field in fields {
+            let pointer = format!("/general/{}", field.field);
+            let Some(source) = sources.get(&pointer) else {
+                continue;
+            };
+            // 内置默认值没有更低的层可以回退
+            let Some(layer) = layers
+                .iter_mut()
+                .find(|layer| layer.layer == source.layer && layer.layer != ConfigLayer::Default)
+            else {
+                continue;
+            };
+
+            log::warn!("忽略 {} 中无效的设置 general.{}: {}", source.origin, field.field, field.message);
+            let value = layers::remove_pointer(&mut layer.value, &pointer).unwrap_or_default();
+            if layer.layer == ConfigLayer::User {
+                ignored.push(IgnoredField {
+                    pointer,
+                    value,
+                    message: field.message,
+                });
             }
+            removed = true;
+        }
+        if !removed {
+            return Ok((config, ignored));
         }
+    }
+}
+
+/// 档案名称用于数据目录名，忽略无效的名称（如 ../..），无效的当前档案改用默认档案
+fn drop_invalid_profiles(config: &mut AppConfig) {
+    if let Err(e) = profiles::validate_name(&config.profile) {
+        log::warn!("忽略无效的当前档案名称 {:?}: {}", config.profile, e);
+        config.profile = default_profile_name();
+    }
+    config.profiles.retain(|name, _| match profiles::validate_name(name) {
+        Ok(()) => true,
         Err(e) => {
-            log::error!("获取配置路径失败: {} - 使用默认配置", e);
+            log::warn!("忽略无效的档案名称 {:?}: {}", name, e);
+            false
+        }
+    });
+}
+
+/// 需要监听修改的配置文件：用户配置文件和系统配置文件
+pub fn watched_paths() -> Vec<PathBuf> {
+    get_config_path().ok().into_iter().chain(layers::system_config_path()).collect()
+}
+
+/// 配置字段及其生效来源
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigEntry {
+    /// 字段路径，如 general.oaUrl
+    pub path: String,
+    /// 生效的值，凭据显示为掩码
+    pub value: Value,
+    pub source: ValueSource,
+}
+
+/// 列出所有配置字段的生效值与来源
+pub fn describe_config() -> Result<Vec<ConfigEntry>, ConfigLoadError> {
+    let (merged, sources) = layers::merge(&load_layers()?);
+    let secret_fields = secret_fields(&merged);
+
+    let entries = layers::leaves(&merged)
+        .into_iter()
+        .filter(|(pointer, _)| pointer != "/schema_version")
+        .filter_map(|(pointer, value)| {
+            let source = sources.get(&pointer)?.clone();
+            let is_secret = secret_fields.iter().any(|(secret, _)| *secret == pointer);
+            let value = match value {
+                Value::String(text) if is_secret && !text.is_empty() => Value::String("******".to_string()),
+                _ => value.clone(),
+            };
+            Some(ConfigEntry {
+                path: pointer.trim_start_matches('/').replace('/', "."),
+                value,
+                source,
+            })
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// 按优先级从低到高加载各层配置
+fn load_layers() -> Result<Vec<Layer>, ConfigLoadError> {
+    let defaults = default_layer();
+    let schema = defaults.value.clone();
+
+    let mut layers = vec![defaults];
+    if let Some(system) = load_system_layer() {
+        layers.push(system);
+    }
+    layers.push(load_user_layer()?);
+    layers.push(layers::environment_layer(&schema));
+    layers.push(layers::command_line_layer(&schema, std::env::args().skip(1)));
+    Ok(layers)
+}
+
+/// 新建配置档案的初始设置：内置默认值及系统配置文件中的公司默认设置
+pub fn base_profile() -> Profile {
+    let mut layers = vec![default_layer()];
+    layers.extend(load_system_layer());
+    let (merged, _) = layers::merge(&layers);
+
+    match serde_json::from_value::<AppConfig>(merged) {
+        Ok(config) => Profile {
+            general: config.general,
+            sync: config.sync,
+        },
+        Err(e) => {
+            log::warn!("读取默认设置失败: {}", e);
+            Profile::default()
         }
     }
-    
-    AppConfig::default()
 }
 
-/// 保存应用配置
+/// 当前档案保存的设置：只合并内置默认值、系统配置文件和用户配置文件，
+/// 不含环境变量和命令行参数的临时覆盖，用于把当前设置存为档案
+pub fn saved_profile() -> Result<Profile, String> {
+    let layers: Vec<Layer> = load_layers()?
+        .into_iter()
+        .filter(|layer| layer.layer <= ConfigLayer::User)
+        .collect();
+    let (mut merged, _) = layers::merge(&layers);
+    resolve_secrets(&mut merged);
+
+    let config: AppConfig = serde_json::from_value(merged).map_err(|e| format!("读取当前档案设置失败: {}", e))?;
+    Ok(Profile {
+        general: config.general,
+        sync: config.sync,
+    })
+}
+
+/// 内置默认值，包含构建时嵌入的更新服务器地址和只读账号
+fn default_layer() -> Layer {
+    let defaults = serde_json::to_value(AppConfig::default()).unwrap_or_default();
+    let mut layer = Layer::new(ConfigLayer::Default, defaults.clone(), "内置默认值");
+
+    if let Some(url) = option_env!("WEBDAV_URL") {
+        let embedded = [
+            ("/webdav/url", Some(url)),
+            ("/webdav/username", Some(option_env!("WEBDAV_READ_USERNAME").unwrap_or_default())),
+            ("/webdav/password", Some(option_env!("WEBDAV_READ_PASSWORD").unwrap_or_default())),
+            ("/webdav/remote_path", option_env!("WEBDAV_REMOTE_PATH")),
+            ("/webdav/base_url", option_env!("WEBDAV_BASE_URL")),
+        ];
+        for (pointer, value) in embedded {
+            if let Some(value) = value {
+                layer.set_text(&defaults, pointer, value, "构建时嵌入");
+            }
+        }
+    }
+    layer
+}
+
+/// 读取系统配置文件，不存在或无效时忽略
+fn load_system_layer() -> Option<Layer> {
+    let path = layers::system_config_path()?;
+    let content = fs::read_to_string(&path).ok()?;
+
+    let mut value: Value = match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(e) => {
+            log::warn!("忽略无效的系统配置文件 {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    if let Err(e) = migrate(&mut value) {
+        log::warn!("忽略无法使用的系统配置文件 {}: {}", path.display(), e);
+        return None;
+    }
+
+    Some(Layer::new(ConfigLayer::System, value, path.display().to_string()))
+}
+
+/// 读取用户配置文件，必要时迁移旧版本文件、将明文凭据移入钥匙串
+fn load_user_layer() -> Result<Layer, ConfigLoadError> {
+    let config_path = get_config_path().map_err(|message| ConfigLoadError::Unreadable { message })?;
+    load_user_file(&config_path)
+}
+
+/// 读取指定的用户配置文件，旧版本文件迁移前备份为 config.json.v<版本>.bak
+fn load_user_file(config_path: &Path) -> Result<Layer, ConfigLoadError> {
+    let origin = config_path.display().to_string();
+
+    if !config_path.exists() {
+        log::info!("配置文件不存在，将创建默认配置: {}", config_path.display());
+
+        // 新的配置文件只记录结构版本，其余字段跟随系统配置和默认值
+        let value = empty_user_config();
+        if let Err(e) = write_config(config_path, value.clone()) {
+            log::warn!("保存默认配置失败: {}", e);
+        }
+        return Ok(Layer::new(ConfigLayer::User, value, origin));
+    }
+
+    let content = fs::read_to_string(config_path).map_err(|e| ConfigLoadError::Unreadable {
+        message: e.to_string(),
+    })?;
+    let mut value: Value = serde_json::from_str(&content).map_err(|e| ConfigLoadError::Invalid {
+        message: e.to_string(),
+    })?;
+
+    let version = migrate(&mut value)?;
+    if version < CURRENT_SCHEMA_VERSION {
+        // 保留迁移前的文件，迁移出错时可以手动恢复
+        let backup = backup_path(config_path, &format!("v{}.bak", version));
+        fs::copy(config_path, &backup).map_err(|e| ConfigLoadError::Migration {
+            from: version,
+            message: format!("备份配置文件失败: {}", e),
+        })?;
+        value = write_config(config_path, value).map_err(|message| ConfigLoadError::Migration { from: version, message })?;
+        log::info!(
+            "配置文件已从版本 {} 迁移到版本 {}，原文件备份为 {}",
+            version,
+            CURRENT_SCHEMA_VERSION,
+            backup.display()
+        );
+    } else if has_plaintext_secrets(&value) {
+        match write_config(config_path, value.clone()) {
+            Ok(sealed) => {
+                value = sealed;
+                log::info!("已将配置文件中的明文凭据迁移到系统钥匙串");
+            }
+            Err(e) => log::warn!("迁移明文凭据失败: {}", e),
+        }
+    }
+
+    log::info!("配置文件加载成功: {}", config_path.display());
+    Ok(Layer::new(ConfigLayer::User, value, origin))
+}
+
+fn empty_user_config() -> Value {
+    serde_json::json!({ "schema_version": CURRENT_SCHEMA_VERSION })
+}
+
+/// 保存应用配置，只把与系统配置、默认值不同的字段写入用户配置文件，
+/// 敏感字段保存到钥匙串，配置文件中只写入引用
+///
+/// 现有配置文件无法加载时拒绝保存，避免覆盖用户的配置
 pub fn save_config(config: &AppConfig) -> Result<(), String> {
+    if let Some(e) = load_error() {
+        return Err(format!("{}，为避免覆盖原有配置，已取消保存", e));
+    }
+
+    let layers = load_layers()?;
+    let user_index = layers
+        .iter()
+        .position(|layer| layer.layer == ConfigLayer::User)
+        .ok_or("缺少用户配置层")?;
+    let (below, _) = layers::merge(&layers[..user_index]);
+    let (above, _) = layers::merge(&layers[user_index + 1..]);
+
+    let new = serde_json::to_value(config).map_err(|e| format!("序列化配置失败: {}", e))?;
+    let user = layers::user_overrides(&new, &layers[user_index].value, &below, &above);
+
     let config_path = get_config_path()?;
-    
-    let content = serde_json::to_string_pretty(config)
-        .map_err(|e| format!("序列化配置失败: {}", e))?;
-    
-    fs::write(&config_path, content)
-        .map_err(|e| format!("写入配置文件失败: {}", e))?;
-    
-    log::info!("配置文件保存成功: {}", config_path.display());
+    write_config(&config_path, user)?;
+
+    // 重新读取保存后的配置；文件监听随后重新加载时配置没有变化，不会把自己的修改当作外部修改
+    refresh_config();
     Ok(())
 }
 
-/// 从环境变量加载WebDAV配置（用于CI/CD或生产环境）
-pub fn load_webdav_from_env() -> Option<WebDAVConfig> {
-    use std::env;
+/// 放弃无法加载的配置文件并恢复默认配置，原文件重命名保留
+pub fn reset_config() -> Result<(), String> {
+    let config_path = get_config_path()?;
+    if config_path.exists() {
+        let backup = backup_path(&config_path, &format!("broken-{}.bak", unix_timestamp()));
+        fs::rename(&config_path, &backup).map_err(|e| format!("备份配置文件失败: {}", e))?;
+        log::warn!("已将无法加载的配置文件移动到 {}", backup.display());
+    }
 
-    // 在开发模式下尝试加载 .env 文件
-    #[cfg(debug_assertions)]
-    {
-        if let Err(e) = dotenv::dotenv() {
-            log::debug!("无法加载 .env 文件: {}", e);
-        } else {
-            log::info!("成功加载 .env 文件");
-        }
+    write_config(&config_path, empty_user_config())?;
+    *load_error_slot().lock().unwrap() = None;
+    refresh_config();
+    Ok(())
+}
+
+/// 写入用户配置文件，写入前将原文件备份为 config.json.bak，返回实际写入的内容
+fn write_config(config_path: &Path, mut config: Value) -> Result<Value, String> {
+    config["schema_version"] = Value::from(CURRENT_SCHEMA_VERSION);
+    seal_secrets(&mut config)?;
+
+    let content = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("序列化配置失败: {}", e))?;
+
+    if config_path.exists() {
+        fs::copy(config_path, backup_path(config_path, "bak"))
+            .map_err(|e| format!("备份配置文件失败: {}", e))?;
     }
 
-    // 优先使用构建时的环境变量（通过 env! 宏在编译时获取）
-    let url = option_env!("WEBDAV_URL")
-        .map(|s| s.to_string())
-        .or_else(|| env::var("WEBDAV_URL").ok())?;
+    // 先写入临时文件再替换，避免写入中断留下不完整的配置
+    let temp_path = backup_path(config_path, "tmp");
+    fs::write(&temp_path, content)
+        .map_err(|e| format!("写入配置文件失败: {}", e))?;
+    fs::rename(&temp_path, config_path)
+        .map_err(|e| format!("写入配置文件失败: {}", e))?;
 
-    let username = option_env!("WEBDAV_USERNAME")
-        .map(|s| s.to_string())
-        .or_else(|| env::var("WEBDAV_USERNAME").ok())?;
+    log::info!("配置文件保存成功: {}", config_path.display());
+    Ok(config)
+}
 
-    let password = option_env!("WEBDAV_PASSWORD")
-        .map(|s| s.to_string())
-        .or_else(|| env::var("WEBDAV_PASSWORD").ok())?;
+/// 配置文件旁的备份路径，如 config.json.bak
+fn backup_path(config_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = config_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    config_path.with_file_name(name)
+}
 
-    let remote_path = option_env!("WEBDAV_REMOTE_PATH")
-        .map(|s| s.to_string())
-        .or_else(|| env::var("WEBDAV_REMOTE_PATH").ok())
-        .unwrap_or_else(|| "/releases".to_string());
+fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
 
-    let base_url = option_env!("WEBDAV_BASE_URL")
-        .map(|s| s.to_string())
-        .or_else(|| env::var("WEBDAV_BASE_URL").ok())?;
+/// 构建时是否设置了发布账号（写权限凭据）
+///
+/// 只在常量求值时判断是否存在，凭据内容不会进入程序；正常情况下 build.rs 已拒绝这样的构建
+const fn has_write_credentials(username: Option<&str>, password: Option<&str>) -> bool {
+    matches!(username, Some(value) if !value.is_empty()) || matches!(password, Some(value) if !value.is_empty())
+}
+
+const EMBEDS_WRITE_CREDENTIALS: bool =
+    has_write_credentials(option_env!("WEBDAV_USERNAME"), option_env!("WEBDAV_PASSWORD"));
 
-    log::info!("从环境变量加载WebDAV配置: url={}, username={}, remote_path={}, base_url={}",
-               url, username, remote_path, base_url);
+/// 程序构建时是否可能嵌入了写权限凭据，启动时据此拒绝运行
+pub fn embeds_write_credentials() -> bool {
+    EMBEDS_WRITE_CREDENTIALS
+}
+
+/// 从运行环境（或 .env 文件）加载发布用的WebDAV配置，包含写权限账号
+///
+/// 只读取运行时的环境变量，写权限凭据不会被编译进程序
+pub fn load_publish_webdav_from_env() -> Option<WebDAVConfig> {
+    use std::env;
+
+    let _ = dotenv::dotenv();
 
     Some(WebDAVConfig {
-        url,
-        username,
-        password,
-        remote_path,
-        base_url,
+        url: env::var("WEBDAV_URL").ok()?,
+        username: env::var("WEBDAV_USERNAME").ok()?,
+        password: env::var("WEBDAV_PASSWORD").ok()?,
+        remote_path: env::var("WEBDAV_REMOTE_PATH").unwrap_or_else(|_| "/releases".to_string()),
+        base_url: env::var("WEBDAV_BASE_URL").ok()?,
     })
 }
 
-/// 获取有效的WebDAV配置（优先使用环境变量）
+/// 获取有效的WebDAV配置
+///
+/// 各字段按优先级从命令行参数、环境变量（兼容 WEBDAV_URL 等旧变量）、用户配置文件、
+/// 系统配置文件和构建时嵌入的值中逐个选取。构建时只会嵌入服务器地址和只读账号
+/// `WEBDAV_READ_USERNAME`、`WEBDAV_READ_PASSWORD`，更新目录公开可读时可以不设置账号；
+/// 具有写权限的 `WEBDAV_USERNAME`、`WEBDAV_PASSWORD` 只在发布时读取，见 [`load_publish_webdav_from_env`]
 pub fn get_webdav_config() -> WebDAVConfig {
-    // 优先从环境变量加载
-    if let Some(env_config) = load_webdav_from_env() {
-        log::info!("使用环境变量中的WebDAV配置");
-        return env_config;
+    load_config().webdav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn write_credentials_are_detected() {
+        assert!(!has_write_credentials(None, None));
+        assert!(!has_write_credentials(Some(""), Some("")));
+        assert!(has_write_credentials(Some("publisher"), None));
+        assert!(has_write_credentials(None, Some("secret")));
+        // 测试构建不设置发布账号
+        assert!(!embeds_write_credentials());
+    }
+
+    #[test]
+    fn invalid_profile_names_are_dropped_at_load() {
+        let mut config = AppConfig {
+            profile: "../..".to_string(),
+            ..AppConfig::default()
+        };
+        config.profiles.insert("work".to_string(), Profile::default());
+        config.profiles.insert("../../..".to_string(), Profile::default());
+        config.profiles.insert("a/b".to_string(), Profile::default());
+
+        drop_invalid_profiles(&mut config);
+
+        assert_eq!(config.profile, profiles::DEFAULT_PROFILE);
+        assert_eq!(config.profiles.keys().collect::<Vec<_>>(), ["work"]);
+    }
+
+    /// 测试用的配置目录，结束时删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("workhelper-{}-{}", name, uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        /// 写入 config.json，返回其路径
+        fn config(&self, content: &str) -> PathBuf {
+            let path = self.0.join("config.json");
+            fs::write(&path, content).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn read_json(path: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// 版本 1：没有 schema_version，只有 webdav 段
+    const V1_FIXTURE: &str = r#"{
+        "webdav": {
+            "url": "https://dav.example.com",
+            "username": "your-username",
+            "password": "your-password",
+            "remote_path": "/workHelper",
+            "base_url": "https://dav.example.com/workHelper"
+        }
+    }"#;
+
+    #[test]
+    fn migrates_v1_config_and_keeps_backup() {
+        let dir = TempDir::new("config-v1");
+        let path = dir.config(V1_FIXTURE);
+
+        let layer = load_user_file(&path).unwrap();
+
+        assert_eq!(layer.value["schema_version"], CURRENT_SCHEMA_VERSION);
+        assert_eq!(layer.value["webdav"]["url"], "https://dav.example.com");
+        assert_eq!(layer.value["webdav"]["remote_path"], "/workHelper");
+        assert!(layer.value["webdav"].get("password").is_none(), "与默认值相同的字段不再写入");
+        assert!(layer.value.get("update").is_none(), "迁移补全的默认更新设置不写入用户文件");
+        assert!(layer.value.get("sync").is_none());
+
+        assert_eq!(read_json(&path), layer.value, "迁移结果写回配置文件");
+        assert_eq!(
+            fs::read_to_string(backup_path(&path, "v1.bak")).unwrap(),
+            V1_FIXTURE,
+            "迁移前的原文件保留为 v1 备份"
+        );
+        assert!(!backup_path(&path, "tmp").exists(), "不留下临时文件");
+    }
+
+    #[test]
+    fn migrates_v2_full_config_to_overrides() {
+        let mut v2 = serde_json::to_value(AppConfig::default()).unwrap();
+        v2["schema_version"] = json!(2);
+        v2["webdav"]["url"] = json!("https://dav.example.com");
+        v2["update"]["channel"] = json!("nightly");
+        v2["update"]["check_interval_hours"] = json!(12);
+        let v2 = serde_json::to_string_pretty(&v2).unwrap();
+        let dir = TempDir::new("config-v2");
+        let path = dir.config(&v2);
+
+        let layer = load_user_file(&path).unwrap();
+
+        assert_eq!(layer.value["schema_version"], CURRENT_SCHEMA_VERSION);
+        assert_eq!(layer.value["webdav"], json!({"url": "https://dav.example.com"}));
+        assert_eq!(layer.value["update"], json!({"channel": "nightly", "check_interval_hours": 12}));
+        assert!(layer.value.get("sync").is_none());
+        assert_eq!(fs::read_to_string(backup_path(&path, "v2.bak")).unwrap(), v2);
+        assert!(!backup_path(&path, "v1.bak").exists());
+    }
+
+    #[test]
+    fn migrate_runs_each_step_from_file_version() {
+        let mut v1 = json!({"update": {"channel": "beta"}});
+        assert_eq!(migrate(&mut v1).unwrap(), 1);
+        assert_eq!(v1["schema_version"], CURRENT_SCHEMA_VERSION);
+        assert_eq!(v1["update"], json!({"channel": "beta"}), "已有的更新设置不被默认值覆盖");
+
+        let mut current = json!({"schema_version": CURRENT_SCHEMA_VERSION, "update": {"channel": "stable"}});
+        assert_eq!(migrate(&mut current).unwrap(), CURRENT_SCHEMA_VERSION);
+        assert_eq!(current["update"]["channel"], "stable", "当前版本不做迁移");
+    }
+
+    #[test]
+    fn current_config_is_not_migrated_or_backed_up() {
+        let dir = TempDir::new("config-v3");
+        let content = r#"{"schema_version": 3, "update": {"channel": "beta"}}"#;
+        let path = dir.config(content);
+
+        let layer = load_user_file(&path).unwrap();
+
+        assert_eq!(layer.value["update"]["channel"], "beta");
+        assert_eq!(fs::read_to_string(&path).unwrap(), content, "文件不被改写");
+        assert!(!backup_path(&path, "bak").exists());
+        assert!(!backup_path(&path, &format!("v{}.bak", CURRENT_SCHEMA_VERSION)).exists());
+    }
+
+    #[test]
+    fn load_errors_are_typed() {
+        let dir = TempDir::new("config-errors");
+
+        let path = dir.config(r#"{"schema_version": 99}"#);
+        let error = load_user_file(&path).unwrap_err();
+        assert!(matches!(error, ConfigLoadError::TooNew { version: 99, supported: CURRENT_SCHEMA_VERSION }));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({"kind": "tooNew", "version": 99, "supported": CURRENT_SCHEMA_VERSION})
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), r#"{"schema_version": 99}"#, "新版本的文件不被改写");
+
+        let path = dir.config(r#"{"webdav": "#);
+        let error = load_user_file(&path).unwrap_err();
+        assert!(matches!(error, ConfigLoadError::Invalid { .. }));
+        assert_eq!(serde_json::to_value(&error).unwrap()["kind"], "invalid");
+
+        let mut not_object = json!([1, 2]);
+        let error = migrate(&mut not_object).unwrap_err();
+        assert!(matches!(error, ConfigLoadError::Migration { from: 1, .. }));
+    }
+
+    #[test]
+    fn missing_config_file_is_created() {
+        let dir = TempDir::new("config-missing");
+        let path = dir.0.join("config.json");
+
+        let layer = load_user_file(&path).unwrap();
+
+        assert_eq!(layer.value, empty_user_config());
+        assert_eq!(read_json(&path), empty_user_config());
+    }
+
+    #[test]
+    fn write_config_backs_up_previous_file() {
+        let dir = TempDir::new("config-write");
+        let path = dir.config(r#"{"schema_version": 3, "update": {"channel": "beta"}}"#);
+
+        write_config(&path, json!({"update": {"channel": "nightly"}})).unwrap();
+
+        assert_eq!(read_json(&path)["update"]["channel"], "nightly");
+        assert_eq!(read_json(&path)["schema_version"], CURRENT_SCHEMA_VERSION);
+        assert_eq!(read_json(&backup_path(&path, "bak"))["update"]["channel"], "beta");
+        assert!(!backup_path(&path, "tmp").exists());
+    }
+
+    #[test]
+    fn invalid_config_keeps_last_good_config() {
+        let mut last_good = None;
+        let mut load_error = None;
+
+        let mut good = AppConfig::default();
+        good.update.channel = UpdateChannel::Beta;
+        let config = keep_last_good(Ok(good), &mut last_good, &mut load_error);
+        assert_eq!(config.update.channel, UpdateChannel::Beta);
+        assert!(load_error.is_none());
+
+        let invalid = ConfigLoadError::Invalid {
+            message: "expected value at line 1".to_string(),
+        };
+        let config = keep_last_good(Err(invalid), &mut last_good, &mut load_error);
+        assert_eq!(config.update.channel, UpdateChannel::Beta, "继续使用上次成功加载的配置");
+        assert!(matches!(load_error, Some(ConfigLoadError::Invalid { .. })), "记录错误以拒绝覆盖配置文件");
+
+        let config = keep_last_good(Ok(AppConfig::default()), &mut last_good, &mut load_error);
+        assert_eq!(config.update.channel, UpdateChannel::Stable);
+        assert!(load_error.is_none(), "修复后清除错误");
+    }
+
+    #[test]
+    fn invalid_config_at_startup_uses_defaults() {
+        let mut last_good = None;
+        let mut load_error = None;
+
+        let error = ConfigLoadError::TooNew { version: 9, supported: CURRENT_SCHEMA_VERSION };
+        let config = keep_last_good(Err(error), &mut last_good, &mut load_error);
+
+        assert_eq!(config.update.channel, UpdateChannel::default());
+        assert!(last_good.is_none());
+        assert!(load_error.is_some());
     }
-    
-    // 否则从配置文件加载
-    let app_config = load_config();
-    log::info!("使用配置文件中的WebDAV配置");
-    app_config.webdav
 }
@@ -0,0 +1,96 @@
+/*!
+ * 配置文件监听模块
+ * 监听用户配置文件和系统配置文件所在目录，文件被手动修改后重新加载并校验，
+ * 修改有效时立即生效并通知界面；修改无效时记录错误并继续使用修改前的配置
+ */
+
+use crate::config;
+use crate::settings::SETTINGS_CHANGED_EVENT;
+use notify::{RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// 配置文件修改并重新加载后发送的事件
+pub const CONFIG_CHANGED_EVENT: &str = "config-changed";
+
+/// 配置文件修改无效时发送的事件，携带 [`config::ConfigLoadError`]
+pub const CONFIG_RELOAD_FAILED_EVENT: &str = "config-reload-failed";
+
+/// 编辑器保存时可能连续产生多个事件，等待文件稳定后再重新加载
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 启动配置文件监听
+pub fn start(app: AppHandle) {
+    let files: BTreeSet<PathBuf> = config::watched_paths().into_iter().collect();
+    let dirs: BTreeSet<PathBuf> = files
+        .iter()
+        .filter_map(|path| path.parent())
+        .filter(|dir| dir.is_dir())
+        .map(PathBuf::from)
+        .collect();
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            let _ = sender.send(event);
+        }
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            log::warn!("无法监听配置文件，修改配置文件后需重启应用: {}", e);
+            return;
+        }
+    };
+
+    for dir in &dirs {
+        if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+            log::warn!("无法监听配置目录 {}: {}", dir.display(), e);
+        }
+    }
+
+    log::info!("已开始监听配置文件: {:?}", files);
+    std::thread::spawn(move || {
+        // 监听器需要与线程同生命周期
+        let _watcher = watcher;
+
+        while let Ok(event) = receiver.recv() {
+            if !touches(&event, &files) {
+                continue;
+            }
+            // 等待连续的修改事件结束
+            while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+            reload(&app);
+        }
+    });
+}
+
+/// 事件是否涉及监听的配置文件（忽略备份、临时文件等同目录下的其他文件）
+fn touches(event: &notify::Event, files: &BTreeSet<PathBuf>) -> bool {
+    !event.kind.is_access() && event.paths.iter().any(|path| files.contains(path))
+}
+
+/// 重新加载配置并通知界面
+fn reload(app: &AppHandle) {
+    match config::reload_config() {
+        Ok(None) => {}
+        Ok(Some(config)) => {
+            log::info!("配置文件已修改，已重新加载");
+            crate::scheduler::reschedule();
+            if let Err(e) = app.emit(CONFIG_CHANGED_EVENT, ()) {
+                log::warn!("发送配置修改事件失败: {}", e);
+            }
+            if let Err(e) = app.emit(SETTINGS_CHANGED_EVENT, &config.general) {
+                log::warn!("发送设置修改事件失败: {}", e);
+            }
+        }
+        Err(e) => {
+            log::error!("{} - 继续使用修改前的配置", e);
+            if let Err(e) = app.emit(CONFIG_RELOAD_FAILED_EVENT, &e) {
+                log::warn!("发送配置加载失败事件失败: {}", e);
+            }
+        }
+    }
+}
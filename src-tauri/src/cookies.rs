@@ -0,0 +1,290 @@
+/*!
+ * Cookie 存储模块
+ * 代发请求（http_request 命令）自动携带并保存服务器设置的 Cookie，使依赖会话 Cookie 的 OA 系统
+ * （验证码会话、单点登录跳转）可以正常使用。Cookie 按服务器区分，保存在当前档案数据目录的 cookies.json 中；
+ * 会话 Cookie 只在本次运行中有效，不写入文件。查看 Cookie 时不返回 HttpOnly Cookie 的值，避免页面脚本读取会话凭据
+ */
+
+use crate::profiles;
+use cookie_store::{CookieExpiration, CookieStore};
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use reqwest::Url;
+use serde::Serialize;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Cookie 文件，保存在当前档案的数据目录中
+const COOKIES_FILE: &str = "cookies.json";
+
+/// Cookie 列表项
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CookieInfo {
+    /// 所属域名
+    pub domain: String,
+    pub path: String,
+    pub name: String,
+    /// HttpOnly Cookie 不返回值
+    pub value: Option<String>,
+    /// 过期时间（Unix时间戳，秒），会话 Cookie 为空
+    pub expires: Option<i64>,
+    pub secure: bool,
+    pub http_only: bool,
+}
+
+/// 已加载的 Cookie 及其文件路径，切换档案后按新档案的文件重新加载
+struct Jar {
+    path: PathBuf,
+    store: CookieStore,
+}
+
+fn jar_slot() -> &'static Mutex<Option<Jar>> {
+    static JAR: OnceLock<Mutex<Option<Jar>>> = OnceLock::new();
+    JAR.get_or_init(|| Mutex::new(None))
+}
+
+/// 请求指定地址时携带的 Cookie 请求头
+pub fn request_header(url: &Url) -> Option<String> {
+    with_jar(|jar| {
+        let pairs: Vec<String> = jar
+            .store
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        (!pairs.is_empty()).then(|| pairs.join("; "))
+    })
+    .ok()
+    .flatten()
+}
+
+/// 保存响应中服务器设置的 Cookie
+pub fn store_response(url: &Url, headers: &HeaderMap) {
+    let set_cookies: Vec<&str> = headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    if set_cookies.is_empty() {
+        return;
+    }
+
+    let result = with_jar(|jar| {
+        for set_cookie in set_cookies {
+            if let Err(e) = jar.store.parse(set_cookie, url) {
+                log::debug!("忽略 {} 设置的无效 Cookie: {:?}", url, e);
+            }
+        }
+        save(jar)
+    });
+    if let Err(e) = result.and_then(|saved| saved) {
+        log::warn!("{}", e);
+    }
+}
+
+/// 列出未过期的 Cookie，提供 host 时只列出发送给该服务器的 Cookie
+pub fn list_cookies(host: Option<&str>) -> Result<Vec<CookieInfo>, String> {
+    with_jar(|jar| cookie_infos(&jar.store, host))
+}
+
+/// 列出存储中未过期的 Cookie，HttpOnly Cookie 不返回值
+fn cookie_infos(store: &CookieStore, host: Option<&str>) -> Vec<CookieInfo> {
+    let mut cookies: Vec<CookieInfo> = store
+        .iter_unexpired()
+        .map(|cookie| CookieInfo {
+            domain: String::from(&cookie.domain),
+            path: String::from(&cookie.path),
+            name: cookie.name().to_string(),
+            value: (!cookie.http_only().unwrap_or(false)).then(|| cookie.value().to_string()),
+            expires: match cookie.expires {
+                CookieExpiration::AtUtc(at) => Some(at.unix_timestamp()),
+                CookieExpiration::SessionEnd => None,
+            },
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+        })
+        .filter(|cookie| host.map_or(true, |host| domain_matches(&cookie.domain, host)))
+        .collect();
+    cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+    cookies
+}
+
+/// 清除 Cookie，提供 host 时只清除发送给该服务器的 Cookie，返回清除的数量
+pub fn clear_cookies(host: Option<&str>) -> Result<usize, String> {
+    with_jar(|jar| {
+        let removed: Vec<(String, String, String)> = jar
+            .store
+            .iter_any()
+            .map(|cookie| (String::from(&cookie.domain), String::from(&cookie.path), cookie.name().to_string()))
+            .filter(|(domain, _, _)| host.map_or(true, |host| domain_matches(domain, host)))
+            .collect();
+        for (domain, path, name) in &removed {
+            jar.store.remove(domain, path, name);
+        }
+        save(jar)?;
+
+        log::info!("已清除 {} 个 Cookie{}", removed.len(), host.map(|host| format!("（{}）", host)).unwrap_or_default());
+        Ok(removed.len())
+    })?
+}
+
+/// Cookie 的域名是否适用于指定服务器
+fn domain_matches(domain: &str, host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+/// 使用当前档案的 Cookie
+fn with_jar<T>(f: impl FnOnce(&mut Jar) -> T) -> Result<T, String> {
+    let path = profiles::profile_dir()?.join(COOKIES_FILE);
+    let mut slot = jar_slot().lock().unwrap();
+    if slot.as_ref().map(|jar| &jar.path) != Some(&path) {
+        *slot = None;
+    }
+    let jar = slot.get_or_insert_with(|| Jar {
+        store: load(&path),
+        path,
+    });
+    Ok(f(jar))
+}
+
+/// 读取 Cookie 文件，跳过已过期的 Cookie；文件不存在或无法解析时从空白开始
+fn load(path: &Path) -> CookieStore {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(_) => return CookieStore::default(),
+    };
+    CookieStore::load_json(BufReader::new(file)).unwrap_or_else(|e| {
+        log::warn!("读取 Cookie 文件失败，已忽略保存的 Cookie: {}", e);
+        CookieStore::default()
+    })
+}
+
+/// 保存未过期的持久 Cookie，先写入临时文件再替换，避免写入中断留下不完整的文件
+fn save(jar: &Jar) -> Result<(), String> {
+    let mut content = Vec::new();
+    jar.store
+        .save_json(&mut content)
+        .map_err(|e| format!("序列化 Cookie 失败: {}", e))?;
+
+    let temp_path = jar.path.with_file_name(format!("{}.tmp", COOKIES_FILE));
+    fs::write(&temp_path, content).map_err(|e| format!("保存 Cookie 失败: {}", e))?;
+    fs::rename(&temp_path, &jar.path).map_err(|e| format!("保存 Cookie 失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试用临时目录，离开作用域时删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("workhelper-{}-{}", name, uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn store(set_cookies: &[(&str, &str)]) -> CookieStore {
+        let mut store = CookieStore::default();
+        for (url, set_cookie) in set_cookies {
+            store.parse(set_cookie, &Url::parse(url).unwrap()).unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn domain_matches_cases() {
+        let cases = [
+            ("oa.example.com", "oa.example.com", true),
+            ("example.com", "oa.example.com", true),
+            (".example.com", "oa.example.com", true),
+            ("Example.COM", "OA.example.com", true),
+            ("example.com", "example.com", true),
+            ("oa.example.com", "example.com", false),
+            ("example.com", "badexample.com", false),
+            ("example.com", "example.com.evil.net", false),
+            ("example.com", "other.org", false),
+        ];
+
+        for (domain, host, expected) in cases {
+            assert_eq!(domain_matches(domain, host), expected, "{} 适用于 {}", domain, host);
+        }
+    }
+
+    #[test]
+    fn list_hides_http_only_values() {
+        let store = store(&[
+            ("https://oa.example.com/", "SESSIONID=secret; Path=/; HttpOnly; Secure"),
+            ("https://oa.example.com/", "theme=dark; Path=/; Max-Age=3600"),
+        ]);
+
+        let cookies = cookie_infos(&store, None);
+        assert_eq!(cookies.len(), 2);
+
+        let session = cookies.iter().find(|cookie| cookie.name == "SESSIONID").unwrap();
+        assert!(session.http_only && session.secure);
+        assert_eq!(session.value, None, "HttpOnly Cookie 不返回值");
+        assert_eq!(session.expires, None, "会话 Cookie 没有过期时间");
+
+        let theme = cookies.iter().find(|cookie| cookie.name == "theme").unwrap();
+        assert!(!theme.http_only);
+        assert_eq!(theme.value.as_deref(), Some("dark"));
+        assert!(theme.expires.is_some());
+
+        // 序列化结果中同样没有 HttpOnly Cookie 的值
+        let json = serde_json::to_string(&cookies).unwrap();
+        assert!(!json.contains("secret"), "{}", json);
+    }
+
+    #[test]
+    fn list_filters_by_host() {
+        let store = store(&[
+            ("https://oa.example.com/", "a=1; Domain=example.com"),
+            ("https://oa.example.com/", "b=2"),
+            ("https://other.org/", "c=3"),
+        ]);
+
+        let names = |host: Option<&str>| -> Vec<String> {
+            cookie_infos(&store, host).into_iter().map(|cookie| cookie.name).collect()
+        };
+        assert_eq!(names(None), vec!["a", "b", "c"]);
+        assert_eq!(names(Some("oa.example.com")), vec!["a", "b"]);
+        assert_eq!(names(Some("www.example.com")), vec!["a"]);
+        assert_eq!(names(Some("other.org")), vec!["c"]);
+        assert!(names(Some("example.net")).is_empty());
+    }
+
+    #[test]
+    fn session_cookies_are_not_saved() {
+        let dir = TempDir::new("cookies");
+        let jar = Jar {
+            path: dir.0.join(COOKIES_FILE),
+            store: store(&[
+                ("https://oa.example.com/", "SESSIONID=secret; HttpOnly"),
+                ("https://oa.example.com/", "remember=1; Max-Age=3600"),
+            ]),
+        };
+        save(&jar).unwrap();
+        assert!(!dir.0.join(format!("{}.tmp", COOKIES_FILE)).exists(), "临时文件已替换为正式文件");
+
+        let loaded = load(&jar.path);
+        let names: Vec<&str> = loaded.iter_any().map(|cookie| cookie.name()).collect();
+        assert_eq!(names, vec!["remember"], "只保存持久 Cookie");
+
+        // 文件无法解析时从空白开始
+        fs::write(&jar.path, "not json").unwrap();
+        assert_eq!(load(&jar.path).iter_any().count(), 0);
+        assert_eq!(load(&dir.0.join("missing.json")).iter_any().count(), 0);
+    }
+}
@@ -0,0 +1,398 @@
+/*!
+ * 差分更新模块
+ * 负责选择可用的差分补丁、校验文件哈希，并将 bsdiff 格式的补丁应用到本地保留的旧版本安装包上，
+ * 还原出新版本的完整安装包；发布时由 workhelper-release 生成补丁
+ */
+
+use crate::history;
+use crate::manifest::{Artifact, DeltaPatch};
+use serde::{Deserialize, Serialize};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// 选定的差分补丁，随版本信息返回给前端，下载时再传回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaInfo {
+    /// 补丁基于的版本，对应安装包需保留在更新目录中
+    pub base_version: String,
+    /// 补丁下载地址
+    pub url: String,
+    /// 补丁文件大小
+    pub file_size: u64,
+    /// 补丁文件的SHA-256
+    pub sha256: String,
+    /// 基础安装包的SHA-256
+    pub base_sha256: String,
+}
+
+/// 为安装包选择差分补丁
+///
+/// 只有在安装包提供了完整文件的SHA-256（用于校验还原结果）、
+/// 且补丁的基础版本安装包仍保留在本地时才可用；多个补丁可用时选择最小的。
+pub fn select_delta(artifact: &Artifact, updates_dir: &Path, base_url: &str) -> Option<DeltaInfo> {
+    artifact.sha256.as_ref()?;
+
+    artifact
+        .deltas
+        .iter()
+        .filter(|delta| history::installer_for_version(updates_dir, &delta.base_version).is_some())
+        .min_by_key(|delta| delta.file_size)
+        .map(|delta: &DeltaPatch| DeltaInfo {
+            base_version: delta.base_version.clone(),
+            url: crate::manifest::resolve_url(&delta.url, base_url),
+            file_size: delta.file_size,
+            sha256: delta.sha256.clone(),
+            base_sha256: delta.base_sha256.clone(),
+        })
+}
+
+/// 计算数据的SHA-256（小写十六进制）
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// 计算文件的SHA-256（小写十六进制）
+pub async fn sha256_file(path: &Path) -> Result<String, String> {
+    let data = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("读取文件失败: {} - {}", path.display(), e))?;
+
+    tokio::task::spawn_blocking(move || sha256_hex(&data))
+        .await
+        .map_err(|e| format!("计算文件哈希失败: {}", e))
+}
+
+/// 比较两个十六进制哈希值（忽略大小写）
+pub fn hash_matches(actual: &str, expected: &str) -> bool {
+    actual.eq_ignore_ascii_case(expected.trim())
+}
+
+/// 补丁文件头：7字节魔数、1字节格式版本、8字节还原后的文件大小（小端）
+const PATCH_MAGIC: &[u8; 7] = b"WHDELTA";
+const PATCH_VERSION: u8 = 1;
+const HEADER_LEN: usize = 16;
+
+/// 还原后的文件大小上限，防止损坏的补丁声明过大的文件
+const MAX_NEW_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// 生成补丁时索引旧文件的块大小
+const BLOCK: usize = 16;
+
+/// 生成 bsdiff 格式的补丁，应用到 old 上可还原出 new
+///
+/// 以固定大小的块索引旧文件，在新文件中逐字节查找相同的块并向前后扩展为匹配区间；
+/// 匹配区间作为混合数据（与旧文件的差值，均为0），其余部分作为追加数据，整体用 zlib 压缩。
+pub fn create_patch(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut index: HashMap<u128, usize> = HashMap::new();
+    for (i, chunk) in old.chunks_exact(BLOCK).enumerate() {
+        index.entry(block_key(chunk)).or_insert(i * BLOCK);
+    }
+
+    // 匹配区间：(新文件起点, 旧文件起点, 长度)，按新文件位置递增且互不重叠
+    let mut matches: Vec<(usize, usize, usize)> = Vec::new();
+    let mut pos = 0;
+    let mut literal_start = 0;
+    while pos + BLOCK <= new.len() {
+        let Some(&old_start) = index.get(&block_key(&new[pos..pos + BLOCK])) else {
+            pos += 1;
+            continue;
+        };
+        let forward = common_len(old[old_start + BLOCK..].iter(), new[pos + BLOCK..].iter());
+        let back = common_len(old[..old_start].iter().rev(), new[literal_start..pos].iter().rev());
+        matches.push((pos - back, old_start - back, back + BLOCK + forward));
+        pos += BLOCK + forward;
+        literal_start = pos;
+    }
+
+    let mut body = Vec::new();
+    if !new.is_empty() {
+        // 第一个控制块只追加首个匹配之前的数据，并移动到首个匹配在旧文件中的位置
+        let (first_new, first_old) = matches.first().map_or((new.len(), 0), |m| (m.0, m.1));
+        write_control(&mut body, 0, first_new, first_old as i64);
+        body.extend_from_slice(&new[..first_new]);
+    }
+    for (i, &(new_start, old_start, len)) in matches.iter().enumerate() {
+        let (literal_end, next_old) = matches
+            .get(i + 1)
+            .map_or((new.len(), old_start + len), |next| (next.0, next.1));
+        write_control(
+            &mut body,
+            len,
+            literal_end - (new_start + len),
+            next_old as i64 - (old_start + len) as i64,
+        );
+        body.extend(
+            new[new_start..new_start + len]
+                .iter()
+                .zip(&old[old_start..old_start + len])
+                .map(|(n, o)| n.wrapping_sub(*o)),
+        );
+        body.extend_from_slice(&new[new_start + len..literal_end]);
+    }
+
+    encode_patch(new.len() as u64, &body)
+}
+
+/// 加上文件头并压缩补丁数据
+fn encode_patch(new_size: u64, body: &[u8]) -> Vec<u8> {
+    let mut patch = Vec::with_capacity(HEADER_LEN + body.len() / 4);
+    patch.extend_from_slice(PATCH_MAGIC);
+    patch.push(PATCH_VERSION);
+    patch.extend_from_slice(&new_size.to_le_bytes());
+
+    let mut encoder = ZlibEncoder::new(patch, Compression::best());
+    encoder.write_all(body).expect("写入内存缓冲区不会失败");
+    encoder.finish().expect("写入内存缓冲区不会失败")
+}
+
+/// 应用 bsdiff 格式的补丁
+///
+/// 补丁以文件头开始，其后为 zlib 压缩的数据：若干控制块，每个控制块为三个8字节整数
+/// （混合长度、追加长度、旧文件偏移），随后依次是与旧文件逐字节相加的混合数据和直接追加的新数据。
+/// 每个控制块在读取数据前都会检查长度和偏移，不会超出文件头声明的大小和旧文件的范围。
+pub fn apply_patch(old: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    let header = patch.get(..HEADER_LEN).ok_or("差分补丁文件不完整")?;
+    if &header[..PATCH_MAGIC.len()] != PATCH_MAGIC {
+        return Err("不是有效的差分补丁文件".to_string());
+    }
+    if header[7] != PATCH_VERSION {
+        return Err(format!("不支持的差分补丁格式版本: {}", header[7]));
+    }
+    let mut size = [0u8; 8];
+    size.copy_from_slice(&header[8..16]);
+    let new_size = u64::from_le_bytes(size);
+    if new_size > MAX_NEW_SIZE {
+        return Err("差分补丁声明的文件大小无效".to_string());
+    }
+
+    let mut body = ZlibDecoder::new(&patch[HEADER_LEN..]);
+    let mut new = Vec::new();
+    let mut old_pos = 0usize;
+    let mut first = true;
+
+    while (new.len() as u64) < new_size {
+        let mut control = [0u8; 24];
+        body.read_exact(&mut control).map_err(|_| "补丁控制块不完整")?;
+        let mix_len = read_offset(&control[0..8]);
+        let copy_len = read_offset(&control[8..16]);
+        let seek_len = read_offset(&control[16..24]);
+
+        // 只有第一个控制块可以不产生数据（仅移动旧文件偏移），其余控制块至少产生1字节
+        let remaining = new_size - new.len() as u64;
+        let (Ok(mix_len), Ok(copy_len)) = (u64::try_from(mix_len), u64::try_from(copy_len)) else {
+            return Err("补丁控制块长度无效".to_string());
+        };
+        let data_len = mix_len.checked_add(copy_len).filter(|len| *len <= remaining);
+        if data_len.map_or(true, |len| len == 0 && !first) {
+            return Err("补丁控制块长度无效".to_string());
+        }
+        let (mix_len, data_len) = (mix_len as usize, data_len.unwrap_or_default());
+        let base = slice(old, old_pos, mix_len).ok_or("补丁偏移超出旧文件范围")?;
+
+        let start = new.len();
+        (&mut body)
+            .take(data_len)
+            .read_to_end(&mut new)
+            .map_err(|e| format!("读取差分补丁数据失败: {}", e))?;
+        if (new.len() - start) as u64 != data_len {
+            return Err("补丁数据不完整".to_string());
+        }
+
+        // 混合数据：与旧文件对应位置的字节相加；追加数据原样保留
+        for (byte, base) in new[start..start + mix_len].iter_mut().zip(base) {
+            *byte = byte.wrapping_add(*base);
+        }
+
+        old_pos = i64::try_from(old_pos + mix_len)
+            .ok()
+            .and_then(|pos| pos.checked_add(seek_len))
+            .and_then(|pos| usize::try_from(pos).ok())
+            .filter(|pos| *pos <= old.len())
+            .ok_or("补丁偏移超出旧文件范围")?;
+        first = false;
+    }
+
+    // 还原完成后补丁中不应再有数据
+    let mut rest = [0u8; 1];
+    match body.read(&mut rest) {
+        Ok(0) => Ok(new),
+        Ok(_) => Err("差分补丁包含多余的数据".to_string()),
+        Err(e) => Err(format!("读取差分补丁数据失败: {}", e)),
+    }
+}
+
+/// 安全地截取切片，长度越界或溢出时返回 None
+fn slice(data: &[u8], start: usize, len: usize) -> Option<&[u8]> {
+    data.get(start..start.checked_add(len)?)
+}
+
+/// 块内容作为索引键
+fn block_key(block: &[u8]) -> u128 {
+    let mut key = [0u8; BLOCK];
+    key.copy_from_slice(block);
+    u128::from_le_bytes(key)
+}
+
+/// 两个序列开头相同的字节数
+fn common_len<'a>(a: impl Iterator<Item = &'a u8>, b: impl Iterator<Item = &'a u8>) -> usize {
+    a.zip(b).take_while(|(a, b)| a == b).count()
+}
+
+/// 写入控制块
+fn write_control(body: &mut Vec<u8>, mix_len: usize, copy_len: usize, seek_len: i64) {
+    body.extend_from_slice(&write_offset(mix_len as i64));
+    body.extend_from_slice(&write_offset(copy_len as i64));
+    body.extend_from_slice(&write_offset(seek_len));
+}
+
+/// 读取 bsdiff 的8字节有符号整数（小端，最高位为符号位）
+fn read_offset(bytes: &[u8]) -> i64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(bytes);
+    let negative = buf[7] & 0x80 != 0;
+    buf[7] &= 0x7f;
+
+    let value = i64::from_le_bytes(buf);
+    if negative {
+        -value
+    } else {
+        value
+    }
+}
+
+/// 写入 bsdiff 的8字节有符号整数
+fn write_offset(value: i64) -> [u8; 8] {
+    let mut buf = value.unsigned_abs().to_le_bytes();
+    if value < 0 {
+        buf[7] |= 0x80;
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 可重复的伪随机数据
+    fn pseudo_random(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    fn round_trip(old: &[u8], new: &[u8]) -> Vec<u8> {
+        let patch = create_patch(old, new);
+        assert_eq!(apply_patch(old, &patch).unwrap(), new);
+        patch
+    }
+
+    #[test]
+    fn round_trip_with_edits() {
+        let old = pseudo_random(64 * 1024, 1);
+        let mut new = old.clone();
+        new[100] ^= 0xff;
+        new.splice(5000..5000, pseudo_random(300, 2));
+        new.drain(20000..21000);
+        new.extend(pseudo_random(1000, 3));
+        new[40000..50000].copy_from_slice(&old[0..10000]);
+
+        let patch = round_trip(&old, &new);
+        assert!(patch.len() < new.len() / 10, "补丁大小 {}", patch.len());
+    }
+
+    #[test]
+    fn round_trip_edge_cases() {
+        let data = pseudo_random(4096, 4);
+        round_trip(&data, &data);
+        round_trip(&[], &data);
+        round_trip(&data, &[]);
+        round_trip(&[], &[]);
+        round_trip(&data[..10], &data[..15]);
+        round_trip(&data, &pseudo_random(4096, 5));
+    }
+
+    #[test]
+    fn rejects_bad_header() {
+        let old = pseudo_random(1024, 6);
+        let patch = create_patch(&old, &old);
+
+        assert!(apply_patch(&old, &patch[..10]).is_err());
+
+        let mut bad_magic = patch.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(apply_patch(&old, &bad_magic).unwrap_err(), "不是有效的差分补丁文件");
+
+        let mut bad_version = patch.clone();
+        bad_version[7] = PATCH_VERSION + 1;
+        assert!(apply_patch(&old, &bad_version).unwrap_err().contains("格式版本"));
+
+        let mut too_large = patch;
+        too_large[8..16].copy_from_slice(&(MAX_NEW_SIZE + 1).to_le_bytes());
+        assert!(apply_patch(&old, &too_large).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_or_trailing_data() {
+        let old = pseudo_random(2048, 7);
+        let new = pseudo_random(2048, 8);
+        let patch = create_patch(&old, &new);
+
+        assert!(apply_patch(&old, &patch[..patch.len() - 8]).is_err());
+
+        let mut body = Vec::new();
+        write_control(&mut body, 0, 4, 0);
+        body.extend_from_slice(b"abcdextra");
+        assert_eq!(apply_patch(&old, &encode_patch(4, &body)).unwrap_err(), "差分补丁包含多余的数据");
+    }
+
+    #[test]
+    fn rejects_out_of_range_controls() {
+        let old = pseudo_random(100, 9);
+
+        // 混合数据超出旧文件
+        let mut body = Vec::new();
+        write_control(&mut body, 200, 0, 0);
+        body.resize(body.len() + 200, 0);
+        assert_eq!(apply_patch(&old, &encode_patch(200, &body)).unwrap_err(), "补丁偏移超出旧文件范围");
+
+        // 长度超出文件头声明的大小
+        let mut body = Vec::new();
+        write_control(&mut body, 0, 1 << 40, 0);
+        assert_eq!(apply_patch(&old, &encode_patch(10, &body)).unwrap_err(), "补丁控制块长度无效");
+
+        // 负数长度
+        let mut body = Vec::new();
+        body.extend_from_slice(&write_offset(-1));
+        body.extend_from_slice(&write_offset(0));
+        body.extend_from_slice(&write_offset(0));
+        assert_eq!(apply_patch(&old, &encode_patch(10, &body)).unwrap_err(), "补丁控制块长度无效");
+
+        // 偏移移到旧文件之前
+        let mut body = Vec::new();
+        write_control(&mut body, 0, 1, -5);
+        body.push(0);
+        write_control(&mut body, 0, 1, 0);
+        body.push(0);
+        assert_eq!(apply_patch(&old, &encode_patch(2, &body)).unwrap_err(), "补丁偏移超出旧文件范围");
+
+        // 除第一个外不产生数据的控制块
+        let mut body = Vec::new();
+        write_control(&mut body, 0, 1, 0);
+        body.push(0);
+        write_control(&mut body, 0, 0, 0);
+        assert_eq!(apply_patch(&old, &encode_patch(2, &body)).unwrap_err(), "补丁控制块长度无效");
+    }
+}
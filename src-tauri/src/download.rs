@@ -0,0 +1,523 @@
+/*!
+ * 更新包断点续传模块
+ * 负责管理下载中的临时文件（.part）及其进度元数据，支持中断后使用Range请求继续下载，
+ * 并按下载ID跟踪进行中的下载以支持取消；同一更新包已在下载时（如后台预下载），新的请求可以等待它完成
+ */
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+/// 断点续传元数据，与 .part 文件一同保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialDownload {
+    /// 下载地址，地址变化时不能续传
+    pub url: String,
+    /// 文件总大小（未知时为0）
+    pub total: u64,
+    /// 已写入 .part 文件的字节数
+    pub downloaded: u64,
+    /// 服务器返回的ETag，用于 If-Range 校验
+    pub etag: Option<String>,
+    /// 服务器返回的Last-Modified，ETag缺失时用于 If-Range 校验
+    pub last_modified: Option<String>,
+}
+
+impl PartialDownload {
+    pub fn new(url: &str, total: u64) -> Self {
+        Self {
+            url: url.to_string(),
+            total,
+            downloaded: 0,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// 从响应头中记录资源校验信息
+    pub fn update_validators(&mut self, headers: &reqwest::header::HeaderMap) {
+        self.etag = header_string(headers, reqwest::header::ETAG);
+        self.last_modified = header_string(headers, reqwest::header::LAST_MODIFIED);
+    }
+
+    /// 续传时使用的 If-Range 值，优先使用强ETag
+    pub fn if_range(&self) -> Option<&str> {
+        match &self.etag {
+            Some(etag) if !etag.starts_with("W/") => Some(etag.as_str()),
+            _ => self.last_modified.as_deref(),
+        }
+    }
+}
+
+/// 下载中的临时文件路径
+pub fn part_path(file_path: &Path) -> PathBuf {
+    append_extension(file_path, "part")
+}
+
+/// 断点续传元数据文件路径
+fn meta_path(file_path: &Path) -> PathBuf {
+    append_extension(file_path, "part.json")
+}
+
+fn append_extension(file_path: &Path, extension: &str) -> PathBuf {
+    let mut name = file_path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+fn header_string(headers: &reqwest::header::HeaderMap, name: reqwest::header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// 读取可续传的下载进度
+///
+/// 只有当元数据中的地址与本次请求一致、且 .part 文件存在时才会返回，
+/// 已下载字节数以 .part 文件的实际长度为准。
+pub fn load_partial(file_path: &Path, url: &str) -> Option<PartialDownload> {
+    let content = std::fs::read_to_string(meta_path(file_path)).ok()?;
+    let mut partial: PartialDownload = serde_json::from_str(&content).ok()?;
+
+    if partial.url != url {
+        log::info!("下载地址已变化，放弃续传: {}", file_path.display());
+        return None;
+    }
+
+    let part_len = std::fs::metadata(part_path(file_path)).ok()?.len();
+    if partial.total > 0 && part_len > partial.total {
+        log::warn!("临时文件大于预期大小，放弃续传: {}", file_path.display());
+        return None;
+    }
+
+    partial.downloaded = part_len;
+    Some(partial)
+}
+
+/// 保存下载进度
+pub async fn save_partial(file_path: &Path, partial: &PartialDownload) -> Result<(), String> {
+    let content = serde_json::to_string(partial)
+        .map_err(|e| format!("序列化下载进度失败: {}", e))?;
+
+    tokio::fs::write(meta_path(file_path), content)
+        .await
+        .map_err(|e| format!("保存下载进度失败: {}", e))
+}
+
+/// 删除临时文件及其元数据
+pub async fn remove_partial(file_path: &Path) {
+    for path in [part_path(file_path), meta_path(file_path)] {
+        if tokio::fs::metadata(&path).await.is_ok() {
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                log::warn!("删除临时下载文件失败: {} - {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// 下载完成后将 .part 文件原子地重命名为最终文件
+pub async fn finalize(file_path: &Path) -> Result<(), String> {
+    // Windows 下目标文件存在时 rename 会失败，先删除旧文件
+    if tokio::fs::metadata(file_path).await.is_ok() {
+        tokio::fs::remove_file(file_path)
+            .await
+            .map_err(|e| format!("删除旧文件失败: {}", e))?;
+    }
+
+    tokio::fs::rename(part_path(file_path), file_path)
+        .await
+        .map_err(|e| format!("重命名下载文件失败: {}", e))?;
+
+    let _ = tokio::fs::remove_file(meta_path(file_path)).await;
+    Ok(())
+}
+
+/// 下载地址指向本地文件（本地目录或SMB共享更新源）时返回对应路径
+///
+/// 支持 file:// 地址以及不带协议的路径（如 C:\releases\... 或 \\server\share\...）
+pub fn local_path(url: &str) -> Option<PathBuf> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return Some(PathBuf::from(path));
+    }
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return None;
+    }
+    Some(PathBuf::from(url))
+}
+
+/// 解析 Content-Range 响应头，返回 (起始字节, 文件总大小)
+///
+/// 例如 "bytes 100-199/1000" 返回 (100, Some(1000))，总大小为 "*" 时返回 None
+pub fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (span, total) = range.split_once('/')?;
+    let (start, _) = span.split_once('-')?;
+
+    let start = start.trim().parse().ok()?;
+    let total = total.trim().parse().ok();
+    Some((start, total))
+}
+
+/// 下载响应的处理方式
+#[derive(Debug, PartialEq, Eq)]
+pub enum ResponseAction {
+    /// 从指定字节开始写入临时文件，0 表示从头下载
+    Write { resume_from: u64 },
+    /// 下载失败，保留临时文件以便之后续传
+    Fail(String),
+    /// 续传失败，需要清除临时文件后重新下载
+    Discard(String),
+}
+
+/// 根据下载响应决定如何写入临时文件，并更新下载进度
+///
+/// 206 且 Content-Range 的起点与已下载字节数一致时续传；服务器忽略 Range 返回 200 时重置进度从头下载；
+/// 416 或续传范围不匹配说明临时文件已损坏或服务器文件已变化，需要清除临时文件。
+pub fn handle_response(
+    partial: &mut PartialDownload,
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+    content_length: Option<u64>,
+) -> ResponseAction {
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        return ResponseAction::Discard("续传失败，已清除临时文件，请重新下载".to_string());
+    }
+
+    if !status.is_success() {
+        return ResponseAction::Fail(format!("下载失败，状态码: {}", status));
+    }
+
+    let resume_from = if status == reqwest::StatusCode::PARTIAL_CONTENT {
+        let content_range = headers
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_content_range);
+
+        match content_range {
+            Some((start, total)) if start == partial.downloaded => {
+                if let Some(total) = total {
+                    partial.total = total;
+                }
+                start
+            }
+            _ => {
+                return ResponseAction::Discard(
+                    "服务器返回的续传范围不匹配，已清除临时文件，请重新下载".to_string(),
+                );
+            }
+        }
+    } else {
+        if partial.downloaded > 0 {
+            log::info!("服务器不支持续传或文件已变化，重新开始下载");
+        }
+        *partial = PartialDownload::new(&partial.url, content_length.unwrap_or(partial.total));
+        0
+    };
+    partial.update_validators(headers);
+
+    ResponseAction::Write { resume_from }
+}
+
+/// 正在进行的下载任务
+#[derive(Clone)]
+pub struct ActiveDownload {
+    /// 取消令牌，触发后下载循环立即停止
+    pub token: CancellationToken,
+    /// 取消时是否保留临时文件以便之后续传
+    keep_partial: Arc<AtomicBool>,
+    /// 下载限速（字节/秒，0为不限速），用于后台低优先级下载
+    rate_limit: Arc<AtomicU64>,
+    /// 下载结束（完成、失败或取消）时触发
+    finished: CancellationToken,
+}
+
+impl ActiveDownload {
+    pub fn keep_partial(&self) -> bool {
+        self.keep_partial.load(Ordering::SeqCst)
+    }
+
+    /// 当前的下载限速
+    pub fn rate_limit(&self) -> Option<u64> {
+        Some(self.rate_limit.load(Ordering::SeqCst)).filter(|limit| *limit > 0)
+    }
+
+    /// 取消限速，用户手动下载时不再以低优先级进行
+    pub fn remove_rate_limit(&self) {
+        self.rate_limit.store(0, Ordering::SeqCst);
+    }
+
+    /// 等待下载结束
+    pub async fn wait_finished(&self) {
+        self.finished.cancelled().await;
+    }
+}
+
+/// 登记下载任务的结果
+pub enum Registration {
+    /// 已登记为新的下载任务
+    Started(ActiveDownload),
+    /// 同一ID的下载正在进行
+    Running(ActiveDownload),
+}
+
+/// 下载任务管理器，作为Tauri托管状态按下载ID跟踪进行中的下载
+#[derive(Default)]
+pub struct DownloadManager {
+    active: Mutex<HashMap<String, ActiveDownload>>,
+}
+
+impl DownloadManager {
+    /// 登记新的下载任务，同一ID已在下载时返回错误
+    pub fn register(&self, download_id: &str, rate_limit: Option<u64>) -> Result<ActiveDownload, String> {
+        match self.register_or_attach(download_id, rate_limit)? {
+            Registration::Started(download) => Ok(download),
+            Registration::Running(_) => Err(format!("该更新包正在下载中: {}", download_id)),
+        }
+    }
+
+    /// 登记新的下载任务，同一ID已在下载时返回正在进行的任务
+    pub fn register_or_attach(&self, download_id: &str, rate_limit: Option<u64>) -> Result<Registration, String> {
+        let mut active = self.active.lock().map_err(|_| "下载任务状态已损坏".to_string())?;
+
+        if let Some(running) = active.get(download_id) {
+            return Ok(Registration::Running(running.clone()));
+        }
+
+        let download = ActiveDownload {
+            token: CancellationToken::new(),
+            keep_partial: Arc::new(AtomicBool::new(true)),
+            rate_limit: Arc::new(AtomicU64::new(rate_limit.unwrap_or(0))),
+            finished: CancellationToken::new(),
+        };
+        active.insert(download_id.to_string(), download.clone());
+        Ok(Registration::Started(download))
+    }
+
+    /// 下载结束后移除任务，并通知等待该任务的请求
+    pub fn finish(&self, download_id: &str) {
+        if let Ok(mut active) = self.active.lock() {
+            if let Some(download) = active.remove(download_id) {
+                download.finished.cancel();
+            }
+        }
+    }
+
+    /// 取消下载，未指定ID时取消全部下载，返回被取消的任务数
+    pub fn cancel(&self, download_id: Option<&str>, keep_partial: bool) -> usize {
+        let Ok(active) = self.active.lock() else {
+            return 0;
+        };
+
+        let mut cancelled = 0;
+        for (id, download) in active.iter() {
+            if download_id.is_some_and(|target| target != id) {
+                continue;
+            }
+            download.keep_partial.store(keep_partial, Ordering::SeqCst);
+            download.token.cancel();
+            cancelled += 1;
+        }
+        cancelled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue, CONTENT_RANGE, ETAG};
+    use reqwest::StatusCode;
+    use std::fs;
+
+    const URL: &str = "https://example.com/WorkHelper_1.2.0_x64-setup.exe";
+
+    /// 测试用临时目录，离开作用域时删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("workhelper-{}-{}", name, uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        /// 写入 .part 文件及其元数据，返回最终文件路径
+        fn partial(&self, part: &[u8], partial: &PartialDownload) -> PathBuf {
+            let file_path = self.0.join("WorkHelper_1.2.0_x64-setup.exe");
+            fs::write(part_path(&file_path), part).unwrap();
+            fs::write(meta_path(&file_path), serde_json::to_string(partial).unwrap()).unwrap();
+            file_path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn resuming(downloaded: u64, total: u64) -> PartialDownload {
+        PartialDownload {
+            downloaded,
+            etag: Some("\"old\"".to_string()),
+            ..PartialDownload::new(URL, total)
+        }
+    }
+
+    fn headers(pairs: &[(reqwest::header::HeaderName, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(name.clone(), HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn parse_content_range_cases() {
+        let cases = [
+            ("bytes 100-199/1000", Some((100, Some(1000)))),
+            ("bytes 0-0/1", Some((0, Some(1)))),
+            (" bytes 100-199/* ", Some((100, None))),
+            ("bytes 100-199", None),
+            ("bytes */1000", None),
+            ("items 100-199/1000", None),
+            ("bytes abc-199/1000", None),
+            ("", None),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(parse_content_range(value), expected, "解析 {:?}", value);
+        }
+    }
+
+    #[test]
+    fn if_range_prefers_strong_etag() {
+        let mut partial = PartialDownload::new(URL, 0);
+        partial.last_modified = Some("Wed, 01 Jan 2026 00:00:00 GMT".to_string());
+        assert_eq!(partial.if_range(), Some("Wed, 01 Jan 2026 00:00:00 GMT"));
+
+        partial.etag = Some("W/\"weak\"".to_string());
+        assert_eq!(partial.if_range(), Some("Wed, 01 Jan 2026 00:00:00 GMT"), "弱ETag不能用于 If-Range");
+
+        partial.etag = Some("\"strong\"".to_string());
+        assert_eq!(partial.if_range(), Some("\"strong\""));
+    }
+
+    #[test]
+    fn load_partial_uses_part_file_length() {
+        let dir = TempDir::new("download");
+        let file_path = dir.partial(&[0; 300], &resuming(100, 1000));
+
+        let partial = load_partial(&file_path, URL).expect("地址一致时可以续传");
+        assert_eq!(partial.downloaded, 300, "已下载字节数以 .part 文件为准");
+        assert_eq!(partial.total, 1000);
+        assert_eq!(partial.etag.as_deref(), Some("\"old\""));
+    }
+
+    #[test]
+    fn load_partial_rejects_unusable_progress() {
+        let dir = TempDir::new("download");
+
+        // 下载地址已变化
+        let file_path = dir.partial(&[0; 100], &resuming(100, 1000));
+        assert!(load_partial(&file_path, "https://mirror.example.com/setup.exe").is_none());
+
+        // 临时文件大于预期大小
+        let file_path = dir.partial(&[0; 1001], &resuming(100, 1000));
+        assert!(load_partial(&file_path, URL).is_none());
+
+        // 总大小未知时不限制临时文件大小
+        let file_path = dir.partial(&[0; 1001], &resuming(100, 0));
+        assert_eq!(load_partial(&file_path, URL).map(|partial| partial.downloaded), Some(1001));
+
+        // 临时文件不存在
+        fs::remove_file(part_path(&file_path)).unwrap();
+        assert!(load_partial(&file_path, URL).is_none());
+
+        // 元数据无法解析或不存在
+        let file_path = dir.partial(&[0; 100], &resuming(100, 1000));
+        fs::write(meta_path(&file_path), "not json").unwrap();
+        assert!(load_partial(&file_path, URL).is_none());
+        fs::remove_file(meta_path(&file_path)).unwrap();
+        assert!(load_partial(&file_path, URL).is_none());
+    }
+
+    #[tokio::test]
+    async fn finalize_replaces_existing_file() {
+        let dir = TempDir::new("download");
+        let file_path = dir.partial(b"new package", &resuming(11, 11));
+        fs::write(&file_path, b"old package").unwrap();
+
+        finalize(&file_path).await.unwrap();
+        assert_eq!(fs::read(&file_path).unwrap(), b"new package");
+        assert!(!part_path(&file_path).exists(), "临时文件已重命名");
+        assert!(!meta_path(&file_path).exists(), "元数据已删除");
+
+        // 临时文件不存在时返回错误
+        assert!(finalize(&file_path).await.is_err());
+    }
+
+    #[test]
+    fn handle_response_resumes_matching_range() {
+        let mut partial = resuming(300, 0);
+        let headers = headers(&[(CONTENT_RANGE, "bytes 300-999/1000"), (ETAG, "\"new\"")]);
+
+        let action = handle_response(&mut partial, StatusCode::PARTIAL_CONTENT, &headers, Some(700));
+        assert_eq!(action, ResponseAction::Write { resume_from: 300 });
+        assert_eq!(partial.downloaded, 300);
+        assert_eq!(partial.total, 1000, "总大小取自 Content-Range");
+        assert_eq!(partial.etag.as_deref(), Some("\"new\""));
+    }
+
+    #[test]
+    fn handle_response_restarts_when_range_ignored() {
+        // 续传请求收到 200，说明服务器不支持续传或文件已变化
+        let mut partial = resuming(300, 1000);
+        let headers = headers(&[(ETAG, "\"new\"")]);
+
+        let action = handle_response(&mut partial, StatusCode::OK, &headers, Some(1200));
+        assert_eq!(action, ResponseAction::Write { resume_from: 0 });
+        assert_eq!(partial.downloaded, 0, "重置下载进度");
+        assert_eq!(partial.total, 1200, "总大小取自本次响应");
+        assert_eq!(partial.url, URL);
+        assert_eq!(partial.etag.as_deref(), Some("\"new\""));
+
+        // 响应没有内容长度时沿用原来的总大小
+        let mut partial = resuming(300, 1000);
+        let action = handle_response(&mut partial, StatusCode::OK, &HeaderMap::new(), None);
+        assert_eq!(action, ResponseAction::Write { resume_from: 0 });
+        assert_eq!((partial.downloaded, partial.total), (0, 1000));
+        assert!(partial.etag.is_none(), "旧的ETag不再适用");
+    }
+
+    #[test]
+    fn handle_response_discards_invalid_resume() {
+        let cases = [
+            (StatusCode::RANGE_NOT_SATISFIABLE, headers(&[])),
+            // 续传起点与已下载字节数不一致
+            (StatusCode::PARTIAL_CONTENT, headers(&[(CONTENT_RANGE, "bytes 200-999/1000")])),
+            // 缺少或无法解析 Content-Range
+            (StatusCode::PARTIAL_CONTENT, headers(&[])),
+            (StatusCode::PARTIAL_CONTENT, headers(&[(CONTENT_RANGE, "bytes */1000")])),
+        ];
+
+        for (status, headers) in cases {
+            let mut partial = resuming(300, 1000);
+            let action = handle_response(&mut partial, status, &headers, None);
+            assert!(matches!(action, ResponseAction::Discard(_)), "{} {:?}: {:?}", status, headers, action);
+        }
+    }
+
+    #[test]
+    fn handle_response_keeps_partial_on_server_error() {
+        let mut partial = resuming(300, 1000);
+        let action = handle_response(&mut partial, StatusCode::SERVICE_UNAVAILABLE, &HeaderMap::new(), None);
+        assert!(matches!(action, ResponseAction::Fail(_)), "{:?}", action);
+        assert_eq!((partial.downloaded, partial.total), (300, 1000), "保留下载进度");
+    }
+}
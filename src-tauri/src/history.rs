@@ -0,0 +1,410 @@
+/*!
+ * 安装历史模块
+ * 负责记录每次通过更新器安装的版本及其安装包，支持回滚到之前的版本，
+ * 并检测更新后首次启动是否失败
+ */
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const HISTORY_FILE: &str = "install_history.json";
+const LAUNCH_STATE_FILE: &str = "launch_state.json";
+
+/// 单次安装记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallRecord {
+    /// 安装前的版本
+    pub from_version: String,
+    /// 安装的版本
+    pub to_version: String,
+    /// 安装包文件名（位于更新目录中）
+    pub file_name: String,
+    /// 安装前版本的安装包文件名，安装时仍保留在更新目录中才有，用于回滚到安装前的版本
+    #[serde(default)]
+    pub from_file_name: Option<String>,
+    /// 安装时间（Unix时间戳，秒）
+    pub installed_at: u64,
+    /// 是否为回滚安装
+    #[serde(default)]
+    pub rollback: bool,
+}
+
+/// 更新后的启动状态，用于检测新版本首次启动失败
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchState {
+    /// 等待确认启动成功的版本
+    pending_version: Option<String>,
+    /// 该版本已尝试启动的次数
+    launch_attempts: u32,
+}
+
+/// 回滚建议，新版本启动失败后提供给前端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackSuggestion {
+    /// 启动失败的版本
+    pub failed_version: String,
+    /// 可回滚到的版本
+    pub rollback_version: String,
+}
+
+/// 本次启动检测到的回滚建议，作为Tauri托管状态保存
+#[derive(Default)]
+pub struct LaunchCheck(pub Mutex<Option<RollbackSuggestion>>);
+
+fn read_json<T: for<'de> Deserialize<'de> + Default>(path: &Path) -> T {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("序列化安装记录失败: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("写入安装记录失败: {}", e))
+}
+
+/// 读取安装历史，按安装时间从旧到新排列
+pub fn load_history(updates_dir: &Path) -> Vec<InstallRecord> {
+    read_json(&updates_dir.join(HISTORY_FILE))
+}
+
+/// 追加安装记录，并将新版本标记为等待首次启动确认
+pub fn record_install(updates_dir: &Path, record: InstallRecord) -> Result<(), String> {
+    let mut history = load_history(updates_dir);
+    let pending_version = record.to_version.clone();
+    history.push(record);
+    write_json(&updates_dir.join(HISTORY_FILE), &history)?;
+
+    let state = LaunchState {
+        pending_version: Some(pending_version),
+        launch_attempts: 0,
+    };
+    write_json(&updates_dir.join(LAUNCH_STATE_FILE), &state)
+}
+
+/// 查找指定版本保留在更新目录中的安装包
+///
+/// 先查找安装记录（安装的版本及安装前的版本），没有记录时查找文件名中带有该版本号的安装包，
+/// 例如第一次通过更新器安装前手动下载或由旧版更新器下载的安装包
+pub fn installer_for_version(updates_dir: &Path, version: &str) -> Option<PathBuf> {
+    load_history(updates_dir)
+        .iter()
+        .rev()
+        .flat_map(|record| installer_names(record).into_iter())
+        .filter(|(record_version, _)| *record_version == version)
+        .map(|(_, file_name)| updates_dir.join(file_name))
+        .find(|path| path.exists())
+        .or_else(|| find_installer_file(updates_dir, version))
+}
+
+/// 记录中的安装包及其版本：安装的版本，以及安装前的版本（如果保留了安装包）
+fn installer_names(record: &InstallRecord) -> Vec<(&str, &str)> {
+    let mut names = vec![(record.to_version.as_str(), record.file_name.as_str())];
+    if let Some(from_file_name) = &record.from_file_name {
+        names.push((record.from_version.as_str(), from_file_name.as_str()));
+    }
+    names
+}
+
+/// 在更新目录中查找文件名带有指定版本号的安装包
+fn find_installer_file(updates_dir: &Path, version: &str) -> Option<PathBuf> {
+    let mut names: Vec<String> = fs::read_dir(updates_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| is_installer_file(name) && names_version(name, version))
+        .collect();
+    names.sort();
+    names.first().map(|name| updates_dir.join(name))
+}
+
+/// 文件名中是否带有完整的版本号，如 WorkHelper_1.2.0_x64-setup.exe 带有 1.2.0，不带有 1.2
+fn names_version(file_name: &str, version: &str) -> bool {
+    file_name.match_indices(version).any(|(start, _)| {
+        let before = file_name[..start].chars().next_back();
+        let mut after = file_name[start + version.len()..].chars();
+        // 版本号后的 . 只有接着数字时才说明版本号还没结束（如 1.2 之后的 .0），接着扩展名时不算
+        let continues = match after.next() {
+            Some('.') => after.next().is_some_and(|c| c.is_ascii_digit()),
+            Some(c) => c.is_ascii_alphanumeric() || c == '-' || c == '+',
+            None => false,
+        };
+        !before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '.') && !continues
+    })
+}
+
+/// 判断文件是否为安装包（不包括下载中的 .part 临时文件）
+pub fn is_installer_file(file_name: &str) -> bool {
+    let lower = file_name.to_lowercase();
+    [".exe", ".msi", ".appimage", ".deb", ".dmg"]
+        .iter()
+        .any(|extension| lower.ends_with(extension))
+}
+
+/// 当前版本之前安装的版本，即默认的回滚目标
+pub fn previous_version(updates_dir: &Path, current_version: &str) -> Option<String> {
+    load_history(updates_dir)
+        .iter()
+        .rev()
+        .find(|record| record.to_version == current_version && record.from_version != current_version)
+        .map(|record| record.from_version.clone())
+}
+
+/// 可以回滚到的版本（安装包仍保留在更新目录中），包括第一次更新前的版本，从新到旧排列
+pub fn rollback_versions(updates_dir: &Path, current_version: &str) -> Vec<String> {
+    let mut versions: Vec<String> = Vec::new();
+    for record in load_history(updates_dir).iter().rev() {
+        for (version, file_name) in installer_names(record) {
+            if version == current_version || versions.iter().any(|existing| existing == version) {
+                continue;
+            }
+            if updates_dir.join(file_name).exists() {
+                versions.push(version.to_string());
+            }
+        }
+    }
+    versions
+}
+
+/// 需要保留的安装包文件名：最近安装的 retain 个版本，以及这些版本安装前的版本
+pub fn retained_installers(updates_dir: &Path, retain: usize) -> Vec<String> {
+    let mut versions: Vec<&str> = Vec::new();
+    let mut files = Vec::new();
+    let history = load_history(updates_dir);
+
+    for record in history.iter().rev() {
+        for (version, file_name) in installer_names(record) {
+            if !versions.contains(&version) {
+                if versions.len() >= retain {
+                    continue;
+                }
+                versions.push(version);
+            }
+            files.push(file_name.to_string());
+        }
+    }
+    files
+}
+
+/// 启动时检查更新后的首次启动状态
+///
+/// 如果新版本上次启动后没有确认成功（前端未完成初始化），说明新版本可能启动即崩溃，
+/// 返回回滚到上一个版本的建议。
+pub fn check_launch(updates_dir: &Path, current_version: &str) -> Option<RollbackSuggestion> {
+    let path = updates_dir.join(LAUNCH_STATE_FILE);
+    let mut state: LaunchState = read_json(&path);
+
+    let pending_version = state.pending_version.clone()?;
+    if pending_version != current_version {
+        // 安装未生效（例如用户取消了安装），不再等待确认
+        let _ = fs::remove_file(&path);
+        return None;
+    }
+
+    let previous_failed = state.launch_attempts > 0;
+    state.launch_attempts += 1;
+    if let Err(e) = write_json(&path, &state) {
+        log::warn!("{}", e);
+    }
+
+    if !previous_failed {
+        return None;
+    }
+
+    log::warn!("检测到版本 {} 更新后启动失败 {} 次", current_version, state.launch_attempts - 1);
+    let rollback_version = previous_version(updates_dir, current_version)?;
+    installer_for_version(updates_dir, &rollback_version)?;
+
+    Some(RollbackSuggestion {
+        failed_version: current_version.to_string(),
+        rollback_version,
+    })
+}
+
+/// 确认当前版本启动成功
+pub fn confirm_launch(updates_dir: &Path) {
+    let path = updates_dir.join(LAUNCH_STATE_FILE);
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            log::warn!("清除启动状态失败: {}", e);
+        } else {
+            log::info!("更新后首次启动成功");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试用的更新目录，结束时删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("workhelper-{}-{}", name, uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn touch(&self, file_name: &str) {
+            fs::write(self.0.join(file_name), b"installer").unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn record(from: &str, to: &str, from_file_name: Option<&str>, installed_at: u64) -> InstallRecord {
+        InstallRecord {
+            from_version: from.to_string(),
+            to_version: to.to_string(),
+            file_name: format!("WorkHelper_{}_x64-setup.exe", to),
+            from_file_name: from_file_name.map(|name| name.to_string()),
+            installed_at,
+            rollback: false,
+        }
+    }
+
+    /// 写入安装历史，不改变启动状态
+    fn write_history(dir: &TempDir, records: &[InstallRecord]) {
+        write_json(&dir.0.join(HISTORY_FILE), &records).unwrap();
+    }
+
+    #[test]
+    fn installer_for_version_prefers_history_records() {
+        let dir = TempDir::new("history-records");
+        write_history(
+            &dir,
+            &[
+                record("1.0.0", "1.1.0", Some("old-1.0.0.exe"), 1),
+                record("1.1.0", "1.2.0", None, 2),
+            ],
+        );
+        dir.touch("old-1.0.0.exe");
+        dir.touch("WorkHelper_1.1.0_x64-setup.exe");
+        dir.touch("WorkHelper_1.2.0_x64-setup.exe");
+
+        assert_eq!(installer_for_version(&dir.0, "1.0.0"), Some(dir.0.join("old-1.0.0.exe")), "安装前版本的安装包");
+        assert_eq!(installer_for_version(&dir.0, "1.2.0"), Some(dir.0.join("WorkHelper_1.2.0_x64-setup.exe")));
+        assert_eq!(installer_for_version(&dir.0, "0.9.0"), None);
+    }
+
+    #[test]
+    fn installer_for_version_falls_back_to_file_names() {
+        let dir = TempDir::new("history-files");
+        // 记录中的安装包已被删除，但目录中还有手动下载的安装包
+        write_history(&dir, &[record("1.0.0", "1.1.0", None, 1)]);
+        dir.touch("WorkHelper_1.1.0_x64-setup.exe.part");
+        dir.touch("WorkHelper_1.1.0_x64-setup.msi");
+        dir.touch("WorkHelper_1.1.10_x64-setup.exe");
+        dir.touch("WorkHelper_1.1.0-beta.1_x64-setup.exe");
+
+        assert_eq!(
+            installer_for_version(&dir.0, "1.1.0"),
+            Some(dir.0.join("WorkHelper_1.1.0_x64-setup.msi")),
+            "不应匹配 .part 文件、1.1.10 或 1.1.0-beta.1"
+        );
+        assert_eq!(installer_for_version(&dir.0, "1.1"), None);
+    }
+
+    #[test]
+    fn names_version_requires_whole_version() {
+        assert!(names_version("WorkHelper_1.2.0_x64-setup.exe", "1.2.0"));
+        assert!(names_version("WorkHelper-1.2.0.dmg", "1.2.0"));
+        assert!(!names_version("WorkHelper_1.2.0_x64-setup.exe", "1.2"));
+        assert!(!names_version("WorkHelper_11.2.0_x64-setup.exe", "1.2.0"));
+        assert!(!names_version("WorkHelper_1.2.0-beta.1_x64-setup.exe", "1.2.0"));
+        assert!(names_version("WorkHelper_1.2.0-beta.1_x64-setup.exe", "1.2.0-beta.1"));
+    }
+
+    #[test]
+    fn retained_installers_keeps_recent_versions_and_their_predecessors() {
+        let dir = TempDir::new("history-retain");
+        write_history(
+            &dir,
+            &[
+                record("1.0.0", "1.1.0", Some("WorkHelper_1.0.0_x64-setup.exe"), 1),
+                record("1.1.0", "1.2.0", None, 2),
+                record("1.2.0", "1.3.0", Some("WorkHelper_1.2.0_x64-setup.exe"), 3),
+            ],
+        );
+
+        let mut retained = retained_installers(&dir.0, 2);
+        retained.sort();
+        retained.dedup();
+        assert_eq!(
+            retained,
+            vec!["WorkHelper_1.2.0_x64-setup.exe", "WorkHelper_1.3.0_x64-setup.exe"],
+            "只保留最近两个版本的安装包"
+        );
+
+        let mut retained = retained_installers(&dir.0, 3);
+        retained.sort();
+        retained.dedup();
+        assert_eq!(
+            retained,
+            vec![
+                "WorkHelper_1.1.0_x64-setup.exe",
+                "WorkHelper_1.2.0_x64-setup.exe",
+                "WorkHelper_1.3.0_x64-setup.exe",
+            ]
+        );
+
+        assert!(retained_installers(&dir.0, 0).is_empty());
+    }
+
+    #[test]
+    fn check_launch_suggests_rollback_after_failed_first_launch() {
+        let dir = TempDir::new("history-launch");
+        dir.touch("WorkHelper_1.1.0_x64-setup.exe");
+        record_install(&dir.0, record("1.1.0", "1.2.0", Some("WorkHelper_1.1.0_x64-setup.exe"), 1)).unwrap();
+
+        // 首次启动只记录尝试次数
+        assert!(check_launch(&dir.0, "1.2.0").is_none());
+
+        // 上次启动没有确认成功，建议回滚
+        let suggestion = check_launch(&dir.0, "1.2.0").expect("应建议回滚");
+        assert_eq!(suggestion.failed_version, "1.2.0");
+        assert_eq!(suggestion.rollback_version, "1.1.0");
+
+        // 确认启动成功后不再建议
+        confirm_launch(&dir.0);
+        assert!(check_launch(&dir.0, "1.2.0").is_none());
+        assert!(check_launch(&dir.0, "1.2.0").is_none());
+    }
+
+    #[test]
+    fn check_launch_ignores_confirmed_or_ineffective_installs() {
+        let dir = TempDir::new("history-launch-ok");
+        dir.touch("WorkHelper_1.1.0_x64-setup.exe");
+        record_install(&dir.0, record("1.1.0", "1.2.0", Some("WorkHelper_1.1.0_x64-setup.exe"), 1)).unwrap();
+
+        // 首次启动后确认成功
+        assert!(check_launch(&dir.0, "1.2.0").is_none());
+        confirm_launch(&dir.0);
+        assert!(check_launch(&dir.0, "1.2.0").is_none());
+
+        // 安装未生效，仍在运行旧版本
+        record_install(&dir.0, record("1.2.0", "1.3.0", None, 2)).unwrap();
+        assert!(check_launch(&dir.0, "1.2.0").is_none());
+        assert!(!dir.0.join(LAUNCH_STATE_FILE).exists(), "不再等待确认");
+    }
+
+    #[test]
+    fn check_launch_without_previous_installer_does_not_suggest() {
+        let dir = TempDir::new("history-launch-missing");
+        record_install(&dir.0, record("1.1.0", "1.2.0", None, 1)).unwrap();
+
+        assert!(check_launch(&dir.0, "1.2.0").is_none());
+        assert!(check_launch(&dir.0, "1.2.0").is_none(), "上一个版本的安装包已不存在");
+    }
+}
@@ -0,0 +1,349 @@
+/*!
+ * HTTP客户端模块
+ * 应用内的HTTP请求（代发请求、检查和下载更新、WebDAV、设置同步、共享配置）共用按网络设置创建的客户端，
+ * 复用连接池，统一设置超时、代理（系统代理、手动设置的 HTTP 或 SOCKS 代理）和内部服务使用的CA证书；
+ * 幂等请求遇到连接失败、超时或服务器暂时不可用时按指数退避重试
+ */
+
+use crate::config::load_config;
+use reqwest::{Certificate, Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// 空闲连接保留时间
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// 每个服务器最多保留的空闲连接数
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+
+/// 最多重试次数
+const MAX_RETRIES: u32 = 10;
+
+/// 重试等待时间上限
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// 服务器暂时不可用、值得重试的状态码
+const RETRY_STATUS: [StatusCode; 4] = [
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// 代理设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", rename_all_fields = "camelCase")]
+pub enum ProxyConfig {
+    /// 使用系统代理（含 HTTP_PROXY、HTTPS_PROXY 环境变量）
+    #[default]
+    System,
+    /// 不使用代理
+    None,
+    /// 手动设置的代理，支持 http://、https://、socks5:// 和 socks5h:// 地址
+    Manual {
+        url: String,
+        #[serde(default)]
+        username: String,
+        #[serde(default)]
+        password: String,
+        /// 不使用代理的地址，逗号分隔，如 localhost,.corp.example.com
+        #[serde(default)]
+        no_proxy: String,
+    },
+}
+
+/// 网络设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkConfig {
+    /// 建立连接的超时时间（秒）
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// 读取超时（秒）：普通请求从发送到读完响应的最长时间，下载安装包时为等待下一段数据的最长时间
+    #[serde(default = "default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+    /// 幂等请求失败后的最多重试次数，0 表示不重试
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// 第一次重试前的等待时间（毫秒），之后每次加倍
+    #[serde(default = "default_retry_delay_ms")]
+    pub retry_delay_ms: u64,
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    /// 额外信任的CA证书文件（PEM 或 DER），用于使用内部证书的服务器
+    #[serde(default)]
+    pub ca_certificates: Vec<String>,
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_read_timeout_secs() -> u64 {
+    30
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+fn default_retry_delay_ms() -> u64 {
+    500
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: default_connect_timeout_secs(),
+            read_timeout_secs: default_read_timeout_secs(),
+            retries: default_retries(),
+            retry_delay_ms: default_retry_delay_ms(),
+            proxy: ProxyConfig::default(),
+            ca_certificates: Vec::new(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// 读取超时
+    pub fn read_timeout(&self) -> Duration {
+        Duration::from_secs(self.read_timeout_secs.max(1))
+    }
+
+    /// 校验网络设置，返回第一个错误
+    ///
+    /// 只在保存网络设置时校验；配置文件中的无效代理或CA证书在创建客户端时记录警告并跳过
+    pub fn validate(&self) -> Result<(), String> {
+        if self.connect_timeout_secs == 0 || self.read_timeout_secs == 0 {
+            return Err("超时时间必须大于0秒".to_string());
+        }
+        if self.retries > MAX_RETRIES {
+            return Err(format!("重试次数不能超过{}次", MAX_RETRIES));
+        }
+        build_proxy(&self.proxy)?;
+        for path in &self.ca_certificates {
+            load_certificates(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// 按网络设置创建的客户端
+struct Clients {
+    config: NetworkConfig,
+    /// 自动跟随重定向的客户端
+    client: reqwest::Client,
+    /// 不跟随重定向的客户端，由调用方自行检查重定向目标
+    no_redirect: reqwest::Client,
+}
+
+/// 共享的HTTP客户端，网络设置修改后在下次使用时重新创建
+#[derive(Clone, Default)]
+pub struct HttpClient {
+    clients: Arc<Mutex<Option<Arc<Clients>>>>,
+    /// 固定的网络设置，为空时使用应用配置中的网络设置
+    fixed: Option<NetworkConfig>,
+}
+
+/// 应用内共享的HTTP客户端，命令中也可通过 Tauri 状态获取
+pub fn shared() -> &'static HttpClient {
+    static SHARED: OnceLock<HttpClient> = OnceLock::new();
+    SHARED.get_or_init(HttpClient::default)
+}
+
+impl HttpClient {
+    /// 使用固定网络设置、不读取应用配置的客户端
+    pub fn with_config(config: NetworkConfig) -> Self {
+        Self {
+            clients: Arc::default(),
+            fixed: Some(config),
+        }
+    }
+
+    /// 自动跟随重定向的客户端
+    pub fn client(&self) -> reqwest::Client {
+        self.clients().client.clone()
+    }
+
+    /// 不跟随重定向的客户端
+    pub fn no_redirect_client(&self) -> reqwest::Client {
+        self.clients().no_redirect.clone()
+    }
+
+    /// 当前生效的网络设置
+    pub fn config(&self) -> NetworkConfig {
+        self.clients().config.clone()
+    }
+
+    /// 发送请求，整个请求受读取超时限制，幂等请求失败时重试
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let config = self.config();
+        self.send_with_retry(request.timeout(config.read_timeout()), &config).await
+    }
+
+    /// 发送下载请求，不限制读取响应体的总时间（由调用方按读取超时检查每段数据），失败时重试
+    pub async fn send_streaming(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let config = self.config();
+        self.send_with_retry(request, &config).await
+    }
+
+    async fn send_with_retry(&self, request: RequestBuilder, config: &NetworkConfig) -> reqwest::Result<Response> {
+        // 请求体为流时无法复制，只发送一次
+        let idempotent = request
+            .try_clone()
+            .and_then(|request| request.build().ok())
+            .is_some_and(|request| is_idempotent(request.method()));
+        if !idempotent {
+            return request.send().await;
+        }
+
+        let mut attempt = 0;
+        loop {
+            let current = request.try_clone().expect("幂等请求已确认可以复制");
+            let result = current.send().await;
+
+            let retryable = match &result {
+                Ok(response) => RETRY_STATUS.contains(&response.status()),
+                Err(e) => e.is_connect() || e.is_timeout(),
+            };
+            if !retryable || attempt >= config.retries.min(MAX_RETRIES) {
+                return result;
+            }
+
+            let delay = retry_delay(config, attempt);
+            match &result {
+                Ok(response) => log::warn!("{} 返回 {}，{:?} 后重试", response.url(), response.status(), delay),
+                Err(e) => log::warn!("HTTP请求失败，{:?} 后重试: {}", delay, e),
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// 当前网络设置对应的客户端，设置变化时重新创建
+    fn clients(&self) -> Arc<Clients> {
+        let config = self.fixed.clone().unwrap_or_else(|| load_config().network);
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(current) = clients.as_ref().filter(|current| current.config == config) {
+            return current.clone();
+        }
+
+        let created = Arc::new(create_clients(config));
+        *clients = Some(created.clone());
+        created
+    }
+}
+
+fn create_clients(config: NetworkConfig) -> Clients {
+    // 无效的代理地址改用系统代理，无法读取的CA证书跳过，不影响其他请求
+    let proxy = build_proxy(&config.proxy).unwrap_or_else(|e| {
+        log::warn!("{}，改用系统代理", e);
+        None
+    });
+    let certificates: Vec<Certificate> = config
+        .ca_certificates
+        .iter()
+        .flat_map(|path| {
+            load_certificates(path).unwrap_or_else(|e| {
+                log::warn!("{}，已跳过", e);
+                Vec::new()
+            })
+        })
+        .collect();
+
+    let build = |redirect: fn() -> reqwest::redirect::Policy| {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs.max(1)))
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+            .tcp_keepalive(Duration::from_secs(60))
+            .redirect(redirect());
+        // 未设置时 reqwest 读取系统代理设置
+        if config.proxy == ProxyConfig::None {
+            builder = builder.no_proxy();
+        } else if let Some(proxy) = &proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        for certificate in &certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+
+        builder.build().unwrap_or_else(|e| {
+            log::error!("按网络设置创建HTTP客户端失败，使用默认设置: {}", e);
+            reqwest::Client::builder().redirect(redirect()).build().unwrap_or_default()
+        })
+    };
+    let client = build(reqwest::redirect::Policy::default);
+    let no_redirect = build(reqwest::redirect::Policy::none);
+    log::info!("已按网络设置创建HTTP客户端，代理: {}", proxy_summary(&config.proxy));
+
+    Clients {
+        config,
+        client,
+        no_redirect,
+    }
+}
+
+fn build_proxy(proxy: &ProxyConfig) -> Result<Option<reqwest::Proxy>, String> {
+    let ProxyConfig::Manual {
+        url,
+        username,
+        password,
+        no_proxy,
+    } = proxy
+    else {
+        return Ok(None);
+    };
+
+    let scheme = url.split("://").next().unwrap_or_default().to_ascii_lowercase();
+    if !url.contains("://") || !matches!(scheme.as_str(), "http" | "https" | "socks5" | "socks5h") {
+        return Err(format!("代理地址 {} 无效，需以 http://、https://、socks5:// 或 socks5h:// 开头", url));
+    }
+
+    let mut built = reqwest::Proxy::all(url).map_err(|e| format!("代理地址 {} 无效: {}", url, e))?;
+    if !username.is_empty() {
+        built = built.basic_auth(username, password);
+    }
+    Ok(Some(built.no_proxy(reqwest::NoProxy::from_string(no_proxy))))
+}
+
+/// 读取CA证书文件，PEM 文件可以包含多个证书
+fn load_certificates(path: &str) -> Result<Vec<Certificate>, String> {
+    let content = std::fs::read(path).map_err(|e| format!("读取CA证书 {} 失败: {}", path, e))?;
+    let certificates = if content.windows(10).any(|window| window == b"-----BEGIN") {
+        Certificate::from_pem_bundle(&content)
+    } else {
+        Certificate::from_der(&content).map(|certificate| vec![certificate])
+    };
+    match certificates {
+        Ok(certificates) if !certificates.is_empty() => Ok(certificates),
+        Ok(_) => Err(format!("CA证书 {} 中没有证书", path)),
+        Err(e) => Err(format!("CA证书 {} 无效: {}", path, e)),
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(method.as_str(), "GET" | "HEAD" | "OPTIONS" | "PUT" | "DELETE" | "PROPFIND")
+}
+
+fn retry_delay(config: &NetworkConfig, attempt: u32) -> Duration {
+    let delay = Duration::from_millis(config.retry_delay_ms).saturating_mul(1 << attempt.min(16));
+    delay.min(MAX_RETRY_DELAY)
+}
+
+/// 日志中显示的代理设置，不含账号密码
+fn proxy_summary(proxy: &ProxyConfig) -> String {
+    match proxy {
+        ProxyConfig::System => "系统代理".to_string(),
+        ProxyConfig::None => "不使用代理".to_string(),
+        ProxyConfig::Manual { url, .. } => match reqwest::Url::parse(url) {
+            Ok(mut parsed) => {
+                let _ = parsed.set_username("");
+                let _ = parsed.set_password(None);
+                parsed.to_string()
+            }
+            Err(_) => url.clone(),
+        },
+    }
+}
@@ -0,0 +1,339 @@
+/*!
+ * HTTP代发模块
+ * 为前端代发HTTP请求（http_request 命令），请求先按 [`crate::http_policy`] 检查，
+ * 使用共享的HTTP客户端发送并逐个检查重定向目标，每次请求自动携带并保存 Cookie（见 [`crate::cookies`]），
+ * 返回包含状态码、响应头、最终地址和耗时的完整响应；二进制响应体（如验证码图片）以 base64 返回
+ */
+
+use crate::config::load_config;
+use crate::cookies;
+use crate::http_client::HttpClient;
+use crate::http_policy::{HttpPolicy, HttpRequestError};
+use base64::{engine::general_purpose, Engine as _};
+use reqwest::header::{HeaderMap, CONTENT_TYPE, COOKIE, LOCATION};
+use reqwest::{Method, StatusCode, Url};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
+
+/// 最多跟随的重定向次数
+const MAX_REDIRECTS: usize = 10;
+
+/// 响应体的编码方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BodyEncoding {
+    /// 文本（UTF-8）
+    Text,
+    /// 二进制内容的 base64 编码
+    Base64,
+}
+
+/// 代发请求的响应
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpResponse {
+    /// HTTP状态码
+    pub status: u16,
+    /// 状态码说明，如 Not Found
+    pub status_text: String,
+    /// 响应头，名称为小写，同名的多个值以 ", " 连接
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+    pub body_encoding: BodyEncoding,
+    /// 跟随重定向后的最终地址
+    pub url: String,
+    /// 从发送请求到读完响应体的耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+impl HttpResponse {
+    /// 状态码是否为 2xx
+    pub fn ok(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// 发送请求
+///
+/// `error_for_status` 为 true 时，非 2xx 响应以 [`HttpRequestError::Status`] 返回，错误中仍带有完整响应
+pub async fn send(
+    http: &HttpClient,
+    url: &str,
+    method: &str,
+    headers: HashMap<String, String>,
+    body: Option<String>,
+    error_for_status: bool,
+) -> Result<HttpResponse, HttpRequestError> {
+    let policy = HttpPolicy::from_config(&load_config());
+    send_with_policy(http, &policy, url, method, headers, body, error_for_status).await
+}
+
+/// 按指定策略检查并发送请求
+async fn send_with_policy(
+    http: &HttpClient,
+    policy: &HttpPolicy,
+    url: &str,
+    method: &str,
+    mut headers: HashMap<String, String>,
+    mut body: Option<String>,
+    error_for_status: bool,
+) -> Result<HttpResponse, HttpRequestError> {
+    let method_name = method.to_uppercase();
+
+    let mut url = policy.check(url, &method_name, &headers, body.as_deref()).map_err(|e| {
+        log::warn!("拒绝HTTP请求: {}", e);
+        e
+    })?;
+    // 策略只允许 GET、POST、PUT 和 DELETE
+    let mut method = Method::from_bytes(method_name.as_bytes()).map_err(|e| failed(e.to_string()))?;
+
+    // 客户端不自动跟随重定向，逐个检查重定向目标，目标不允许访问时返回重定向响应本身
+    let client = http.no_redirect_client();
+    let started = Instant::now();
+    let mut redirects = 0;
+    let response = loop {
+        let mut request = client.request(method.clone(), url.clone());
+        for (key, value) in &headers {
+            request = request.header(key, value);
+        }
+        if let Some(body) = &body {
+            request = request.body(body.clone());
+        }
+        if let Some(cookie) = cookies::request_header(&url) {
+            request = request.header(COOKIE, cookie);
+        }
+
+        let response = http.send(request).await.map_err(|e| {
+            log::error!("HTTP请求失败: {}", e);
+            failed(format!("HTTP请求失败: {}", e))
+        })?;
+        cookies::store_response(response.url(), response.headers());
+
+        let Some(next) = redirect_target(&response) else {
+            break response;
+        };
+        let to_get = redirects_to_get(response.status(), &method);
+        let next_method = if to_get { Method::GET } else { method.clone() };
+        if redirects >= MAX_REDIRECTS || !policy.allows(&next, next_method.as_str()) {
+            log::warn!("停止跟随重定向: {} -> {}", url, next);
+            break response;
+        }
+
+        if to_get {
+            method = next_method;
+            body = None;
+        }
+        // 重定向到其他服务器时不再发送认证信息
+        if next.origin() != url.origin() {
+            headers.retain(|name, _| !name.eq_ignore_ascii_case("authorization"));
+        }
+        url = next;
+        redirects += 1;
+    };
+
+    let status = response.status();
+    let final_url = response.url().to_string();
+    let headers = collect_headers(response.headers());
+    let text_like = is_text_content(response.headers());
+    let bytes = response.bytes().await.map_err(|e| {
+        log::error!("读取响应失败: {}", e);
+        failed(format!("读取响应失败: {}", e))
+    })?;
+
+    let (body, body_encoding) = match String::from_utf8(bytes.to_vec()) {
+        Ok(text) if text_like => (text, BodyEncoding::Text),
+        _ => (general_purpose::STANDARD.encode(&bytes), BodyEncoding::Base64),
+    };
+
+    let response = HttpResponse {
+        status: status.as_u16(),
+        status_text: status.canonical_reason().unwrap_or_default().to_string(),
+        headers,
+        body,
+        body_encoding,
+        url: final_url,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+
+    if error_for_status && !response.ok() {
+        log::warn!("HTTP请求返回错误状态 {}: {}", response.status, response.url);
+        return Err(HttpRequestError::Status {
+            response: Box::new(response),
+        });
+    }
+    Ok(response)
+}
+
+/// 重定向后是否改为不带请求体的 GET：与浏览器一致，303 以及 301、302 的 POST 请求
+fn redirects_to_get(status: StatusCode, method: &Method) -> bool {
+    status == StatusCode::SEE_OTHER
+        || (matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND) && method == Method::POST)
+}
+
+fn failed(message: String) -> HttpRequestError {
+    HttpRequestError::Failed { message }
+}
+
+/// 重定向响应的目标地址
+fn redirect_target(response: &reqwest::Response) -> Option<Url> {
+    if !response.status().is_redirection() {
+        return None;
+    }
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    response.url().join(location).ok()
+}
+
+fn collect_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    let mut collected: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        collected
+            .entry(name.as_str().to_string())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    collected
+}
+
+/// 响应体是否按文本返回：未声明类型或声明为文本类型（text/*、JSON、XML、JavaScript、表单）
+fn is_text_content(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok()) else {
+        return true;
+    };
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("json")
+        || mime.ends_with("xml")
+        || mime.contains("javascript")
+        || mime == "application/x-www-form-urlencoded"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::http_client::{NetworkConfig, ProxyConfig};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 本地HTTP服务器，按顺序响应请求，返回服务器地址和收到的请求
+    async fn serve(responses: Vec<String>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let Ok(Ok((mut stream, _))) =
+                    tokio::time::timeout(std::time::Duration::from_secs(2), listener.accept()).await
+                else {
+                    break;
+                };
+                let mut request = vec![0; 4096];
+                let read = stream.read(&mut request).await.unwrap();
+                stream.write_all(response.as_bytes()).await.unwrap();
+                requests.push(String::from_utf8_lossy(&request[..read]).to_string());
+            }
+            requests
+        });
+        (address, handle)
+    }
+
+    fn redirect(status: &str, location: &str) -> String {
+        format!("HTTP/1.1 {}\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status, location)
+    }
+
+    fn ok(body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    fn test_http() -> HttpClient {
+        HttpClient::with_config(NetworkConfig {
+            retries: 0,
+            proxy: ProxyConfig::None,
+            ..NetworkConfig::default()
+        })
+    }
+
+    /// OA 为 oa_url，更新源为静态目录 update_url
+    fn policy(oa_url: &str, update_url: &str) -> HttpPolicy {
+        let mut config = AppConfig::default();
+        config.general.oa_url = format!("{}/api", oa_url);
+        config.general.llm_base_url = String::new();
+        config.update.source = crate::config::UpdateSourceConfig::Static {
+            url: format!("{}/releases", update_url),
+        };
+        HttpPolicy::from_config(&config)
+    }
+
+    #[tokio::test]
+    async fn redirect_to_disallowed_origin_is_not_followed() {
+        let (other, other_requests) = serve(vec![ok("secret")]).await;
+        let (oa, oa_requests) = serve(vec![redirect("302 Found", &format!("{}/steal", other))]).await;
+        let policy = policy(&oa, "https://updates.example.com");
+
+        let response = send_with_policy(&test_http(), &policy, &format!("{}/api/login", oa), "GET", HashMap::new(), None, false)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 302, "返回重定向响应本身");
+        assert_eq!(response.headers["location"], format!("{}/steal", other));
+        assert_eq!(oa_requests.await.unwrap().len(), 1);
+        assert!(other_requests.await.unwrap().is_empty(), "不应访问其他服务器（端口不同也是不同服务器）");
+    }
+
+    #[tokio::test]
+    async fn redirect_to_read_only_origin_rechecks_method() {
+        let (update, update_requests) = serve(vec![ok("latest")]).await;
+        let (oa, _oa_requests) = serve(vec![
+            redirect("307 Temporary Redirect", &format!("{}/releases/latest.json", update)),
+            redirect("303 See Other", &format!("{}/releases/latest.json", update)),
+        ])
+        .await;
+        let policy = policy(&oa, &update);
+        let mut headers = HashMap::new();
+        headers.insert("Authorization".to_string(), "Bearer oa-token".to_string());
+
+        // 307 保留 POST，更新服务器只允许 GET，不跟随
+        let response = send_with_policy(
+            &test_http(),
+            &policy,
+            &format!("{}/api/submit", oa),
+            "POST",
+            headers.clone(),
+            Some("{}".to_string()),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status, 307);
+
+        // 303 改为 GET，可以跟随，且不把 OA 的认证信息发送给更新服务器
+        let response = send_with_policy(
+            &test_http(),
+            &policy,
+            &format!("{}/api/submit", oa),
+            "POST",
+            headers,
+            Some("{}".to_string()),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "latest");
+        assert_eq!(response.url, format!("{}/releases/latest.json", update));
+
+        let requests = update_requests.await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("GET /releases/latest.json "), "{}", requests[0]);
+        assert!(!requests[0].to_ascii_lowercase().contains("authorization"), "{}", requests[0]);
+    }
+}
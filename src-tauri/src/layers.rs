@@ -0,0 +1,441 @@
+/*!
+ * 配置分层模块
+ * 按「内置默认值 < 系统配置文件 < 用户配置文件 < 环境变量 < 命令行参数」的优先级逐字段合并配置，
+ * 并记录每个字段的生效来源。系统配置文件供IT统一下发公司默认设置（例如OA地址），
+ * 保存时只把用户修改过的字段写入用户配置文件
+ */
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// 指定系统配置文件路径的环境变量
+const SYSTEM_CONFIG_ENV: &str = "WORKHELPER_SYSTEM_CONFIG";
+
+/// 覆盖配置字段的环境变量前缀，字段之间用双下划线分隔，如 WORKHELPER__GENERAL__OA_URL
+const ENV_PREFIX: &str = "WORKHELPER__";
+
+/// 兼容旧版本的环境变量
+const LEGACY_ENV: [(&str, &str); 5] = [
+    ("WEBDAV_URL", "/webdav/url"),
+    ("WEBDAV_READ_USERNAME", "/webdav/username"),
+    ("WEBDAV_READ_PASSWORD", "/webdav/password"),
+    ("WEBDAV_REMOTE_PATH", "/webdav/remote_path"),
+    ("WEBDAV_BASE_URL", "/webdav/base_url"),
+];
+
+/// 覆盖配置字段的命令行参数，如 --set general.oaUrl=https://oa.example.com/api/oa
+const SET_ARG: &str = "--set";
+
+/// 配置层，越靠后优先级越高
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigLayer {
+    /// 内置默认值（含构建时嵌入的值）
+    Default,
+    /// 系统配置文件，由IT统一管理
+    System,
+    /// 用户配置文件
+    User,
+    /// 环境变量
+    Environment,
+    /// 命令行参数
+    CommandLine,
+}
+
+/// 字段的生效来源
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueSource {
+    pub layer: ConfigLayer,
+    /// 具体来源：配置文件路径、环境变量名或命令行参数
+    pub origin: String,
+}
+
+/// 一层配置：只包含该层设置的字段
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub layer: ConfigLayer,
+    pub value: Value,
+    /// 单独记录来源的字段（JSON Pointer），未记录的字段来源为 origin
+    origins: BTreeMap<String, String>,
+    origin: String,
+}
+
+impl Layer {
+    pub fn new(layer: ConfigLayer, value: Value, origin: impl Into<String>) -> Self {
+        Self {
+            layer,
+            value,
+            origins: BTreeMap::new(),
+            origin: origin.into(),
+        }
+    }
+
+    fn empty(layer: ConfigLayer, origin: &str) -> Self {
+        Self::new(layer, Value::Object(Map::new()), origin)
+    }
+
+    fn source(&self, pointer: &str) -> ValueSource {
+        ValueSource {
+            layer: self.layer,
+            origin: self.origins.get(pointer).cloned().unwrap_or_else(|| self.origin.clone()),
+        }
+    }
+
+    /// 按默认配置中对应字段的类型解析文本并设置字段
+    pub fn set_text(&mut self, schema: &Value, pointer: &str, text: &str, origin: &str) {
+        let value = match schema.pointer(pointer) {
+            Some(Value::Number(_)) => match text.parse::<u64>() {
+                Ok(number) => Value::from(number),
+                Err(_) => {
+                    log::warn!("{} 的值不是有效的数字: {}", origin, text);
+                    return;
+                }
+            },
+            Some(Value::Bool(_)) => match text {
+                "true" | "1" => Value::Bool(true),
+                "false" | "0" => Value::Bool(false),
+                _ => {
+                    log::warn!("{} 的值不是有效的布尔值: {}", origin, text);
+                    return;
+                }
+            },
+            Some(Value::String(_)) => Value::String(text.to_string()),
+            // 对象、数组或默认配置中没有的字段按JSON解析，解析失败时作为字符串
+            _ => serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())),
+        };
+
+        set_pointer(&mut self.value, pointer, value);
+        self.origins.insert(pointer.to_string(), origin.to_string());
+    }
+}
+
+/// 系统配置文件路径
+pub fn system_config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(SYSTEM_CONFIG_ENV) {
+        return Some(PathBuf::from(path));
+    }
+
+    if cfg!(windows) {
+        std::env::var_os("ProgramData").map(|dir| PathBuf::from(dir).join("WorkHelper").join("config.json"))
+    } else if cfg!(target_os = "macos") {
+        Some(PathBuf::from("/Library/Application Support/WorkHelper/config.json"))
+    } else {
+        Some(PathBuf::from("/etc/workhelper/config.json"))
+    }
+}
+
+/// 读取环境变量中的配置
+pub fn environment_layer(schema: &Value) -> Layer {
+    let mut layer = Layer::empty(ConfigLayer::Environment, "环境变量");
+
+    for (name, pointer) in LEGACY_ENV {
+        if let Ok(text) = std::env::var(name) {
+            layer.set_text(schema, pointer, &text, name);
+        }
+    }
+    // 只设置了服务器地址时匿名访问，不使用默认配置中的占位账号
+    if std::env::var("WEBDAV_URL").is_ok() {
+        for pointer in ["/webdav/username", "/webdav/password"] {
+            if layer.value.pointer(pointer).is_none() {
+                layer.set_text(schema, pointer, "", "WEBDAV_URL");
+            }
+        }
+    }
+
+    for (name, text) in std::env::vars() {
+        let Some(path) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let segments: Vec<&str> = path.split("__").collect();
+        let pointer = resolve_pointer(schema, &segments);
+        layer.set_text(schema, &pointer, &text, &name);
+    }
+
+    layer
+}
+
+/// 读取命令行参数中的配置（--set 字段路径=值，字段路径用 . 分隔）
+pub fn command_line_layer(schema: &Value, args: impl IntoIterator<Item = String>) -> Layer {
+    let mut layer = Layer::empty(ConfigLayer::CommandLine, "命令行参数");
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let assignment = if arg == SET_ARG {
+            match args.next() {
+                Some(next) => next,
+                None => break,
+            }
+        } else if let Some(assignment) = arg.strip_prefix("--set=") {
+            assignment.to_string()
+        } else {
+            continue;
+        };
+
+        let Some((path, text)) = assignment.split_once('=') else {
+            log::warn!("忽略无效的命令行配置: {}", assignment);
+            continue;
+        };
+        let segments: Vec<&str> = path.split('.').collect();
+        let pointer = resolve_pointer(schema, &segments);
+        layer.set_text(schema, &pointer, text, &format!("{} {}", SET_ARG, assignment));
+    }
+
+    layer
+}
+
+/// 将字段名路径转换为 JSON Pointer，字段名忽略大小写和下划线与默认配置匹配
+fn resolve_pointer(schema: &Value, segments: &[&str]) -> String {
+    let mut pointer = String::new();
+    let mut current = Some(schema);
+
+    for segment in segments {
+        let key = current
+            .and_then(Value::as_object)
+            .and_then(|object| object.keys().find(|key| normalize(key) == normalize(segment)))
+            .cloned()
+            .unwrap_or_else(|| {
+                // 默认配置中没有的字段（如更新源的参数），全大写时按 snake_case 处理
+                if segment.chars().any(|c| c.is_ascii_lowercase()) {
+                    segment.to_string()
+                } else {
+                    segment.to_ascii_lowercase()
+                }
+            });
+        current = current.and_then(|value| value.get(&key));
+        pointer.push('/');
+        pointer.push_str(&key);
+    }
+    pointer
+}
+
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| *c != '_').flat_map(char::to_lowercase).collect()
+}
+
+// ==================== 合并 ====================
+
+/// 逐字段合并各层配置，返回合并结果和各字段的生效来源
+pub fn merge(layers: &[Layer]) -> (Value, BTreeMap<String, ValueSource>) {
+    let mut merged = Value::Object(Map::new());
+    let mut sources = BTreeMap::new();
+
+    for layer in layers {
+        for (pointer, value) in leaves(&layer.value) {
+            set_pointer(&mut merged, &pointer, value.clone());
+            sources.retain(|path: &String, _| !path.starts_with(&format!("{}/", pointer)));
+            sources.insert(pointer.clone(), layer.source(&pointer));
+        }
+    }
+    (merged, sources)
+}
+
+/// 计算保存时写入用户配置文件的内容
+///
+/// - 与更高优先级层（环境变量、命令行）相同的字段不写入，保留用户文件中原有的值
+/// - 用户文件中没有、且与更低优先级层相同的字段不写入，继续跟随系统配置和默认值
+/// - 其余字段写入用户文件
+pub fn user_overrides(new: &Value, user: &Value, below: &Value, above: &Value) -> Value {
+    let mut result = Value::Object(Map::new());
+
+    for (pointer, value) in leaves(new) {
+        let keep = if above.pointer(&pointer) == Some(value) {
+            user.pointer(&pointer).cloned()
+        } else if user.pointer(&pointer).is_none() && below.pointer(&pointer) == Some(value) {
+            None
+        } else {
+            Some(value.clone())
+        };
+
+        if let Some(keep) = keep {
+            set_pointer(&mut result, &pointer, keep);
+        }
+    }
+    result
+}
+
+/// 列出所有字段（JSON Pointer 与值）
+///
+/// 带 type 标记的对象（如更新源）作为一个整体，避免不同类型的字段混在一起
+pub fn leaves(value: &Value) -> Vec<(String, &Value)> {
+    fn walk<'a>(value: &'a Value, pointer: String, result: &mut Vec<(String, &'a Value)>) {
+        match value {
+            Value::Object(object) if !object.contains_key("type") || pointer.is_empty() => {
+                for (key, child) in object {
+                    walk(child, format!("{}/{}", pointer, key), result);
+                }
+            }
+            _ => result.push((pointer, value)),
+        }
+    }
+
+    let mut result = Vec::new();
+    walk(value, String::new(), &mut result);
+    result
+}
+
+/// 按 JSON Pointer 设置字段，自动创建中间对象
+pub fn set_pointer(root: &mut Value, pointer: &str, value: Value) {
+    let mut current = root;
+    let mut segments = pointer.trim_start_matches('/').split('/').peekable();
+
+    while let Some(segment) = segments.next() {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        let object = current.as_object_mut().expect("已确保为对象");
+        if segments.peek().is_none() {
+            object.insert(segment.to_string(), value);
+            return;
+        }
+        current = object.entry(segment.to_string()).or_insert_with(|| Value::Object(Map::new()));
+    }
+}
+
+/// 按 JSON Pointer 删除字段，返回删除的值
+pub fn remove_pointer(root: &mut Value, pointer: &str) -> Option<Value> {
+    let (parent, key) = pointer.rsplit_once('/')?;
+    let parent = if parent.is_empty() { Some(root) } else { root.pointer_mut(parent) };
+    parent?.as_object_mut()?.remove(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "general": { "oaUrl": "", "model": "", "timeoutSecs": 30, "autoStart": false },
+            "update": { "source": { "type": "webdav", "url": "" } }
+        })
+    }
+
+    #[test]
+    fn merge_prefers_higher_layer_per_field() {
+        let layers = [
+            Layer::new(ConfigLayer::Default, schema(), "内置默认值"),
+            Layer::new(ConfigLayer::System, json!({ "general": { "oaUrl": "https://system" } }), "system.json"),
+            Layer::new(ConfigLayer::User, json!({ "general": { "oaUrl": "https://user", "model": "m" } }), "user.json"),
+        ];
+        let (merged, sources) = merge(&layers);
+
+        assert_eq!(merged["general"]["oaUrl"], "https://user");
+        assert_eq!(merged["general"]["model"], "m");
+        assert_eq!(merged["general"]["timeoutSecs"], 30);
+        assert_eq!(sources["/general/oaUrl"].layer, ConfigLayer::User);
+        assert_eq!(sources["/general/oaUrl"].origin, "user.json");
+        assert_eq!(sources["/general/timeoutSecs"].layer, ConfigLayer::Default);
+    }
+
+    #[test]
+    fn merge_records_per_field_origin() {
+        let mut environment = Layer::empty(ConfigLayer::Environment, "环境变量");
+        environment.set_text(&schema(), "/general/timeoutSecs", "60", "WORKHELPER__GENERAL__TIMEOUT_SECS");
+        let layers = [Layer::new(ConfigLayer::Default, schema(), "内置默认值"), environment];
+        let (merged, sources) = merge(&layers);
+
+        assert_eq!(merged["general"]["timeoutSecs"], 60);
+        assert_eq!(sources["/general/timeoutSecs"].layer, ConfigLayer::Environment);
+        assert_eq!(sources["/general/timeoutSecs"].origin, "WORKHELPER__GENERAL__TIMEOUT_SECS");
+        assert_eq!(sources["/general/oaUrl"].origin, "内置默认值");
+    }
+
+    #[test]
+    fn merge_replaces_tagged_objects_as_a_whole() {
+        let layers = [
+            Layer::new(ConfigLayer::Default, schema(), "内置默认值"),
+            Layer::new(
+                ConfigLayer::User,
+                json!({ "update": { "source": { "type": "local", "path": "/srv/updates" } } }),
+                "user.json",
+            ),
+        ];
+        let (merged, sources) = merge(&layers);
+
+        assert_eq!(merged["update"]["source"], json!({ "type": "local", "path": "/srv/updates" }));
+        assert_eq!(sources["/update/source"].layer, ConfigLayer::User);
+    }
+
+    #[test]
+    fn user_overrides_skips_values_from_higher_layers() {
+        let new = json!({ "general": { "oaUrl": "https://env", "model": "m" } });
+        let above = json!({ "general": { "oaUrl": "https://env" } });
+
+        // 用户文件中原有的值保留，没有的字段不写入
+        let user = json!({ "general": { "oaUrl": "https://user" } });
+        let result = user_overrides(&new, &user, &json!({}), &above);
+        assert_eq!(result, json!({ "general": { "oaUrl": "https://user", "model": "m" } }));
+
+        let result = user_overrides(&new, &json!({}), &json!({}), &above);
+        assert_eq!(result, json!({ "general": { "model": "m" } }));
+    }
+
+    #[test]
+    fn user_overrides_follows_lower_layers_unless_user_set() {
+        let new = json!({ "general": { "oaUrl": "https://system", "model": "m" } });
+        let below = json!({ "general": { "oaUrl": "https://system", "model": "m" } });
+
+        // 用户文件中没有且与更低层相同的字段继续跟随更低层
+        let result = user_overrides(&new, &json!({}), &below, &json!({}));
+        assert_eq!(result, json!({}));
+
+        // 用户文件中已有的字段即使与更低层相同也保留
+        let user = json!({ "general": { "model": "m" } });
+        let result = user_overrides(&new, &user, &below, &json!({}));
+        assert_eq!(result, json!({ "general": { "model": "m" } }));
+    }
+
+    #[test]
+    fn user_overrides_writes_changed_values() {
+        let new = json!({ "general": { "oaUrl": "https://new" } });
+        let below = json!({ "general": { "oaUrl": "https://system" } });
+        let above = json!({ "general": { "model": "m" } });
+
+        let result = user_overrides(&new, &json!({}), &below, &above);
+        assert_eq!(result, json!({ "general": { "oaUrl": "https://new" } }));
+    }
+
+    #[test]
+    fn resolve_pointer_matches_schema_names() {
+        assert_eq!(resolve_pointer(&schema(), &["GENERAL", "OA_URL"]), "/general/oaUrl");
+        assert_eq!(resolve_pointer(&schema(), &["general", "oaurl"]), "/general/oaUrl");
+        // 默认配置中没有的字段：全大写时转为 snake_case，否则原样保留
+        assert_eq!(resolve_pointer(&schema(), &["UPDATE", "SOURCE", "BASE_DIR"]), "/update/source/base_dir");
+        assert_eq!(resolve_pointer(&schema(), &["update", "source", "baseDir"]), "/update/source/baseDir");
+    }
+
+    #[test]
+    fn set_text_parses_by_schema_type() {
+        let mut layer = Layer::empty(ConfigLayer::CommandLine, "命令行参数");
+        layer.set_text(&schema(), "/general/timeoutSecs", "45", "--set");
+        layer.set_text(&schema(), "/general/autoStart", "1", "--set");
+        layer.set_text(&schema(), "/general/oaUrl", "123", "--set");
+        layer.set_text(&schema(), "/update/source", r#"{"type":"local","path":"/srv"}"#, "--set");
+
+        assert_eq!(layer.value["general"]["timeoutSecs"], 45);
+        assert_eq!(layer.value["general"]["autoStart"], true);
+        assert_eq!(layer.value["general"]["oaUrl"], "123");
+        assert_eq!(layer.value["update"]["source"]["type"], "local");
+    }
+
+    #[test]
+    fn set_text_ignores_invalid_values() {
+        let mut layer = Layer::empty(ConfigLayer::Environment, "环境变量");
+        layer.set_text(&schema(), "/general/timeoutSecs", "abc", "WORKHELPER__GENERAL__TIMEOUT_SECS");
+        layer.set_text(&schema(), "/general/autoStart", "yes", "WORKHELPER__GENERAL__AUTO_START");
+
+        assert_eq!(layer.value, json!({}));
+    }
+
+    #[test]
+    fn command_line_layer_reads_set_arguments() {
+        let args = ["--set", "general.oaUrl=https://cli", "--verbose", "--set=general.timeoutSecs=10", "--set", "invalid"];
+        let layer = command_line_layer(&schema(), args.iter().map(|arg| arg.to_string()));
+
+        assert_eq!(layer.value, json!({ "general": { "oaUrl": "https://cli", "timeoutSecs": 10 } }));
+        assert_eq!(layer.source("/general/oaUrl").origin, "--set general.oaUrl=https://cli");
+    }
+}
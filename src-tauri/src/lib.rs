@@ -7,7 +7,71 @@ use tokio::io::AsyncWriteExt;
 
 // 配置模块
 mod config;
-use config::get_webdav_config;
+use config::{get_webdav_config, load_config, save_config};
+
+// 配置分层模块
+mod layers;
+
+// 配置文件监听模块
+mod config_watcher;
+
+// 凭据存储模块
+mod secrets;
+
+// 基础设置模块
+mod settings;
+
+// 配置档案模块
+mod profiles;
+
+// 配置包模块
+mod bundle;
+
+// HTTP客户端模块
+mod http_client;
+
+// HTTP请求策略模块
+mod http_policy;
+
+// HTTP代发模块
+mod http_proxy;
+
+// Cookie 存储模块
+mod cookies;
+
+// 版本号比较模块
+mod version;
+
+// 更新清单模块
+mod manifest;
+
+// 灰度发布模块
+mod rollout;
+
+// 安装历史模块
+mod history;
+
+// 差分更新模块
+mod delta;
+
+// 更新源模块
+mod update_source;
+
+// WebDAV客户端模块
+pub mod webdav;
+
+// 设置同步模块
+mod sync;
+
+// 发布模块（workhelper-release 命令）
+pub mod release;
+
+// 后台更新检查模块
+mod scheduler;
+use version::is_newer_version;
+
+// 断点续传模块
+mod download;
 
 // ==================== 更新相关数据结构 ====================
 
@@ -19,10 +83,20 @@ pub struct VersionInfo {
     pub release_date: String,
     pub changelog: String,
     pub download_url: String,
+    /// 备用下载地址，主地址下载失败时按顺序尝试
+    pub mirrors: Vec<String>,
     pub file_size: u64,
     pub file_size_formatted: String,
     pub platform: String,
+    pub arch: String,
+    pub file_name: String,
     pub file_type: String,
+    pub format: manifest::ArtifactFormat,
+    pub channel: String,
+    /// 完整安装包的SHA-256
+    pub sha256: Option<String>,
+    /// 可用的差分补丁，存在时优先下载补丁
+    pub delta: Option<delta::DeltaInfo>,
 }
 
 /// 更新检查结果
@@ -33,6 +107,14 @@ pub struct UpdateCheckResult {
     pub current_version: String,
     pub latest_version: Option<String>,
     pub version_info: Option<VersionInfo>,
+    /// 当前版本低于最低版本或已被禁用，必须更新
+    pub mandatory: bool,
+    /// 当前安装实例是否在灰度推送范围内
+    pub rollout_eligible: bool,
+    /// 最新版本没有适用于当前平台的安装包
+    pub no_artifact_for_platform: bool,
+    /// 当前平台标识，例如 "windows-x86_64"
+    pub platform: String,
     pub error: Option<String>,
 }
 
@@ -67,8 +149,23 @@ pub struct DownloadResult {
     pub status: DownloadStatus,
     pub file_path: Option<String>,
     pub error: Option<String>,
+    /// 实际提供文件的下载地址（主地址或镜像）
+    pub served_by: Option<String>,
 }
 
+/// 下载状态事件，下载结束（完成、失败或取消）时发送
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadStatusEvent {
+    pub download_id: String,
+    pub status: DownloadStatus,
+    pub error: Option<String>,
+    pub served_by: Option<String>,
+}
+
+/// 下载过程中保存断点续传进度的间隔（字节）
+const PROGRESS_SAVE_INTERVAL: u64 = 1024 * 1024;
+
 // ==================== Tauri 命令实现 ====================
 
 // Tauri 命令：选择目录
@@ -196,49 +293,121 @@ fn get_app_version() -> String {
 // Tauri 命令：检查更新
 #[tauri::command]
 async fn check_for_updates() -> Result<UpdateCheckResult, String> {
+    Ok(run_update_check().await)
+}
+
+// 检查更新，前端手动检查和后台定时检查共用
+async fn run_update_check() -> UpdateCheckResult {
     let current_version = env!("CARGO_PKG_VERSION").to_string();
+    let platform = manifest::Platform::current().label();
 
     log::info!("开始检查更新，当前版本: {}", current_version);
 
     // 从WebDAV服务器获取最新版本信息
     match fetch_latest_version_info().await {
-        Ok(version_info) => {
-            // 比较版本号
-            let has_update = is_newer_version(&current_version, &version_info.version);
+        Ok((version_info, policy)) => {
+            let rollout::UpdateDecision { has_update, mandatory, rollout_eligible } =
+                policy.evaluate(&current_version, &version_info.version, &rollout::install_id());
 
             if has_update {
-                log::info!("发现新版本: {} -> {}", current_version, version_info.version);
-                Ok(UpdateCheckResult {
+                log::info!("发现新版本: {} -> {} (强制更新: {})", current_version, version_info.version, mandatory);
+                UpdateCheckResult {
                     has_update: true,
                     current_version,
                     latest_version: Some(version_info.version.clone()),
                     version_info: Some(version_info),
+                    mandatory,
+                    rollout_eligible,
+                    no_artifact_for_platform: false,
+                    platform,
                     error: None,
-                })
+                }
             } else {
-                log::info!("当前已是最新版本: {}", current_version);
-                Ok(UpdateCheckResult {
+                if is_newer_version(&current_version, &version_info.version) {
+                    log::info!(
+                        "新版本 {} 正在灰度发布（{}%），当前安装实例暂不推送",
+                        version_info.version, policy.rollout_percentage
+                    );
+                } else {
+                    log::info!("当前已是最新版本: {}", current_version);
+                }
+                UpdateCheckResult {
                     has_update: false,
                     current_version,
                     latest_version: Some(version_info.version),
                     version_info: None,
+                    mandatory: false,
+                    rollout_eligible,
+                    no_artifact_for_platform: false,
+                    platform,
                     error: None,
-                })
+                }
+            }
+        }
+        Err(FetchError::NoArtifact { version, .. }) => {
+            // 只有版本更新时才需要提示用户当前平台暂无安装包
+            let no_artifact_for_platform = is_newer_version(&current_version, &version);
+            if no_artifact_for_platform {
+                log::warn!("新版本 {} 没有适用于当前平台（{}）的安装包", version, platform);
+            } else {
+                log::info!("当前已是最新版本: {}", current_version);
+            }
+            UpdateCheckResult {
+                has_update: false,
+                current_version,
+                latest_version: Some(version),
+                version_info: None,
+                mandatory: false,
+                rollout_eligible: false,
+                no_artifact_for_platform,
+                platform,
+                error: None,
             }
         }
         Err(error) => {
             log::error!("检查更新失败: {}", error);
-            Ok(UpdateCheckResult {
+            UpdateCheckResult {
                 has_update: false,
                 current_version,
                 latest_version: None,
                 version_info: None,
-                error: Some(error),
-            })
+                mandatory: false,
+                rollout_eligible: false,
+                no_artifact_for_platform: false,
+                platform,
+                error: Some(error.to_string()),
+            }
         }
     }
 }
 
+// Tauri 命令：跳过指定版本，后台检查不再提示该版本
+#[tauri::command]
+async fn skip_update_version(version: String) -> Result<(), String> {
+    let mut config = load_config();
+    log::info!("跳过版本: {}", version);
+    config.update.skipped_version = Some(version);
+    config.update.remind_after = None;
+    save_config(&config)
+}
+
+// Tauri 命令：稍后提醒，在指定小时数（默认24小时）内后台检查不再提示
+#[tauri::command]
+async fn remind_update_later(hours: Option<u64>) -> Result<(), String> {
+    let hours = hours.unwrap_or(24);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut config = load_config();
+    config.update.remind_after = Some(now + hours * 3600);
+    save_config(&config)?;
+
+    log::info!("{} 小时后再提醒更新", hours);
+    Ok(())
+}
+
 // Tauri 命令：最小化窗口
 #[tauri::command]
 async fn window_minimize(window: tauri::Window) -> Result<(), String> {
@@ -251,124 +420,129 @@ async fn window_close(window: tauri::Window) -> Result<(), String> {
     window.close().map_err(|e| e.to_string())
 }
 
-// 从WebDAV服务器获取最新版本信息
-async fn fetch_latest_version_info() -> Result<VersionInfo, String> {
-    // 获取WebDAV配置
-    let webdav_config = get_webdav_config();
-    let latest_json_url = format!("{}{}/latest.json", webdav_config.url, webdav_config.remote_path);
-
-    // 创建HTTP客户端
-    let client = reqwest::Client::new();
+/// 获取最新版本信息失败的原因
+#[derive(Debug)]
+enum FetchError {
+    /// 最新版本没有适用于当前平台的安装包
+    NoArtifact { version: String, platform: String },
+    Other(String),
+}
 
-    // 构建认证头
-    use base64::{Engine as _, engine::general_purpose};
-    let auth = general_purpose::STANDARD.encode(format!("{}:{}", webdav_config.username, webdav_config.password));
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::NoArtifact { version, platform } => {
+                write!(f, "版本 {} 没有适用于当前平台（{}）的安装包", version, platform)
+            }
+            FetchError::Other(message) => f.write_str(message),
+        }
+    }
+}
 
-    // 发送请求获取版本信息
-    let response = client
-        .get(&latest_json_url)
-        .header("Authorization", format!("Basic {}", auth))
-        .send()
-        .await
-        .map_err(|e| format!("请求版本信息失败: {}", e))?;
+impl From<String> for FetchError {
+    fn from(message: String) -> Self {
+        FetchError::Other(message)
+    }
+}
 
-    if !response.status().is_success() {
-        return Err(format!("获取版本信息失败，状态码: {}", response.status()));
+impl From<&str> for FetchError {
+    fn from(message: &str) -> Self {
+        FetchError::Other(message.to_string())
     }
+}
 
-    // 解析JSON响应
-    let version_data: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| format!("解析版本信息失败: {}", e))?;
+// 从配置的更新源获取最新版本信息及其发布策略
+async fn fetch_latest_version_info() -> Result<(VersionInfo, rollout::UpdatePolicy), FetchError> {
+    let config = load_config();
+    let source = update_source::from_config(&config.update.source, get_webdav_config());
+    log::info!("从更新源获取版本信息: {}", source.name());
+
+    let version_data = source.fetch_manifest().await?;
+    let base_url = source.base_url();
+
+    // 按配置的更新通道选择版本条目
+    let channel = config.update.channel;
+    let (release, parsed_version) = version::select_release(&version_data, channel)?;
 
     // 提取版本信息
-    let version = version_data["version"]
+    let version = release["version"]
         .as_str()
         .ok_or("版本号字段缺失")?
         .to_string();
 
-    let release_date = version_data["releaseDate"]
+    let release_date = release["releaseDate"]
         .as_str()
         .unwrap_or("")
         .to_string();
 
-    let changelog = version_data["changelog"]
+    let changelog = release["changelog"]
         .as_str()
         .unwrap_or("本次更新无代码变更")
         .to_string();
 
-    // 构建Windows平台的下载URL
-    let download_url = format!(
-        "{}/v{}/windows/WorkHelper_{}_x64-setup.exe",
-        webdav_config.base_url, version, version
-    );
+    // 选择适用于当前平台的安装包
+    let platform = manifest::Platform::current();
+    let artifact = manifest::select_artifact(release, &version, &platform)?.ok_or_else(|| {
+        FetchError::NoArtifact {
+            version: version.clone(),
+            platform: platform.label(),
+        }
+    })?;
+    let download_url = artifact.resolve_url(&base_url);
+    let mirrors = artifact.mirror_urls(&base_url, &manifest::manifest_mirrors(&version_data));
 
     // 尝试从JSON中获取文件大小，如果没有则从服务器获取
-    let (file_size, file_size_formatted) = if let (Some(size), Some(formatted)) = (
-        version_data["fileSize"].as_u64(),
-        version_data["fileSizeFormatted"].as_str()
-    ) {
-        (size, formatted.to_string())
-    } else {
+    let (file_size, file_size_formatted) = match artifact.file_size {
+        Some(size) => (
+            size,
+            artifact.file_size_formatted.clone().unwrap_or_else(|| format_file_size(size)),
+        ),
         // 如果JSON中没有文件大小信息，则从服务器获取
-        get_file_size(&client, &download_url, &auth).await
+        None => source.file_size(&download_url).await,
     };
 
-    Ok(VersionInfo {
+    let policy = rollout::UpdatePolicy::from_manifest(&version_data, release);
+
+    // 本地保留了补丁基础版本的安装包时，可以只下载差分补丁
+    let delta = get_updates_directory()
+        .ok()
+        .and_then(|updates_dir| delta::select_delta(&artifact, &updates_dir, &base_url));
+
+    let version_info = VersionInfo {
+        file_name: artifact.resolve_file_name(&version),
         version,
         release_date,
         changelog,
         download_url,
+        mirrors,
         file_size,
         file_size_formatted,
-        platform: "windows".to_string(),
-        file_type: "exe".to_string(),
-    })
-}
-
-// 比较版本号，判断是否有新版本
-fn is_newer_version(current: &str, latest: &str) -> bool {
-    // 简单的版本号比较，实际项目中建议使用semver库
-    let current_parts: Vec<u32> = current
-        .split('.')
-        .filter_map(|s| s.parse().ok())
-        .collect();
-
-    let latest_parts: Vec<u32> = latest
-        .split('.')
-        .filter_map(|s| s.parse().ok())
-        .collect();
-
-    // 补齐版本号位数
-    let max_len = current_parts.len().max(latest_parts.len());
-    let mut current_padded = current_parts;
-    let mut latest_padded = latest_parts;
+        platform: platform.os,
+        arch: platform.arch,
+        file_type: artifact.format.extension().to_string(),
+        format: artifact.format,
+        channel: version::channel_of(&parsed_version).as_str().to_string(),
+        sha256: artifact.sha256.clone(),
+        delta,
+    };
 
-    current_padded.resize(max_len, 0);
-    latest_padded.resize(max_len, 0);
+    Ok((version_info, policy))
+}
 
-    // 逐位比较
-    for (c, l) in current_padded.iter().zip(latest_padded.iter()) {
-        if l > c {
-            return true;
-        } else if l < c {
-            return false;
-        }
+// 提供认证信息时添加 Authorization 请求头
+fn with_authorization(request: reqwest::RequestBuilder, authorization: Option<&str>) -> reqwest::RequestBuilder {
+    match authorization {
+        Some(authorization) => request.header("Authorization", authorization),
+        None => request,
     }
-
-    false
 }
 
-// 获取文件大小
-async fn get_file_size(client: &reqwest::Client, url: &str, auth: &str) -> (u64, String) {
+// 获取文件大小，authorization 为完整的 Authorization 请求头
+async fn get_file_size(http: &http_client::HttpClient, url: &str, authorization: Option<&str>) -> (u64, String) {
+    let client = http.client();
+
     // 首先尝试HEAD请求
-    if let Ok(response) = client
-        .head(url)
-        .header("Authorization", format!("Basic {}", auth))
-        .send()
-        .await
-    {
+    if let Ok(response) = http.send(with_authorization(client.head(url), authorization)).await {
         if response.status().is_success() {
             if let Some(content_length) = response.headers().get("content-length") {
                 if let Ok(content_length_str) = content_length.to_str() {
@@ -381,11 +555,8 @@ async fn get_file_size(client: &reqwest::Client, url: &str, auth: &str) -> (u64,
     }
 
     // 如果HEAD请求失败，尝试Range请求获取文件大小
-    if let Ok(response) = client
-        .get(url)
-        .header("Authorization", format!("Basic {}", auth))
-        .header("Range", "bytes=0-0")
-        .send()
+    if let Ok(response) = http
+        .send(with_authorization(client.get(url), authorization).header("Range", "bytes=0-0"))
         .await
     {
         if response.status().as_u16() == 206 { // Partial Content
@@ -403,16 +574,13 @@ async fn get_file_size(client: &reqwest::Client, url: &str, auth: &str) -> (u64,
     }
 
     // 尝试WebDAV PROPFIND请求
-    if let Some((size, formatted)) = get_file_size_webdav(client, url, auth).await {
+    if let Some((size, formatted)) = get_file_size_webdav(http, url, authorization).await {
         return (size, formatted);
     }
 
     // 如果都失败了，尝试下载前1KB来验证文件存在
-    if let Ok(response) = client
-        .get(url)
-        .header("Authorization", format!("Basic {}", auth))
-        .header("Range", "bytes=0-1023")
-        .send()
+    if let Ok(response) = http
+        .send(with_authorization(client.get(url), authorization).header("Range", "bytes=0-1023"))
         .await
     {
         if response.status().is_success() {
@@ -426,52 +594,15 @@ async fn get_file_size(client: &reqwest::Client, url: &str, auth: &str) -> (u64,
 }
 
 // 使用WebDAV PROPFIND获取文件大小
-async fn get_file_size_webdav(client: &reqwest::Client, url: &str, auth: &str) -> Option<(u64, String)> {
-    let propfind_body = r#"<?xml version="1.0" encoding="utf-8" ?>
-<D:propfind xmlns:D="DAV:">
-    <D:prop>
-        <D:getcontentlength/>
-    </D:prop>
-</D:propfind>"#;
-
-    match client
-        .request(reqwest::Method::from_bytes(b"PROPFIND").ok()?, url)
-        .header("Authorization", format!("Basic {}", auth))
-        .header("Content-Type", "application/xml")
-        .header("Depth", "0")
-        .body(propfind_body)
-        .send()
-        .await
-    {
-        Ok(response) => {
-            if response.status().is_success() {
-                if let Ok(body) = response.text().await {
-                    // 简单的XML解析，查找getcontentlength
-                    if let Some(start) = body.find("<D:getcontentlength>") {
-                        if let Some(end) = body[start..].find("</D:getcontentlength>") {
-                            let size_str = &body[start + 20..start + end];
-                            if let Ok(size) = size_str.parse::<u64>() {
-                                return Some((size, format_file_size(size)));
-                            }
-                        }
-                    }
-
-                    // 尝试其他可能的标签格式
-                    if let Some(start) = body.find("<getcontentlength>") {
-                        if let Some(end) = body[start..].find("</getcontentlength>") {
-                            let size_str = &body[start + 18..start + end];
-                            if let Ok(size) = size_str.parse::<u64>() {
-                                return Some((size, format_file_size(size)));
-                            }
-                        }
-                    }
-                }
-            }
+async fn get_file_size_webdav(http: &http_client::HttpClient, url: &str, authorization: Option<&str>) -> Option<(u64, String)> {
+    let webdav = webdav::WebDavClient::new(http.clone(), "", authorization.map(|a| a.to_string()));
+    match webdav.stat(url).await {
+        Ok(resource) => resource.content_length.map(|size| (size, format_file_size(size))),
+        Err(e) => {
+            log::debug!("PROPFIND获取文件大小失败: {}", e);
+            None
         }
-        Err(_) => {}
     }
-
-    None
 }
 
 // 格式化文件大小
@@ -500,54 +631,426 @@ fn health_check() -> Result<String, String> {
 }
 
 // Tauri 命令：下载更新包
+// 下载内容先写入 .part 临时文件，中断后再次调用时使用Range请求继续下载，完成后再重命名为正式文件
+// 下载任务按 download_id（默认为文件名）登记，可通过 cancel_download 取消
+// 同一更新包已在下载（如后台预下载）时取消其限速并等待它结束，进度照常通过 download-progress 事件发送；
+// 它未能完成时由本次请求继续下载（从保存的进度续传）
+// 提供 delta 时先尝试下载差分补丁并在本地还原，失败时回退为下载完整安装包；提供 sha256 时校验最终文件
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 async fn download_update(
     app: tauri::AppHandle,
+    manager: tauri::State<'_, download::DownloadManager>,
     download_url: String,
+    mirrors: Option<Vec<String>>,
     file_name: String,
     expected_size: u64,
+    download_id: Option<String>,
+    sha256: Option<String>,
+    delta: Option<delta::DeltaInfo>,
+) -> Result<DownloadResult, String> {
+    let download_id = download_id.unwrap_or_else(|| file_name.clone());
+    let active = loop {
+        let running = match manager.register_or_attach(&download_id, None)? {
+            download::Registration::Started(active) => break active,
+            download::Registration::Running(running) => running,
+        };
+
+        log::info!("更新包正在下载中，等待其完成: {}", download_id);
+        running.remove_rate_limit();
+        running.wait_finished().await;
+
+        if running.token.is_cancelled() {
+            return Ok(DownloadResult {
+                status: DownloadStatus::Cancelled,
+                file_path: None,
+                error: None,
+                served_by: None,
+            });
+        }
+        let file_path = update_file_path(&get_updates_directory()?, &file_name)?;
+        if file_path.exists() {
+            return Ok(DownloadResult {
+                status: DownloadStatus::Completed,
+                file_path: Some(file_path.to_string_lossy().to_string()),
+                error: None,
+                served_by: None,
+            });
+        }
+    };
+
+    let result = download_update_package(
+        &app,
+        &active,
+        &download_url,
+        &mirrors.unwrap_or_default(),
+        &file_name,
+        expected_size,
+        sha256.as_deref(),
+        delta.as_ref(),
+    )
+    .await;
+    manager.finish(&download_id);
+
+    // 通知前端下载的最终状态
+    if let Ok(result) = &result {
+        let status = DownloadStatusEvent {
+            download_id: download_id.clone(),
+            status: result.status.clone(),
+            error: result.error.clone(),
+            served_by: result.served_by.clone(),
+        };
+        if let Err(e) = app.emit("download-status", &status) {
+            log::warn!("发送下载状态事件失败: {}", e);
+        }
+    }
+
+    result
+}
+
+// 下载更新包：优先使用差分补丁，失败时回退为完整下载，并校验最终文件哈希
+#[allow(clippy::too_many_arguments)]
+async fn download_update_package(
+    app: &tauri::AppHandle,
+    active: &download::ActiveDownload,
+    download_url: &str,
+    mirrors: &[String],
+    file_name: &str,
+    expected_size: u64,
+    sha256: Option<&str>,
+    delta: Option<&delta::DeltaInfo>,
+) -> Result<DownloadResult, String> {
+    if let (Some(delta), Some(sha256)) = (delta, sha256) {
+        match apply_delta_update(app, active, delta, file_name, sha256).await {
+            Ok(result) if !matches!(result.status, DownloadStatus::Failed) => return Ok(result),
+            Ok(result) => log::warn!(
+                "差分更新失败，改为下载完整安装包: {}",
+                result.error.unwrap_or_default()
+            ),
+            Err(e) => log::warn!("差分更新失败，改为下载完整安装包: {}", e),
+        }
+    }
+
+    let result = download_with_failover(app, active, download_url, mirrors, file_name, expected_size).await?;
+
+    // 校验完整安装包的哈希
+    if let (DownloadStatus::Completed, Some(expected), Some(path)) = (&result.status, sha256, &result.file_path) {
+        let path = PathBuf::from(path);
+        let actual = delta::sha256_file(&path).await?;
+        if !delta::hash_matches(&actual, expected) {
+            let _ = tokio::fs::remove_file(&path).await;
+            return Ok(DownloadResult {
+                status: DownloadStatus::Failed,
+                file_path: None,
+                error: Some("安装包校验失败，文件可能已损坏，请重新下载".to_string()),
+                served_by: None,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+// 下载差分补丁并应用到本地保留的旧版本安装包上，还原前后都会校验哈希
+async fn apply_delta_update(
+    app: &tauri::AppHandle,
+    active: &download::ActiveDownload,
+    delta: &delta::DeltaInfo,
+    file_name: &str,
+    sha256: &str,
+) -> Result<DownloadResult, String> {
+    let updates_dir = get_updates_directory()?;
+    let file_path = update_file_path(&updates_dir, file_name)?;
+
+    // 校验基础安装包
+    let base_path = history::installer_for_version(&updates_dir, &delta.base_version)
+        .ok_or_else(|| format!("版本 {} 的安装包已不存在", delta.base_version))?;
+    let base_hash = delta::sha256_file(&base_path).await?;
+    if !delta::hash_matches(&base_hash, &delta.base_sha256) {
+        return Err(format!("基础安装包校验失败: {}", base_path.display()));
+    }
+
+    // 下载补丁文件
+    log::info!("下载差分补丁: {} -> {}", delta.base_version, file_name);
+    let patch_name = format!("{}.patch", file_name);
+    let authorization = update_source::download_authorization(&delta.url);
+    let result = perform_download(
+        app,
+        active,
+        &delta.url,
+        authorization.as_deref(),
+        &patch_name,
+        delta.file_size,
+    )
+    .await?;
+    if !matches!(result.status, DownloadStatus::Completed) {
+        return Ok(result);
+    }
+
+    let patch_path = update_file_path(&updates_dir, &patch_name)?;
+    let patch_hash = delta::sha256_file(&patch_path).await?;
+    if !delta::hash_matches(&patch_hash, &delta.sha256) {
+        let _ = tokio::fs::remove_file(&patch_path).await;
+        return Err("差分补丁校验失败".to_string());
+    }
+
+    // 应用补丁并校验还原出的安装包
+    let old = tokio::fs::read(&base_path)
+        .await
+        .map_err(|e| format!("读取基础安装包失败: {}", e))?;
+    let patch = tokio::fs::read(&patch_path)
+        .await
+        .map_err(|e| format!("读取差分补丁失败: {}", e))?;
+    let _ = tokio::fs::remove_file(&patch_path).await;
+
+    let new = tokio::task::spawn_blocking(move || delta::apply_patch(&old, &patch))
+        .await
+        .map_err(|e| format!("应用差分补丁失败: {}", e))??;
+
+    if !delta::hash_matches(&delta::sha256_hex(&new), sha256) {
+        return Err("差分还原后的安装包校验失败".to_string());
+    }
+
+    // 先写入临时文件再重命名，避免留下不完整的安装包
+    tokio::fs::write(download::part_path(&file_path), &new)
+        .await
+        .map_err(|e| format!("写入安装包失败: {}", e))?;
+    download::finalize(&file_path).await?;
+
+    log::info!("差分更新完成: {}", file_path.display());
+
+    Ok(DownloadResult {
+        status: DownloadStatus::Completed,
+        file_path: Some(file_path.to_string_lossy().to_string()),
+        error: None,
+        served_by: None,
+    })
+}
+
+// 从本地目录或SMB共享复制更新包
+async fn copy_local_package(
+    app: &tauri::AppHandle,
+    source_path: &std::path::Path,
+    file_path: &std::path::Path,
+) -> Result<DownloadResult, String> {
+    log::info!("从本地路径复制更新包: {}", source_path.display());
+
+    let copied = match tokio::fs::copy(source_path, download::part_path(file_path)).await {
+        Ok(copied) => copied,
+        Err(e) => {
+            return Ok(DownloadResult {
+                status: DownloadStatus::Failed,
+                file_path: None,
+                error: Some(format!("复制更新包失败: {} - {}", source_path.display(), e)),
+                served_by: None,
+            });
+        }
+    };
+    download::finalize(file_path).await?;
+
+    let progress = DownloadProgress {
+        downloaded: copied,
+        total: copied,
+        percentage: 100.0,
+        speed: format_speed(0.0),
+        remaining_time: format_time(0.0),
+    };
+    if let Err(e) = app.emit("download-progress", &progress) {
+        log::warn!("发送下载进度事件失败: {}", e);
+    }
+
+    Ok(DownloadResult {
+        status: DownloadStatus::Completed,
+        file_path: Some(file_path.to_string_lossy().to_string()),
+        error: None,
+        served_by: None,
+    })
+}
+
+// 依次尝试主地址和各镜像地址，直到下载完成或被取消
+async fn download_with_failover(
+    app: &tauri::AppHandle,
+    active: &download::ActiveDownload,
+    download_url: &str,
+    mirrors: &[String],
+    file_name: &str,
+    expected_size: u64,
+) -> Result<DownloadResult, String> {
+    let mut last_error = String::new();
+
+    for url in std::iter::once(download_url).chain(mirrors.iter().map(String::as_str)) {
+        // 与更新源同一服务器的地址沿用更新源的认证信息
+        let authorization = update_source::download_authorization(url);
+
+        match perform_download(app, active, url, authorization.as_deref(), file_name, expected_size).await {
+            Ok(result) if !matches!(result.status, DownloadStatus::Failed) => {
+                if let DownloadStatus::Completed = result.status {
+                    log::info!("更新包下载完成，下载地址: {}", url);
+                }
+                return Ok(DownloadResult {
+                    served_by: Some(url.to_string()),
+                    ..result
+                });
+            }
+            Ok(result) => last_error = result.error.unwrap_or_default(),
+            Err(e) => last_error = e,
+        }
+        log::warn!("从 {} 下载失败: {}", url, last_error);
+    }
+
+    if !mirrors.is_empty() {
+        last_error = format!("所有下载地址均不可用: {}", last_error);
+    }
+    Ok(DownloadResult {
+        status: DownloadStatus::Failed,
+        file_path: None,
+        error: Some(last_error),
+        served_by: None,
+    })
+}
+
+// 执行下载，取消令牌触发时停止并按取消策略处理临时文件
+async fn perform_download(
+    app: &tauri::AppHandle,
+    active: &download::ActiveDownload,
+    download_url: &str,
+    authorization: Option<&str>,
+    file_name: &str,
+    expected_size: u64,
 ) -> Result<DownloadResult, String> {
     log::info!("开始下载更新包: {}", file_name);
 
     // 获取下载目录
     let download_dir = get_updates_directory()?;
-    let file_path = download_dir.join(&file_name);
+    let file_path = update_file_path(&download_dir, file_name)?;
+    let part_path = download::part_path(&file_path);
 
-    // 创建HTTP客户端
-    let client = reqwest::Client::new();
-
-    // 发送下载请求
-    let response = client
-        .get(&download_url)
-        .send()
-        .await
-        .map_err(|e| format!("下载请求失败: {}", e))?;
+    // 本地目录或SMB共享更新源直接复制文件，只允许复制更新目录内的文件
+    if let Some(source_path) = update_source::local_package_path(download_url)? {
+        return copy_local_package(app, &source_path, &file_path).await;
+    }
 
-    if !response.status().is_success() {
-        return Ok(DownloadResult {
-            status: DownloadStatus::Failed,
-            file_path: None,
-            error: Some(format!("下载失败，状态码: {}", response.status())),
-        });
+    // 读取上次未完成的下载进度
+    let mut partial = download::load_partial(&file_path, download_url)
+        .unwrap_or_else(|| download::PartialDownload::new(download_url, expected_size));
+
+    // 使用共享的HTTP客户端
+    let http = app.state::<http_client::HttpClient>();
+    let client = http.client();
+    let read_timeout = http.config().read_timeout();
+
+    // 发送下载请求，存在未完成的下载时请求剩余部分
+    // GitHub 私有仓库的附件通过接口地址下载，需要声明接收文件内容
+    let mut request = with_authorization(client.get(download_url), authorization)
+        .header("Accept", update_source::ASSET_ACCEPT);
+    if partial.downloaded > 0 {
+        log::info!("检测到未完成的下载，从第 {} 字节继续", partial.downloaded);
+        request = request.header("Range", format!("bytes={}-", partial.downloaded));
+        if let Some(if_range) = partial.if_range() {
+            request = request.header("If-Range", if_range);
+        }
     }
 
+    let response = tokio::select! {
+        response = http.send_streaming(request) => response.map_err(|e| format!("下载请求失败: {}", e))?,
+        _ = active.token.cancelled() => {
+            if !active.keep_partial() {
+                download::remove_partial(&file_path).await;
+            }
+            return Ok(cancelled_result());
+        }
+    };
+
+    // 206 表示服务器接受了续传，其他成功状态从头下载；续传范围无效时清除临时文件
+    let content_length = response.content_length();
+    let resume_from = match download::handle_response(&mut partial, response.status(), response.headers(), content_length) {
+        download::ResponseAction::Write { resume_from } => resume_from,
+        download::ResponseAction::Fail(error) => {
+            return Ok(DownloadResult {
+                status: DownloadStatus::Failed,
+                file_path: None,
+                error: Some(error),
+                served_by: None,
+            });
+        }
+        download::ResponseAction::Discard(error) => {
+            download::remove_partial(&file_path).await;
+            return Ok(DownloadResult {
+                status: DownloadStatus::Failed,
+                file_path: None,
+                error: Some(error),
+                served_by: None,
+            });
+        }
+    };
+
     // 获取文件总大小
-    let total_size = response.content_length().unwrap_or(expected_size);
+    let total_size = partial.total;
 
-    // 创建文件
-    let mut file = tokio::fs::File::create(&file_path)
-        .await
-        .map_err(|e| format!("创建文件失败: {}", e))?;
+    // 打开临时文件，续传时追加写入
+    let mut file = if resume_from > 0 {
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&part_path)
+            .await
+            .map_err(|e| format!("打开临时文件失败: {}", e))?
+    } else {
+        tokio::fs::File::create(&part_path)
+            .await
+            .map_err(|e| format!("创建文件失败: {}", e))?
+    };
+    download::save_partial(&file_path, &partial).await?;
 
     // 下载文件并显示进度
-    let mut downloaded = 0u64;
+    let mut downloaded = resume_from;
+    let mut last_saved = downloaded;
     let mut stream = response.bytes_stream();
     let start_time = std::time::Instant::now();
 
     use futures_util::StreamExt;
 
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("下载数据失败: {}", e))?;
+    loop {
+        let next = tokio::select! {
+            next = tokio::time::timeout(read_timeout, stream.next()) => next,
+            _ = active.token.cancelled() => {
+                log::info!("下载已取消: {}", file_name);
+                let _ = file.flush().await;
+                drop(file);
+
+                // 按取消策略保留进度或删除临时文件
+                if active.keep_partial() {
+                    partial.downloaded = downloaded;
+                    if let Err(e) = download::save_partial(&file_path, &partial).await {
+                        log::warn!("{}", e);
+                    }
+                } else {
+                    download::remove_partial(&file_path).await;
+                }
+                return Ok(cancelled_result());
+            }
+        };
+
+        // 超过读取超时仍未收到数据时按下载失败处理
+        let next = match next {
+            Ok(next) => next.map(|chunk| chunk.map_err(|e| e.to_string())),
+            Err(_) => Some(Err(format!("{} 秒内未收到数据", read_timeout.as_secs()))),
+        };
+        let Some(chunk) = next else {
+            break;
+        };
+
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                // 保存已下载的进度，下次调用时继续
+                let _ = file.flush().await;
+                partial.downloaded = downloaded;
+                if let Err(save_error) = download::save_partial(&file_path, &partial).await {
+                    log::warn!("{}", save_error);
+                }
+                return Err(format!("下载数据失败（已保存进度，重试将继续下载）: {}", e));
+            }
+        };
 
         file.write_all(&chunk)
             .await
@@ -555,6 +1058,27 @@ async fn download_update(
 
         downloaded += chunk.len() as u64;
 
+        // 限速下载：下载过快时等待
+        if let Some(rate_limit) = active.rate_limit() {
+            let expected = (downloaded - resume_from) as f64 / rate_limit as f64;
+            let elapsed = start_time.elapsed().as_secs_f64();
+            if expected > elapsed {
+                // 取消时立即结束等待，由下一轮循环处理取消
+                tokio::select! {
+                    _ = tokio::time::sleep(tokio::time::Duration::from_secs_f64(expected - elapsed)) => {}
+                    _ = active.token.cancelled() => {}
+                }
+            }
+        }
+
+        // 每下载1MB保存一次进度
+        if downloaded - last_saved >= PROGRESS_SAVE_INTERVAL {
+            file.flush().await.map_err(|e| format!("刷新文件失败: {}", e))?;
+            partial.downloaded = downloaded;
+            download::save_partial(&file_path, &partial).await?;
+            last_saved = downloaded;
+        }
+
         // 计算下载进度
         let percentage = if total_size > 0 {
             (downloaded as f64 / total_size as f64) * 100.0
@@ -562,10 +1086,10 @@ async fn download_update(
             0.0
         };
 
-        // 计算下载速度
+        // 计算下载速度（只统计本次下载的字节数）
         let elapsed = start_time.elapsed().as_secs_f64();
         let speed = if elapsed > 0.0 {
-            downloaded as f64 / elapsed
+            (downloaded - resume_from) as f64 / elapsed
         } else {
             0.0
         };
@@ -598,6 +1122,27 @@ async fn download_update(
     }
 
     file.flush().await.map_err(|e| format!("刷新文件失败: {}", e))?;
+    file.sync_all().await.map_err(|e| format!("同步文件失败: {}", e))?;
+    // 重命名前必须关闭文件句柄
+    drop(file);
+
+    // 校验文件大小，连接提前结束时保留进度以便续传
+    if total_size > 0 && downloaded != total_size {
+        partial.downloaded = downloaded;
+        if downloaded < total_size {
+            download::save_partial(&file_path, &partial).await?;
+        } else {
+            download::remove_partial(&file_path).await;
+        }
+        return Ok(DownloadResult {
+            status: DownloadStatus::Failed,
+            file_path: None,
+            error: Some(format!("下载不完整: 已下载 {} / {} 字节", downloaded, total_size)),
+            served_by: None,
+        });
+    }
+
+    download::finalize(&file_path).await?;
 
     log::info!("更新包下载完成: {}", file_path.display());
 
@@ -605,21 +1150,43 @@ async fn download_update(
         status: DownloadStatus::Completed,
         file_path: Some(file_path.to_string_lossy().to_string()),
         error: None,
+        served_by: None,
     })
 }
 
+// 下载被取消时的结果
+fn cancelled_result() -> DownloadResult {
+    DownloadResult {
+        status: DownloadStatus::Cancelled,
+        file_path: None,
+        error: None,
+        served_by: None,
+    }
+}
+
 // Tauri 命令：取消下载
+// 未指定 download_id 时取消全部下载；keep_partial 默认为 true，保留临时文件以便之后续传
 #[tauri::command]
-async fn cancel_download() -> Result<(), String> {
-    // 这里可以实现下载取消逻辑
-    // 由于当前实现是同步下载，实际项目中需要使用可取消的异步任务
-    log::info!("取消下载请求");
-    Ok(())
+async fn cancel_download(
+    manager: tauri::State<'_, download::DownloadManager>,
+    download_id: Option<String>,
+    keep_partial: Option<bool>,
+) -> Result<bool, String> {
+    let cancelled = manager.cancel(download_id.as_deref(), keep_partial.unwrap_or(true));
+
+    if cancelled == 0 {
+        log::info!("没有正在进行的下载需要取消");
+    } else {
+        log::info!("已取消 {} 个下载任务", cancelled);
+    }
+
+    Ok(cancelled > 0)
 }
 
 // Tauri 命令：安装更新并重启
+// 提供 version 时记录安装历史，用于之后回滚和检测新版本启动失败
 #[tauri::command]
-async fn install_update_and_restart(file_path: String) -> Result<(), String> {
+async fn install_update_and_restart(file_path: String, version: Option<String>) -> Result<(), String> {
     log::info!("开始安装更新: {}", file_path);
 
     // 验证文件存在
@@ -627,12 +1194,117 @@ async fn install_update_and_restart(file_path: String) -> Result<(), String> {
         return Err("更新文件不存在".to_string());
     }
 
+    if let Some(version) = version {
+        record_install(&file_path, &version, false)?;
+    }
+
+    launch_installer_and_exit(&file_path).await
+}
+
+// Tauri 命令：回滚到之前安装的版本
+// 未指定 version 时回滚到当前版本之前安装的版本，安装包需仍保留在更新目录中
+#[tauri::command]
+async fn rollback_update(version: Option<String>) -> Result<(), String> {
+    let updates_dir = get_updates_directory()?;
+    let current_version = env!("CARGO_PKG_VERSION");
+
+    let target_version = match version {
+        Some(version) => version,
+        None => history::previous_version(&updates_dir, current_version)
+            .ok_or("没有找到可回滚的上一个版本")?,
+    };
+
+    let installer = history::installer_for_version(&updates_dir, &target_version)
+        .ok_or_else(|| format!("版本 {} 的安装包已不存在，无法回滚", target_version))?;
+    let file_path = installer.to_string_lossy().to_string();
+
+    log::info!("开始回滚: {} -> {}", current_version, target_version);
+    record_install(&file_path, &target_version, true)?;
+
+    // 回滚后跳过回滚前的版本，避免后台检查再次提示并预下载它
+    if is_newer_version(&target_version, current_version) {
+        let mut config = load_config();
+        config.update.skipped_version = Some(current_version.to_string());
+        config.update.remind_after = None;
+        save_config(&config)?;
+    }
+
+    launch_installer_and_exit(&file_path).await
+}
+
+/// 安装历史信息
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallHistory {
+    pub current_version: String,
+    pub records: Vec<history::InstallRecord>,
+    /// 安装包仍保留、可以回滚到的版本
+    pub rollback_versions: Vec<String>,
+}
+
+// Tauri 命令：获取安装历史
+#[tauri::command]
+async fn get_install_history() -> Result<InstallHistory, String> {
+    let updates_dir = get_updates_directory()?;
+    let current_version = env!("CARGO_PKG_VERSION");
+
+    Ok(InstallHistory {
+        current_version: current_version.to_string(),
+        records: history::load_history(&updates_dir),
+        rollback_versions: history::rollback_versions(&updates_dir, current_version),
+    })
+}
+
+// Tauri 命令：获取启动时检测到的回滚建议（新版本上次启动失败时返回）
+#[tauri::command]
+async fn get_rollback_suggestion(
+    launch_check: tauri::State<'_, history::LaunchCheck>,
+) -> Result<Option<history::RollbackSuggestion>, String> {
+    let suggestion = launch_check.0.lock().map_err(|_| "启动检查状态已损坏".to_string())?;
+    Ok(suggestion.clone())
+}
+
+// 记录一次安装
+fn record_install(file_path: &str, version: &str, rollback: bool) -> Result<(), String> {
+    let updates_dir = get_updates_directory()?;
+    let file_name = std::path::Path::new(file_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("无效的安装包路径")?
+        .to_string();
+
+    let installed_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    // 记录安装前版本的安装包，第一次更新后也能回滚到原来的版本
+    let from_version = env!("CARGO_PKG_VERSION");
+    let from_file_name = history::installer_for_version(&updates_dir, from_version)
+        .and_then(|path| path.file_name().and_then(|n| n.to_str()).map(str::to_string))
+        .filter(|name| *name != file_name);
+    if from_file_name.is_none() {
+        log::warn!("更新目录中没有版本 {} 的安装包，安装后无法回滚到该版本", from_version);
+    }
+
+    history::record_install(&updates_dir, history::InstallRecord {
+        from_version: from_version.to_string(),
+        to_version: version.to_string(),
+        file_name,
+        from_file_name,
+        installed_at,
+        rollback,
+    })
+}
+
+// 启动安装程序并退出当前应用
+async fn launch_installer_and_exit(file_path: &str) -> Result<(), String> {
     // 启动安装程序
     #[cfg(target_os = "windows")]
     {
         use std::os::windows::process::CommandExt;
 
-        Command::new(&file_path)
+        installer_command(file_path)?
             .creation_flags(0x08000000) // CREATE_NO_WINDOW
             .spawn()
             .map_err(|e| format!("启动安装程序失败: {}", e))?;
@@ -640,7 +1312,7 @@ async fn install_update_and_restart(file_path: String) -> Result<(), String> {
 
     #[cfg(not(target_os = "windows"))]
     {
-        Command::new(&file_path)
+        installer_command(file_path)?
             .spawn()
             .map_err(|e| format!("启动安装程序失败: {}", e))?;
     }
@@ -652,12 +1324,54 @@ async fn install_update_and_restart(file_path: String) -> Result<(), String> {
     std::process::exit(0);
 }
 
+// 根据安装包格式构建安装命令
+fn installer_command(file_path: &str) -> Result<Command, String> {
+    let extension = std::path::Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let command = match extension.as_str() {
+        "msi" => {
+            let mut command = Command::new("msiexec");
+            command.arg("/i").arg(file_path);
+            command
+        }
+        // deb 包交给系统的软件包安装器处理
+        "deb" => {
+            let mut command = Command::new("xdg-open");
+            command.arg(file_path);
+            command
+        }
+        "dmg" => {
+            let mut command = Command::new("open");
+            command.arg(file_path);
+            command
+        }
+        "appimage" => {
+            // AppImage 需要可执行权限才能直接运行
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(file_path, fs::Permissions::from_mode(0o755))
+                    .map_err(|e| format!("设置安装包可执行权限失败: {}", e))?;
+            }
+            Command::new(file_path)
+        }
+        _ => Command::new(file_path),
+    };
+
+    Ok(command)
+}
+
 // Tauri 命令：获取已下载的更新路径
+// file_name 为版本信息中的安装包文件名，未提供时使用旧版Windows安装包命名
 #[tauri::command]
-async fn get_downloaded_update_path(version: String) -> Result<Option<String>, String> {
+async fn get_downloaded_update_path(version: String, file_name: Option<String>) -> Result<Option<String>, String> {
     let updates_dir = get_updates_directory()?;
-    let file_name = format!("WorkHelper_{}_x64-setup.exe", version);
-    let file_path = updates_dir.join(&file_name);
+    let file_name = file_name.unwrap_or_else(|| format!("WorkHelper_{}_x64-setup.exe", version));
+    let file_path = update_file_path(&updates_dir, &file_name)?;
 
     if file_path.exists() {
         Ok(Some(file_path.to_string_lossy().to_string()))
@@ -667,6 +1381,7 @@ async fn get_downloaded_update_path(version: String) -> Result<Option<String>, S
 }
 
 // Tauri 命令：清理旧的更新文件
+// 最近安装过的版本（数量由配置 update.retain_installers 决定）的安装包会保留用于回滚
 #[tauri::command]
 async fn cleanup_old_updates() -> Result<(), String> {
     let updates_dir = get_updates_directory()?;
@@ -681,14 +1396,18 @@ async fn cleanup_old_updates() -> Result<(), String> {
 
     let current_version = env!("CARGO_PKG_VERSION");
 
+    // 保留最近安装的几个版本的安装包，用于回滚
+    let retained = history::retained_installers(&updates_dir, load_config().update.retain_installers);
+
     for entry in entries {
         let entry = entry.map_err(|e| format!("读取目录项失败: {}", e))?;
         let path = entry.path();
 
         if path.is_file() {
             if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                // 如果不是当前版本的更新文件，则删除
-                if !file_name.contains(current_version) && file_name.ends_with(".exe") {
+                // 如果既不是当前版本也不是需要保留的安装包，则删除
+                let keep = file_name.contains(current_version) || retained.iter().any(|r| r == file_name);
+                if !keep && history::is_installer_file(file_name) {
                     if let Err(e) = fs::remove_file(&path) {
                         log::warn!("删除旧更新文件失败: {} - {}", path.display(), e);
                     } else {
@@ -718,6 +1437,15 @@ fn get_updates_directory() -> Result<PathBuf, String> {
     Ok(updates_dir)
 }
 
+// 更新目录中指定文件的路径
+// 文件名来自版本信息或前端，包含路径分隔符或 .. 时拒绝，避免读写更新目录以外的文件
+fn update_file_path(updates_dir: &std::path::Path, file_name: &str) -> Result<PathBuf, String> {
+    if file_name.is_empty() || file_name.contains(['/', '\\', ':']) || file_name.contains("..") {
+        return Err(format!("无效的更新文件名: {}", file_name));
+    }
+    Ok(updates_dir.join(file_name))
+}
+
 // 格式化下载速度
 fn format_speed(bytes_per_second: f64) -> String {
     if bytes_per_second < 1024.0 {
@@ -741,62 +1469,50 @@ fn format_time(seconds: f64) -> String {
 }
 
 // Tauri 命令：通用HTTP请求
-// 使用reqwest库发送HTTP请求，解决跨域问题
+// 使用reqwest库发送HTTP请求，解决跨域问题；只允许访问配置中的服务器，见 http_policy 模块
+// error_for_status 为 true 时非 2xx 响应作为错误返回
 #[tauri::command]
 async fn http_request(
+    http: tauri::State<'_, http_client::HttpClient>,
     url: String,
     method: String,
     headers: Option<std::collections::HashMap<String, String>>,
-    body: Option<String>
-) -> Result<String, String> {
-    let client = reqwest::Client::new();
-
-    // 构建请求
-    let mut request = match method.to_uppercase().as_str() {
-        "GET" => client.get(&url),
-        "POST" => client.post(&url),
-        "PUT" => client.put(&url),
-        "DELETE" => client.delete(&url),
-        _ => return Err("不支持的HTTP方法".to_string()),
-    };
+    body: Option<String>,
+    error_for_status: Option<bool>
+) -> Result<http_proxy::HttpResponse, http_policy::HttpRequestError> {
+    http_proxy::send(
+        &http,
+        &url,
+        &method,
+        headers.unwrap_or_default(),
+        body,
+        error_for_status.unwrap_or(false),
+    )
+    .await
+}
 
-    // 添加请求头
-    if let Some(headers) = headers {
-        for (key, value) in headers {
-            request = request.header(&key, &value);
-        }
-    }
 
-    // 添加请求体
-    if let Some(body) = body {
-        request = request.body(body);
-    }
 
-    // 发送请求
-    match request.send().await {
-        Ok(response) => {
-            match response.text().await {
-                Ok(text) => {
-                    Ok(text)
-                }
-                Err(e) => {
-                    log::error!("读取响应失败: {}", e);
-                    Err(format!("读取响应失败: {}", e))
-                }
-            }
-        }
-        Err(e) => {
-            log::error!("HTTP请求失败: {}", e);
-            Err(format!("HTTP请求失败: {}", e))
-        }
-    }
+// Tauri 命令：列出保存的 Cookie，提供 host 时只列出发送给该服务器的 Cookie
+#[tauri::command]
+async fn list_cookies(host: Option<String>) -> Result<Vec<cookies::CookieInfo>, String> {
+    cookies::list_cookies(host.as_deref())
 }
 
-
+// Tauri 命令：清除保存的 Cookie，提供 host 时只清除该服务器的 Cookie，返回清除的数量
+#[tauri::command]
+async fn clear_cookies(host: Option<String>) -> Result<usize, String> {
+    cookies::clear_cookies(host.as_deref())
+}
 
 // Tauri 命令：关闭启动画面并显示主窗口
+// 前端完成初始化后调用，同时确认更新后的新版本已成功启动
 #[tauri::command]
 async fn close_splashscreen(app: tauri::AppHandle) -> Result<(), String> {
+    if let Ok(updates_dir) = get_updates_directory() {
+        history::confirm_launch(&updates_dir);
+    }
+
     // 先显示主窗口，确保无缝切换
     if let Some(main_window) = app.get_webview_window("main") {
         main_window.show().map_err(|e| {
@@ -826,9 +1542,195 @@ async fn close_splashscreen(app: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+// Tauri 命令：获取配置文件加载错误，配置文件可以正常使用时返回 None
+#[tauri::command]
+async fn get_config_load_error() -> Result<Option<config::ConfigLoadError>, String> {
+    load_config();
+    Ok(config::load_error())
+}
+
+// Tauri 命令：放弃无法加载的配置文件并恢复默认配置，原文件保留为备份
+#[tauri::command]
+async fn reset_config() -> Result<(), String> {
+    config::reset_config()
+}
+
+// Tauri 命令：列出各配置字段的生效值及来源（默认值、系统配置、用户配置、环境变量或命令行）
+#[tauri::command]
+async fn describe_config() -> Result<Vec<config::ConfigEntry>, config::ConfigLoadError> {
+    config::describe_config()
+}
+
+// Tauri 命令：获取网络设置
+#[tauri::command]
+async fn get_network_config() -> Result<http_client::NetworkConfig, String> {
+    Ok(load_config().network)
+}
+
+// Tauri 命令：保存网络设置，校验代理地址和CA证书后生效，下次发送请求时使用新的设置
+#[tauri::command]
+async fn save_network_config(network_config: http_client::NetworkConfig) -> Result<(), String> {
+    network_config.validate()?;
+    let mut config = load_config();
+    config.network = network_config;
+    save_config(&config)
+}
+
+// Tauri 命令：获取设置同步配置
+#[tauri::command]
+async fn get_sync_config() -> Result<config::SyncConfig, String> {
+    Ok(load_config().sync)
+}
+
+// Tauri 命令：保存设置同步配置
+#[tauri::command]
+async fn save_sync_config(sync_config: config::SyncConfig) -> Result<(), String> {
+    let mut config = load_config();
+    config.sync = sync_config;
+    save_config(&config)
+}
+
+// Tauri 命令：加密同步设置，两边都有修改且未指定处理方式时返回冲突
+#[tauri::command]
+async fn sync_settings(
+    app: tauri::AppHandle,
+    passphrase: String,
+    local_storage: std::collections::BTreeMap<String, String>,
+    strategy: Option<sync::SyncStrategy>,
+) -> Result<sync::SyncResult, String> {
+    let result = sync::sync(&passphrase, local_storage, strategy).await?;
+    if matches!(result.status, sync::SyncStatus::Pulled | sync::SyncStatus::Merged) {
+        notify_settings_changed(&app, &settings::get_settings());
+    }
+    Ok(result)
+}
+
+// Tauri 命令：从云端恢复设置，覆盖本机数据
+#[tauri::command]
+async fn restore_synced_settings(app: tauri::AppHandle, passphrase: String) -> Result<sync::SyncResult, String> {
+    let result = sync::restore(&passphrase).await?;
+    notify_settings_changed(&app, &settings::get_settings());
+    Ok(result)
+}
+
+// Tauri 命令：获取基础设置
+#[tauri::command]
+async fn get_settings() -> Result<settings::GeneralSettings, String> {
+    Ok(settings::get_settings())
+}
+
+// Tauri 命令：获取默认的基础设置（用于恢复默认模板）
+#[tauri::command]
+async fn get_default_settings() -> Result<settings::GeneralSettings, String> {
+    Ok(settings::GeneralSettings::default())
+}
+
+// Tauri 命令：更新基础设置的部分字段，校验失败时返回各字段的错误，修改后通知所有窗口
+#[tauri::command]
+async fn update_settings(
+    app: tauri::AppHandle,
+    patch: serde_json::Map<String, serde_json::Value>,
+) -> Result<settings::GeneralSettings, settings::SettingsError> {
+    let (settings, changed) = tokio::task::spawn_blocking(move || settings::update_settings(&patch))
+        .await
+        .map_err(|e| format!("更新设置失败: {}", e))??;
+    if changed {
+        notify_settings_changed(&app, &settings);
+    }
+    Ok(settings)
+}
+
+// Tauri 命令：导入旧版前端保存在 localStorage 中的设置
+#[tauri::command]
+async fn import_local_settings(
+    app: tauri::AppHandle,
+    local_settings: serde_json::Map<String, serde_json::Value>,
+) -> Result<settings::GeneralSettings, settings::SettingsError> {
+    let (settings, changed) = tokio::task::spawn_blocking(move || settings::import_local_settings(&local_settings))
+        .await
+        .map_err(|e| format!("导入设置失败: {}", e))??;
+    if changed {
+        notify_settings_changed(&app, &settings);
+    }
+    Ok(settings)
+}
+
+// Tauri 命令：列出配置档案
+#[tauri::command]
+async fn list_profiles() -> Result<Vec<profiles::ProfileInfo>, String> {
+    tokio::task::spawn_blocking(profiles::list_profiles)
+        .await
+        .map_err(|e| format!("读取配置档案失败: {}", e))
+}
+
+// Tauri 命令：新建配置档案，copy_current 为 true 时复制当前档案的设置
+#[tauri::command]
+async fn create_profile(name: String, copy_current: bool) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || profiles::create_profile(&name, copy_current))
+        .await
+        .map_err(|e| format!("新建配置档案失败: {}", e))?
+}
+
+// Tauri 命令：切换配置档案
+#[tauri::command]
+async fn switch_profile(app: tauri::AppHandle, name: String) -> Result<settings::GeneralSettings, String> {
+    let settings = tokio::task::spawn_blocking(move || profiles::switch_profile(&name))
+        .await
+        .map_err(|e| format!("切换配置档案失败: {}", e))??;
+    notify_settings_changed(&app, &settings);
+    Ok(settings)
+}
+
+// Tauri 命令：删除配置档案及其数据目录
+#[tauri::command]
+async fn delete_profile(name: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || profiles::delete_profile(&name))
+        .await
+        .map_err(|e| format!("删除配置档案失败: {}", e))?
+}
+
+// Tauri 命令：导出可共享的设置（模板、大模型接口、OA 地址）为配置包，不含令牌、密码和账号
+#[tauri::command]
+async fn export_config(path: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || bundle::export_bundle(std::path::Path::new(&path)))
+        .await
+        .map_err(|e| format!("导出配置失败: {}", e))?
+}
+
+// Tauri 命令：导入配置包
+#[tauri::command]
+async fn import_config(app: tauri::AppHandle, path: String) -> Result<settings::GeneralSettings, settings::SettingsError> {
+    let (settings, changed) = tokio::task::spawn_blocking(move || bundle::import_bundle(std::path::Path::new(&path)))
+        .await
+        .map_err(|e| format!("导入配置失败: {}", e))??;
+    if changed {
+        notify_settings_changed(&app, &settings);
+    }
+    Ok(settings)
+}
+
+// Tauri 命令：立即刷新订阅的共享配置，返回是否应用了新的配置
+#[tauri::command]
+async fn refresh_config_subscription(app: tauri::AppHandle) -> Result<bool, String> {
+    bundle::refresh_and_notify(&app).await
+}
+
+/// 向所有窗口广播设置已修改
+fn notify_settings_changed(app: &tauri::AppHandle, settings: &settings::GeneralSettings) {
+    if let Err(e) = app.emit(settings::SETTINGS_CHANGED_EVENT, settings) {
+        log::warn!("发送设置修改事件失败: {}", e);
+    }
+}
+
 // Tauri 应用程序入口
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // 构建时可能嵌入了写权限凭据的程序一旦分发就会泄露发布账号，拒绝运行
+    if config::embeds_write_credentials() {
+        eprintln!("错误：此程序构建时设置了具有写权限的WebDAV发布账号（WEBDAV_USERNAME、WEBDAV_PASSWORD），已拒绝启动。请只使用只读账号（WEBDAV_READ_USERNAME、WEBDAV_READ_PASSWORD）重新构建");
+        std::process::exit(1);
+    }
+
     // 在应用启动时加载环境变量
     if let Err(e) = dotenv::dotenv() {
         println!("警告：无法加载 .env 文件: {}", e);
@@ -838,6 +1740,9 @@ pub fn run() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
+        .manage(http_client::shared().clone())
+        .manage(download::DownloadManager::default())
+        .manage(history::LaunchCheck::default())
         .setup(|app| {
             // 在生产环境也启用日志，方便调试
             app.handle().plugin(
@@ -849,6 +1754,28 @@ pub fn run() {
             println!("工作助手应用程序启动成功");
                 log::info!("WorkHelper 启动成功 - 版本: {}", env!("CARGO_PKG_VERSION"));
 
+            // 启动后台更新检查
+            scheduler::start(app.handle().clone());
+
+            // 监听配置文件的手动修改
+            config_watcher::start(app.handle().clone());
+
+            // 定期刷新订阅的团队共享配置
+            bundle::start(app.handle().clone());
+
+            // 检查更新后的新版本上次是否启动失败
+            if let Ok(updates_dir) = get_updates_directory() {
+                if let Some(suggestion) = history::check_launch(&updates_dir, env!("CARGO_PKG_VERSION")) {
+                    log::warn!("建议回滚到版本: {}", suggestion.rollback_version);
+                    if let Err(e) = app.emit("update-rollback-suggested", &suggestion) {
+                        log::warn!("发送回滚建议事件失败: {}", e);
+                    }
+                    if let Ok(mut pending) = app.state::<history::LaunchCheck>().0.lock() {
+                        *pending = Some(suggestion);
+                    }
+                }
+            }
+
             // 创建启动画面窗口 - 在主窗口加载前先显示
             let splashscreen_window = tauri::WebviewWindowBuilder::new(
                 app,
@@ -901,12 +1828,39 @@ pub fn run() {
             health_check,
             close_splashscreen,
             http_request,
+            list_cookies,
+            clear_cookies,
             check_for_updates,
             download_update,
             cancel_download,
             install_update_and_restart,
+            rollback_update,
+            get_install_history,
+            get_rollback_suggestion,
+            skip_update_version,
+            remind_update_later,
             get_downloaded_update_path,
-            cleanup_old_updates
+            cleanup_old_updates,
+            get_network_config,
+            save_network_config,
+            get_sync_config,
+            save_sync_config,
+            sync_settings,
+            restore_synced_settings,
+            get_config_load_error,
+            reset_config,
+            describe_config,
+            get_settings,
+            get_default_settings,
+            update_settings,
+            import_local_settings,
+            list_profiles,
+            create_profile,
+            switch_profile,
+            delete_profile,
+            export_config,
+            import_config,
+            refresh_config_subscription
         ])
         .run(tauri::generate_context!())
         .expect("运行Tauri应用程序时出错");
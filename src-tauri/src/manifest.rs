@@ -0,0 +1,411 @@
+/*!
+ * 更新清单模块
+ * 负责解析 latest.json 中按平台列出的安装包，并为当前运行的系统和架构选择合适的安装包
+ */
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 安装包格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactFormat {
+    Nsis,
+    Msi,
+    #[serde(alias = "AppImage")]
+    AppImage,
+    Deb,
+    Dmg,
+}
+
+impl ArtifactFormat {
+    /// 安装包文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArtifactFormat::Nsis => "exe",
+            ArtifactFormat::Msi => "msi",
+            ArtifactFormat::AppImage => "AppImage",
+            ArtifactFormat::Deb => "deb",
+            ArtifactFormat::Dmg => "dmg",
+        }
+    }
+
+    /// 安装包适用的系统
+    pub fn os(&self) -> &'static str {
+        match self {
+            ArtifactFormat::Nsis | ArtifactFormat::Msi => "windows",
+            ArtifactFormat::AppImage | ArtifactFormat::Deb => "linux",
+            ArtifactFormat::Dmg => "macos",
+        }
+    }
+
+    /// 根据文件扩展名推断安装包格式
+    pub fn from_file_name(name: &str) -> Option<ArtifactFormat> {
+        let lower = name.to_lowercase();
+        if lower.ends_with(".exe") {
+            Some(ArtifactFormat::Nsis)
+        } else if lower.ends_with(".msi") {
+            Some(ArtifactFormat::Msi)
+        } else if lower.ends_with(".appimage") {
+            Some(ArtifactFormat::AppImage)
+        } else if lower.ends_with(".deb") {
+            Some(ArtifactFormat::Deb)
+        } else if lower.ends_with(".dmg") {
+            Some(ArtifactFormat::Dmg)
+        } else {
+            None
+        }
+    }
+
+    /// 各系统可用的安装包格式，按优先级排列
+    fn preferred_for(os: &str) -> &'static [ArtifactFormat] {
+        match os {
+            "windows" => &[ArtifactFormat::Nsis, ArtifactFormat::Msi],
+            "linux" => &[ArtifactFormat::AppImage, ArtifactFormat::Deb],
+            "macos" => &[ArtifactFormat::Dmg],
+            _ => &[],
+        }
+    }
+}
+
+/// latest.json 中的单个安装包条目
+///
+/// 可以用 target（Rust 目标三元组，如 "x86_64-pc-windows-msvc"）或 os/arch 描述平台，
+/// url 可以是完整地址，也可以是相对于 WebDAV base_url 的路径；
+/// mirrors 为备用下载地址，主地址不可用时按顺序尝试。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub arch: Option<String>,
+    pub format: ArtifactFormat,
+    pub url: String,
+    #[serde(default)]
+    pub file_name: Option<String>,
+    #[serde(default)]
+    pub file_size: Option<u64>,
+    #[serde(default)]
+    pub file_size_formatted: Option<String>,
+    /// 完整安装包的SHA-256，下载或差分还原后用于校验
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// 基于旧版本安装包的差分补丁
+    #[serde(default)]
+    pub deltas: Vec<DeltaPatch>,
+    /// 备用下载地址
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    /// 构建时由 Tauri 签名工具生成的安装包签名
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+/// 差分补丁条目，将 base_version 的安装包还原为当前版本的安装包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaPatch {
+    pub base_version: String,
+    pub url: String,
+    pub file_size: u64,
+    /// 补丁文件的SHA-256
+    pub sha256: String,
+    /// 基础安装包的SHA-256
+    pub base_sha256: String,
+}
+
+impl Artifact {
+    /// 安装包适用的系统
+    fn os(&self) -> Option<String> {
+        if let Some(os) = &self.os {
+            return Some(normalize_os(os));
+        }
+
+        let target = self.target.as_deref()?;
+        if target.contains("windows") {
+            Some("windows".to_string())
+        } else if target.contains("darwin") || target.contains("apple") {
+            Some("macos".to_string())
+        } else if target.contains("linux") {
+            Some("linux".to_string())
+        } else {
+            None
+        }
+    }
+
+    /// 安装包适用的架构
+    fn arch(&self) -> Option<String> {
+        if let Some(arch) = &self.arch {
+            return Some(normalize_arch(arch));
+        }
+
+        let target = self.target.as_deref()?;
+        target.split('-').next().map(normalize_arch)
+    }
+
+    /// 安装包的完整下载地址
+    pub fn resolve_url(&self, base_url: &str) -> String {
+        resolve_url(&self.url, base_url)
+    }
+
+    /// 备用下载地址，按顺序排列
+    ///
+    /// 先是安装包自身列出的镜像，再是清单顶层 mirrors 中的镜像根地址
+    /// （仅当安装包地址为相对路径时适用），与主地址重复的会被去掉。
+    pub fn mirror_urls(&self, base_url: &str, manifest_mirrors: &[String]) -> Vec<String> {
+        let primary = self.resolve_url(base_url);
+        let mut urls: Vec<String> = self
+            .mirrors
+            .iter()
+            .map(|mirror| resolve_url(mirror, base_url))
+            .collect();
+
+        if !is_absolute_url(&self.url) {
+            urls.extend(manifest_mirrors.iter().map(|mirror| resolve_url(&self.url, mirror)));
+        }
+
+        let mut unique: Vec<String> = Vec::new();
+        for url in urls {
+            if url != primary && !unique.contains(&url) {
+                unique.push(url);
+            }
+        }
+        unique
+    }
+
+    /// 本地保存的文件名，未指定时取下载地址的最后一段
+    pub fn resolve_file_name(&self, version: &str) -> String {
+        if let Some(file_name) = &self.file_name {
+            return file_name.clone();
+        }
+
+        self.url
+            .rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| format!("WorkHelper_{}.{}", version, self.format.extension()))
+    }
+}
+
+/// 将清单中的地址解析为完整地址，相对路径基于 base_url
+pub fn resolve_url(url: &str, base_url: &str) -> String {
+    if is_absolute_url(url) {
+        url.to_string()
+    } else {
+        format!("{}/{}", base_url.trim_end_matches('/'), url.trim_start_matches('/'))
+    }
+}
+
+fn is_absolute_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// 清单顶层的镜像根地址列表，相对路径的安装包在这些地址下同样可用
+pub fn manifest_mirrors(manifest: &Value) -> Vec<String> {
+    manifest["mirrors"]
+        .as_array()
+        .map(|mirrors| {
+            mirrors
+                .iter()
+                .filter_map(|mirror| mirror.as_str())
+                .map(|mirror| mirror.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 当前运行的平台
+#[derive(Debug, Clone)]
+pub struct Platform {
+    pub os: String,
+    pub arch: String,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        Self {
+            os: normalize_os(std::env::consts::OS),
+            arch: normalize_arch(std::env::consts::ARCH),
+        }
+    }
+
+    /// 用于提示信息的平台标识，例如 "windows-x86_64"
+    pub fn label(&self) -> String {
+        format!("{}-{}", self.os, self.arch)
+    }
+}
+
+fn normalize_os(os: &str) -> String {
+    match os.to_lowercase().as_str() {
+        "win" | "win32" | "win64" | "windows" => "windows".to_string(),
+        "mac" | "macos" | "osx" | "darwin" => "macos".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn normalize_arch(arch: &str) -> String {
+    match arch.to_lowercase().as_str() {
+        "x64" | "amd64" | "x86_64" => "x86_64".to_string(),
+        "arm64" | "aarch64" => "aarch64".to_string(),
+        "x86" | "i386" | "i686" | "ia32" => "x86".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// 根据安装包文件名推断架构，未标明时视为 x86_64
+pub fn arch_from_file_name(name: &str) -> String {
+    let lower = name.to_lowercase();
+    if lower.contains("aarch64") || lower.contains("arm64") {
+        "aarch64".to_string()
+    } else if lower.contains("x86_64") || lower.contains("x64") || lower.contains("amd64") {
+        "x86_64".to_string()
+    } else if lower.contains("i686") || lower.contains("x86") {
+        "x86".to_string()
+    } else {
+        "x86_64".to_string()
+    }
+}
+
+/// 为当前平台选择安装包
+///
+/// 版本条目中有 "artifacts" 列表时从中挑选；没有时兼容旧格式，
+/// 仅在 Windows x86_64 上使用约定路径的 NSIS 安装包。没有适用于该平台的安装包时返回 None。
+pub fn select_artifact(release: &Value, version: &str, platform: &Platform) -> Result<Option<Artifact>, String> {
+    let artifacts: Vec<Artifact> = match release.get("artifacts") {
+        Some(artifacts) => serde_json::from_value(artifacts.clone())
+            .map_err(|e| format!("解析安装包列表失败: {}", e))?,
+        None => vec![legacy_windows_artifact(release, version)],
+    };
+
+    for format in ArtifactFormat::preferred_for(&platform.os) {
+        let matched = artifacts.iter().find(|artifact| {
+            artifact.format == *format
+                && artifact.os().as_deref() == Some(platform.os.as_str())
+                && artifact.arch().as_deref() == Some(platform.arch.as_str())
+        });
+
+        if let Some(artifact) = matched {
+            return Ok(Some(artifact.clone()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// 旧版 latest.json 约定的 Windows 安装包，文件大小取自版本条目的顶层字段
+fn legacy_windows_artifact(release: &Value, version: &str) -> Artifact {
+    Artifact {
+        target: None,
+        os: Some("windows".to_string()),
+        arch: Some("x86_64".to_string()),
+        format: ArtifactFormat::Nsis,
+        url: format!("v{}/windows/WorkHelper_{}_x64-setup.exe", version, version),
+        file_name: None,
+        file_size: release["fileSize"].as_u64(),
+        file_size_formatted: release["fileSizeFormatted"].as_str().map(|s| s.to_string()),
+        sha256: release["sha256"].as_str().map(|s| s.to_string()),
+        deltas: Vec::new(),
+        mirrors: Vec::new(),
+        signature: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn platform(os: &str, arch: &str) -> Platform {
+        Platform {
+            os: os.to_string(),
+            arch: arch.to_string(),
+        }
+    }
+
+    fn selected_url(release: &Value, platform: &Platform) -> Option<String> {
+        select_artifact(release, "1.2.0", platform).unwrap().map(|artifact| artifact.url)
+    }
+
+    #[test]
+    fn select_artifact_by_os_arch_and_format() {
+        let release = json!({
+            "version": "1.2.0",
+            "artifacts": [
+                {"os": "windows", "arch": "x86_64", "format": "msi", "url": "win-x64.msi"},
+                {"os": "windows", "arch": "x86_64", "format": "nsis", "url": "win-x64.exe"},
+                {"os": "win64", "arch": "arm64", "format": "msi", "url": "win-arm64.msi"},
+                {"target": "x86_64-unknown-linux-gnu", "format": "deb", "url": "linux-x64.deb"},
+                {"target": "x86_64-unknown-linux-gnu", "format": "appimage", "url": "linux-x64.AppImage"},
+                {"target": "aarch64-unknown-linux-gnu", "format": "deb", "url": "linux-arm64.deb"},
+                {"target": "aarch64-apple-darwin", "format": "dmg", "url": "mac-arm64.dmg"},
+                {"os": "darwin", "arch": "x64", "format": "dmg", "url": "mac-x64.dmg"},
+            ],
+        });
+
+        let cases = [
+            // 同一平台有多种格式时按优先级选择
+            (platform("windows", "x86_64"), Some("win-x64.exe")),
+            (platform("linux", "x86_64"), Some("linux-x64.AppImage")),
+            // 首选格式缺失时退回其他格式
+            (platform("windows", "aarch64"), Some("win-arm64.msi")),
+            (platform("linux", "aarch64"), Some("linux-arm64.deb")),
+            // target 和 os/arch 别名都能匹配
+            (platform("macos", "aarch64"), Some("mac-arm64.dmg")),
+            (platform("macos", "x86_64"), Some("mac-x64.dmg")),
+            // 没有对应架构或系统时不选择
+            (platform("windows", "x86"), None),
+            (platform("freebsd", "x86_64"), None),
+        ];
+
+        for (platform, expected) in cases {
+            assert_eq!(
+                selected_url(&release, &platform).as_deref(),
+                expected,
+                "平台 {}",
+                platform.label()
+            );
+        }
+    }
+
+    #[test]
+    fn select_artifact_ignores_mismatched_format() {
+        // 格式与系统不符的条目不会被选中
+        let release = json!({
+            "artifacts": [{"os": "linux", "arch": "x86_64", "format": "nsis", "url": "wrong.exe"}],
+        });
+        assert_eq!(selected_url(&release, &platform("linux", "x86_64")), None);
+    }
+
+    #[test]
+    fn select_artifact_legacy_windows_fallback() {
+        let release = json!({
+            "version": "1.2.0",
+            "fileSize": 1024,
+            "fileSizeFormatted": "1 KB",
+            "sha256": "abc",
+        });
+
+        let artifact = select_artifact(&release, "1.2.0", &platform("windows", "x86_64"))
+            .unwrap()
+            .expect("旧格式清单在 Windows x86_64 上使用约定的安装包");
+        assert_eq!(artifact.format, ArtifactFormat::Nsis);
+        assert_eq!(artifact.url, "v1.2.0/windows/WorkHelper_1.2.0_x64-setup.exe");
+        assert_eq!(artifact.file_size, Some(1024));
+        assert_eq!(artifact.file_size_formatted.as_deref(), Some("1 KB"));
+        assert_eq!(artifact.sha256.as_deref(), Some("abc"));
+
+        for other in [platform("windows", "aarch64"), platform("linux", "x86_64"), platform("macos", "aarch64")] {
+            assert_eq!(selected_url(&release, &other), None, "旧格式清单不提供 {} 的安装包", other.label());
+        }
+    }
+
+    #[test]
+    fn select_artifact_rejects_invalid_list() {
+        let release = json!({"artifacts": [{"os": "windows", "format": "zip", "url": "a.zip"}]});
+        assert!(select_artifact(&release, "1.2.0", &platform("windows", "x86_64")).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+/*!
+ * 配置档案模块
+ * 为不同公司或角色保存多套设置（OA 地址与账号、大模型接口、日报周报模板、同步目录），
+ * 每个档案使用独立的数据目录和项目列表。配置中的 general、sync 为当前档案的设置，
+ * 其他档案保存在 profiles 下，切换时互相交换
+ */
+
+use crate::config::{base_profile, is_unresolved_secret, load_config, save_config, saved_profile, SyncConfig};
+use crate::secrets;
+use crate::settings::GeneralSettings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// 默认档案名称，升级前的配置即为该档案
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// 档案名称最大长度（字符数）
+const MAX_NAME_CHARS: usize = 32;
+
+/// 档案中的敏感字段（JSON Pointer）及对应的凭据名称
+pub const SECRET_FIELDS: [(&str, &str); 3] = [
+    ("/sync/password", "sync.password"),
+    ("/general/deepseekToken", "settings.deepseek_token"),
+    ("/general/oaPassword", "settings.oa_password"),
+];
+
+/// 档案的设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub general: GeneralSettings,
+    #[serde(default)]
+    pub sync: SyncConfig,
+}
+
+/// 档案列表项
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileInfo {
+    pub name: String,
+    /// 是否为当前使用的档案
+    pub active: bool,
+}
+
+/// 非当前档案的凭据名称，如 profiles.work.settings.oa_password
+pub fn secret_name(profile: &str, name: &str) -> String {
+    format!("profiles.{}.{}", profile, name)
+}
+
+/// 列出所有档案，当前档案排在最前
+pub fn list_profiles() -> Vec<ProfileInfo> {
+    let config = load_config();
+
+    let mut profiles = vec![ProfileInfo {
+        name: config.profile.clone(),
+        active: true,
+    }];
+    profiles.extend(config.profiles.keys().map(|name| ProfileInfo {
+        name: name.clone(),
+        active: false,
+    }));
+    profiles
+}
+
+/// 新建档案
+///
+/// `copy_current` 为 true 时复制当前档案的设置，否则使用默认设置（含系统配置文件中的公司默认设置）
+pub fn create_profile(name: &str, copy_current: bool) -> Result<(), String> {
+    validate_name(name)?;
+
+    let mut config = load_config();
+    let exists = std::iter::once(&config.profile)
+        .chain(config.profiles.keys())
+        .any(|existing| existing.to_lowercase() == name.to_lowercase());
+    if exists {
+        return Err(format!("档案 {} 已存在", name));
+    }
+
+    let profile = if copy_current {
+        saved_profile()?
+    } else {
+        base_profile()
+    };
+    config.profiles.insert(name.to_string(), profile);
+    save_config(&config)?;
+
+    log::info!("已新建配置档案: {}", name);
+    Ok(())
+}
+
+/// 切换到指定档案，返回切换后的基础设置
+pub fn switch_profile(name: &str) -> Result<GeneralSettings, String> {
+    let mut config = load_config();
+    if config.profile == name {
+        return Ok(config.general);
+    }
+
+    let target = config
+        .profiles
+        .remove(name)
+        .ok_or_else(|| format!("档案 {} 不存在", name))?;
+    // 保存到档案中的是用户的设置，环境变量和命令行参数的临时覆盖不随档案保存
+    let previous = saved_profile()?;
+    config.general = target.general;
+    config.sync = target.sync;
+    let previous_name = std::mem::replace(&mut config.profile, name.to_string());
+    config.profiles.insert(previous_name.clone(), previous);
+    save_config(&config)?;
+
+    // 当前档案的凭据已保存到新的名称下，删除切换前保存在该档案名下的旧凭据；
+    // 无法读取的凭据仍以引用指向旧名称，保留不删
+    for (_, secret) in SECRET_FIELDS {
+        let secret = secret_name(name, secret);
+        if is_unresolved_secret(&secret) {
+            continue;
+        }
+        if let Err(e) = secrets::delete_secret(&secret) {
+            log::warn!("{}", e);
+        }
+    }
+
+    log::info!("已从配置档案 {} 切换到 {}", previous_name, name);
+    Ok(config.general)
+}
+
+/// 删除档案及其数据目录，不能删除当前档案和默认档案
+pub fn delete_profile(name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    if name == DEFAULT_PROFILE {
+        return Err("不能删除默认档案".to_string());
+    }
+
+    let mut config = load_config();
+    if config.profile == name {
+        return Err("不能删除正在使用的档案，请先切换到其他档案".to_string());
+    }
+    if config.profiles.remove(name).is_none() {
+        return Err(format!("档案 {} 不存在", name));
+    }
+    save_config(&config)?;
+
+    delete_secrets(name);
+    let dir = profile_dir_of(name)?;
+    if dir.exists() {
+        if let Err(e) = fs::remove_dir_all(&dir) {
+            log::warn!("删除档案数据目录失败: {}", e);
+        }
+    }
+
+    log::info!("已删除配置档案: {}", name);
+    Ok(())
+}
+
+/// 当前档案的数据目录
+pub fn profile_dir() -> Result<PathBuf, String> {
+    let dir = profile_dir_of(&load_config().profile)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("创建档案数据目录失败: {}", e))?;
+    Ok(dir)
+}
+
+/// 档案的数据目录，默认档案沿用升级前的数据目录
+fn profile_dir_of(name: &str) -> Result<PathBuf, String> {
+    validate_name(name)?;
+    let data_dir = dirs::data_local_dir()
+        .ok_or("无法获取本地数据目录")?
+        .join("WorkHelper");
+
+    if name == DEFAULT_PROFILE {
+        Ok(data_dir)
+    } else {
+        Ok(data_dir.join("profiles").join(name))
+    }
+}
+
+/// 删除保存在档案名下的凭据
+fn delete_secrets(profile: &str) {
+    for (_, name) in SECRET_FIELDS {
+        if let Err(e) = secrets::delete_secret(&secret_name(profile, name)) {
+            log::warn!("{}", e);
+        }
+    }
+}
+
+/// 档案名称用于目录名和凭据名称，只允许文字、数字、- 和 _
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("档案名称不能为空".to_string());
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("档案名称不能超过{}个字符", MAX_NAME_CHARS));
+    }
+    if !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err("档案名称只能包含文字、数字、- 和 _".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_name_rejects_path_traversal() {
+        for name in ["..", "../..", ".", "a/b", "a\\b", "../default", "C:", "", "a b"] {
+            assert!(validate_name(name).is_err(), "{:?} 应被拒绝", name);
+        }
+        assert!(validate_name(&"a".repeat(MAX_NAME_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn validate_name_accepts_plain_names() {
+        for name in [DEFAULT_PROFILE, "work", "公司-A_2"] {
+            assert!(validate_name(name).is_ok(), "{:?} 应被接受", name);
+        }
+    }
+
+    #[test]
+    fn profile_dir_rejects_traversal_names() {
+        assert!(profile_dir_of("../..").is_err());
+        assert!(profile_dir_of("..").is_err());
+        if let Ok(dir) = profile_dir_of("work") {
+            assert!(dir.ends_with("WorkHelper/profiles/work"));
+        }
+    }
+}
@@ -0,0 +1,875 @@
+/*!
+ * 发布模块
+ * 供 workhelper-release 命令使用：收集构建产物并计算哈希、签名（配置了签名私钥时），基于上一个版本的安装包
+ * 生成差分补丁，生成 latest.json，通过WebDAV（MKCOL/PUT）上传安装包和清单，支持预演（--dry-run）和回滚上一次发布。
+ * 清单中的安装包条目直接使用 manifest 模块的 Artifact，与客户端解析的格式保持一致。
+ */
+
+use crate::config::{load_publish_webdav_from_env, UpdateChannel};
+use crate::delta;
+use crate::manifest::{arch_from_file_name, Artifact, ArtifactFormat, DeltaPatch};
+use crate::version::parse_version;
+use crate::webdav::{Condition, WebDavClient, WebDavError};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const USAGE: &str = "用法: workhelper-release <命令> [选项]
+
+命令:
+  publish    上传构建产物并更新 latest.json
+  rollback   撤销一次发布，恢复该通道在发布前的清单条目并删除该版本的文件
+
+选项:
+  --version <版本>          发布或回滚的版本，publish 默认为当前 Cargo 版本，rollback 默认为清单中该通道的最新版本
+  --channel <通道>          stable（默认）、beta 或 nightly
+  --bundle-dir <目录>       构建产物目录，默认为 target/release/bundle
+  --changelog <文件>        更新日志文件，默认根据上一个标签以来的 Git 提交生成
+  --rollout <百分比>        灰度推送比例（0-100）
+  --minimum-version <版本>  低于该版本的客户端必须更新
+  --no-delta                不生成差分补丁
+  --sign-key <文件>         签名私钥文件，默认读取 TAURI_SIGNING_PRIVATE_KEY、TAURI_SIGNING_PRIVATE_KEY_PATH 环境变量
+  --dry-run                 只打印将要执行的操作和生成的清单，不修改服务器";
+
+/// 发布前的清单备份文件名，位于版本目录中，回滚时用于恢复
+const PREVIOUS_MANIFEST: &str = "previous-latest.json";
+
+/// 签名私钥（内容或文件路径）的环境变量，与 Tauri 构建时使用的相同
+const SIGNING_KEY_ENV: [&str; 2] = ["TAURI_SIGNING_PRIVATE_KEY", "TAURI_SIGNING_PRIVATE_KEY_PATH"];
+
+/// 清单顶层中属于单个版本的字段，发布或回滚正式版时整体替换
+const RELEASE_FIELDS: [&str; 13] = [
+    "version",
+    "releaseDate",
+    "changelog",
+    "artifacts",
+    "rolloutPercentage",
+    // 旧版发布脚本写入的字段
+    "platform",
+    "downloads",
+    "fileSize",
+    "fileSizeFormatted",
+    "sha256",
+    "mandatory",
+    "minVersion",
+    "signature",
+];
+
+/// 命令行参数
+#[derive(Debug)]
+struct Options {
+    command: String,
+    version: Option<String>,
+    channel: UpdateChannel,
+    bundle_dir: PathBuf,
+    changelog: Option<PathBuf>,
+    rollout: Option<u32>,
+    minimum_version: Option<String>,
+    sign_key: Option<PathBuf>,
+    no_delta: bool,
+    dry_run: bool,
+}
+
+/// 命令入口
+pub fn run(args: Vec<String>) -> Result<(), String> {
+    let options = parse_args(&args)?;
+
+    match options.command.as_str() {
+        "publish" => tauri::async_runtime::block_on(publish(&options)),
+        "rollback" => tauri::async_runtime::block_on(rollback(&options)),
+        _ => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        command: args.first().cloned().unwrap_or_else(|| "help".to_string()),
+        version: None,
+        channel: UpdateChannel::Stable,
+        bundle_dir: Path::new(env!("CARGO_MANIFEST_DIR")).join("target/release/bundle"),
+        changelog: None,
+        rollout: None,
+        minimum_version: None,
+        sign_key: None,
+        no_delta: false,
+        dry_run: false,
+    };
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("参数 {} 缺少取值", arg))
+        };
+
+        match arg.as_str() {
+            "--version" => options.version = Some(value()?.trim_start_matches('v').to_string()),
+            "--channel" => {
+                let channel = value()?;
+                options.channel = serde_json::from_value(Value::String(channel.clone()))
+                    .map_err(|_| format!("未知的更新通道: {}", channel))?;
+            }
+            "--bundle-dir" => options.bundle_dir = PathBuf::from(value()?),
+            "--changelog" => options.changelog = Some(PathBuf::from(value()?)),
+            "--rollout" => {
+                let rollout = value()?;
+                let rollout = rollout
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|rollout| *rollout <= 100)
+                    .ok_or_else(|| format!("灰度比例无效: {}", rollout))?;
+                options.rollout = Some(rollout);
+            }
+            "--minimum-version" => options.minimum_version = Some(value()?),
+            "--sign-key" => options.sign_key = Some(PathBuf::from(value()?)),
+            "--no-delta" => options.no_delta = true,
+            "--dry-run" => options.dry_run = true,
+            other => return Err(format!("未知参数: {}\n\n{}", other, USAGE)),
+        }
+    }
+
+    Ok(options)
+}
+
+// ==================== 发布 ====================
+
+async fn publish(options: &Options) -> Result<(), String> {
+    let version = options
+        .version
+        .clone()
+        .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
+    println!("🚀 发布版本 v{}（{} 通道）", version, options.channel.as_str());
+
+    let signing = options.sign_key.is_some()
+        || SIGNING_KEY_ENV
+            .iter()
+            .any(|name| std::env::var_os(name).is_some_and(|value| !value.is_empty()));
+    if !signing {
+        println!("⚠️ 未配置签名私钥，没有 .sig 签名文件的安装包将不带签名发布");
+    }
+
+    let mut artifacts = collect_artifacts(&options.bundle_dir, &version, signing, options.sign_key.as_deref())?;
+    for (path, artifact) in &artifacts {
+        println!(
+            "📦 {} ({}, {}-{}) sha256={}{}",
+            path.display(),
+            artifact.file_size_formatted.as_deref().unwrap_or(""),
+            artifact.os.as_deref().unwrap_or(""),
+            artifact.arch.as_deref().unwrap_or(""),
+            artifact.sha256.as_deref().unwrap_or(""),
+            if artifact.signature.is_some() { "，已签名" } else { "" }
+        );
+    }
+
+    let changelog = match &options.changelog {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| format!("读取更新日志失败: {} - {}", path.display(), e))?,
+        None => git_changelog(),
+    };
+
+    let webdav = Publisher::from_env(options.dry_run)?;
+    let existing = webdav.get_json("latest.json").await?;
+
+    // 重新发布会用已包含该版本的清单覆盖备份，之后就无法回滚到发布前的状态
+    let backup_path = format!("v{}/{}", version, PREVIOUS_MANIFEST);
+    let published = existing
+        .as_ref()
+        .and_then(|manifest| channel_version(manifest, options.channel))
+        .is_some_and(|published| published == version);
+    if published || webdav.get(&backup_path).await?.is_some() {
+        return Err(format!(
+            "版本 v{} 已经发布过，请先执行 rollback --version {} 撤销后再重新发布",
+            version, version
+        ));
+    }
+
+    // 基于该通道上一个版本的安装包生成差分补丁，生成失败时只发布完整安装包
+    let mut patches = Vec::new();
+    let previous = existing.as_ref().and_then(|manifest| channel_entry(manifest, options.channel));
+    if let (false, Some(previous)) = (options.no_delta, previous) {
+        for (path, artifact) in &mut artifacts {
+            match build_delta(&webdav, previous, path, artifact, &version).await {
+                Ok(Some(patch)) => patches.push(patch),
+                Ok(None) => {}
+                Err(e) => println!("⚠️ 跳过 {} 的差分补丁: {}", path.display(), e),
+            }
+        }
+    }
+
+    let mut entry = json!({
+        "version": version,
+        "releaseDate": utc_timestamp(),
+        "changelog": changelog.trim(),
+        "artifacts": artifacts.iter().map(|(_, artifact)| artifact).collect::<Vec<_>>(),
+    });
+    if let Some(rollout) = options.rollout {
+        entry["rolloutPercentage"] = json!(rollout);
+    }
+
+    let mut manifest = existing.clone().unwrap_or_else(|| json!({}));
+    if let Some(minimum_version) = &options.minimum_version {
+        manifest["minimumVersion"] = json!(minimum_version);
+    }
+    apply_release(&mut manifest, options.channel, entry)?;
+
+    let manifest_content = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("序列化版本信息失败: {}", e))?;
+
+    if options.dry_run {
+        println!("\n📝 将要上传的 latest.json:\n{}", manifest_content);
+        println!("\n✅ 预演完成，未修改服务器");
+        return Ok(());
+    }
+
+    // 先上传安装包，最后更新清单，避免客户端看到尚未上传完成的版本
+    for (path, artifact) in &artifacts {
+        let data = fs::read(path).map_err(|e| format!("读取安装包失败: {} - {}", path.display(), e))?;
+        webdav.put(&artifact.url, data).await?;
+    }
+    for (url, patch) in patches {
+        webdav.put(&url, patch).await?;
+    }
+
+    if let Some(existing) = &existing {
+        let backup = serde_json::to_vec_pretty(existing)
+            .map_err(|e| format!("序列化版本信息失败: {}", e))?;
+        webdav.create(&backup_path, backup).await?;
+    }
+    webdav.put("latest.json", manifest_content.into_bytes()).await?;
+
+    println!("🎉 版本 v{} 发布完成", version);
+    Ok(())
+}
+
+/// 在构建产物目录（及其 nsis、msi 等子目录）中查找指定版本的安装包
+///
+/// signing 表示已配置签名私钥（key_file 为 --sign-key 指定的文件），缺少签名的安装包会先签名
+fn collect_artifacts(
+    bundle_dir: &Path,
+    version: &str,
+    signing: bool,
+    key_file: Option<&Path>,
+) -> Result<Vec<(PathBuf, Artifact)>, String> {
+    let mut dirs = vec![bundle_dir.to_path_buf()];
+    let entries = fs::read_dir(bundle_dir)
+        .map_err(|e| format!("读取构建目录失败: {} - {}", bundle_dir.display(), e))?;
+    dirs.extend(entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()));
+
+    let mut artifacts = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for path in entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file()) {
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if file_name_version(name) != Some(version) {
+                continue;
+            }
+            let Some(format) = ArtifactFormat::from_file_name(name) else {
+                continue;
+            };
+
+            let signature = signature(&path, signing, key_file)?;
+            artifacts.push((path.clone(), build_artifact(&path, name, format, version, signature)?));
+        }
+    }
+
+    if artifacts.is_empty() {
+        return Err(format!(
+            "未在 {} 中找到版本 {} 的安装包，请先执行构建",
+            bundle_dir.display(),
+            version
+        ));
+    }
+    Ok(artifacts)
+}
+
+/// 安装包文件名中的版本号，如 WorkHelper_1.2.0-beta.1_x64-setup.exe 中的 1.2.0-beta.1
+///
+/// Tauri 生成的文件名以 _ 分隔产品名、版本号和架构，取第一个能解析为版本号的部分
+fn file_name_version(name: &str) -> Option<&str> {
+    name.split('_')
+        .find(|part| part.contains('.') && parse_version(part).is_some())
+        .map(|part| part.trim_start_matches('v'))
+}
+
+/// 读取安装包同名的 .sig 签名文件
+///
+/// 配置了签名私钥时，签名文件不存在或比安装包旧则调用 Tauri 签名工具重新签名，仍未得到签名时报错
+fn signature(path: &Path, signing: bool, key_file: Option<&Path>) -> Result<Option<String>, String> {
+    let mut signature_path = path.as_os_str().to_owned();
+    signature_path.push(".sig");
+    let signature_path = PathBuf::from(signature_path);
+
+    if !signing {
+        return Ok(fs::read_to_string(&signature_path).ok().map(|signature| signature.trim().to_string()));
+    }
+
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    if modified(&signature_path) < modified(path) {
+        println!("🔏 签名 {}", path.display());
+        let mut command = Command::new(if cfg!(windows) { "pnpm.cmd" } else { "pnpm" });
+        command
+            .current_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(".."))
+            .args(["tauri", "signer", "sign"]);
+        if let Some(key_file) = key_file {
+            command.arg("--private-key-path").arg(key_file);
+        }
+        let status = command
+            .arg(path)
+            .status()
+            .map_err(|e| format!("启动 Tauri 签名工具失败: {}", e))?;
+        if !status.success() {
+            return Err(format!("签名安装包失败: {}", path.display()));
+        }
+    }
+
+    match fs::read_to_string(&signature_path) {
+        Ok(signature) if !signature.trim().is_empty() => Ok(Some(signature.trim().to_string())),
+        _ => Err(format!("已配置签名私钥，但安装包 {} 没有生成签名", path.display())),
+    }
+}
+
+/// 计算安装包的大小和哈希，生成清单条目
+fn build_artifact(
+    path: &Path,
+    name: &str,
+    format: ArtifactFormat,
+    version: &str,
+    signature: Option<String>,
+) -> Result<Artifact, String> {
+    let data = fs::read(path).map_err(|e| format!("读取安装包失败: {} - {}", path.display(), e))?;
+    let size = data.len() as u64;
+
+    Ok(Artifact {
+        target: None,
+        os: Some(format.os().to_string()),
+        arch: Some(arch_from_file_name(name)),
+        format,
+        // 相对于 base_url 的路径，清单中的镜像同样适用
+        url: format!("v{}/{}/{}", version, format.os(), name),
+        file_name: Some(name.to_string()),
+        file_size: Some(size),
+        file_size_formatted: Some(crate::format_file_size(size)),
+        sha256: Some(crate::delta::sha256_hex(&data)),
+        deltas: Vec::new(),
+        mirrors: Vec::new(),
+        signature,
+    })
+}
+
+/// 以上一个版本中同平台、同格式的安装包为基础生成差分补丁，写入安装包条目并返回补丁路径和内容
+///
+/// 上一个版本没有对应的安装包或哈希，或补丁不比完整安装包小时不生成
+async fn build_delta(
+    webdav: &Publisher,
+    previous: &Value,
+    path: &Path,
+    artifact: &mut Artifact,
+    version: &str,
+) -> Result<Option<(String, Vec<u8>)>, String> {
+    let Some(base_version) = previous["version"].as_str().filter(|base| *base != version) else {
+        return Ok(None);
+    };
+    let bases: Vec<Artifact> = serde_json::from_value(previous["artifacts"].clone()).unwrap_or_default();
+    let Some(base) = bases
+        .into_iter()
+        .find(|base| base.format == artifact.format && base.os == artifact.os && base.arch == artifact.arch)
+    else {
+        return Ok(None);
+    };
+    let Some(base_sha256) = base.sha256 else {
+        return Ok(None);
+    };
+
+    let old = webdav
+        .get(&base.url)
+        .await?
+        .ok_or_else(|| format!("版本 {} 的安装包已不存在", base_version))?;
+    if !delta::hash_matches(&delta::sha256_hex(&old), &base_sha256) {
+        return Err(format!("版本 {} 的安装包校验失败", base_version));
+    }
+    let new = fs::read(path).map_err(|e| format!("读取安装包失败: {} - {}", path.display(), e))?;
+    let patch = tokio::task::spawn_blocking(move || delta::create_patch(&old, &new))
+        .await
+        .map_err(|e| format!("生成差分补丁失败: {}", e))?;
+
+    let size = patch.len() as u64;
+    if size >= artifact.file_size.unwrap_or(u64::MAX) {
+        return Ok(None);
+    }
+
+    let name = format!("{}.from-{}.patch", artifact.file_name.as_deref().unwrap_or_default(), base_version);
+    let url = match artifact.url.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/{}", dir, name),
+        None => name,
+    };
+    println!(
+        "🧩 {} ({}，基于 v{}) sha256={}",
+        url,
+        crate::format_file_size(size),
+        base_version,
+        delta::sha256_hex(&patch)
+    );
+    artifact.deltas.push(DeltaPatch {
+        base_version: base_version.to_string(),
+        url: url.clone(),
+        file_size: size,
+        sha256: delta::sha256_hex(&patch),
+        base_sha256,
+    });
+    Ok(Some((url, patch)))
+}
+
+/// 将版本条目写入清单：正式版替换顶层条目，预发布版写入 channels 中对应的通道
+fn apply_release(manifest: &mut Value, channel: UpdateChannel, entry: Value) -> Result<(), String> {
+    let manifest = manifest.as_object_mut().ok_or("服务器上的 latest.json 格式错误")?;
+    let Value::Object(entry) = entry else {
+        return Err("版本条目格式错误".to_string());
+    };
+
+    if channel == UpdateChannel::Stable {
+        for field in RELEASE_FIELDS {
+            manifest.remove(field);
+        }
+        manifest.extend(entry);
+    } else {
+        let channels = manifest
+            .entry("channels")
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .ok_or("latest.json 中的 channels 格式错误")?;
+        channels.insert(channel.as_str().to_string(), Value::Object(entry));
+    }
+    Ok(())
+}
+
+/// 根据上一个标签以来的 Git 提交生成更新日志
+fn git_changelog() -> String {
+    let git = |args: &[&str]| -> Option<String> {
+        let output = Command::new("git").args(args).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let range = match git(&["describe", "--tags", "--abbrev=0"]) {
+        Some(tag) if !tag.is_empty() => format!("{}..HEAD", tag),
+        _ => "HEAD".to_string(),
+    };
+
+    match git(&["log", &range, "--pretty=format:- %s (%h)"]) {
+        Some(log) if !log.is_empty() => log,
+        _ => "本次更新包含功能改进和问题修复".to_string(),
+    }
+}
+
+/// 当前UTC时间，ISO 8601 格式
+fn utc_timestamp() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = (secs / 86400, secs % 86400);
+
+    // 由自1970-01-01起的天数换算公历日期
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+// ==================== 回滚 ====================
+
+async fn rollback(options: &Options) -> Result<(), String> {
+    let webdav = Publisher::from_env(options.dry_run)?;
+
+    let version = match &options.version {
+        Some(version) => version.clone(),
+        None => webdav
+            .get_json("latest.json")
+            .await?
+            .and_then(|manifest| channel_version(&manifest, options.channel))
+            .ok_or_else(|| {
+                format!(
+                    "无法从 latest.json 读取 {} 通道的最新版本，请使用 --version 指定",
+                    options.channel.as_str()
+                )
+            })?,
+    };
+    println!("🔄 回滚版本 v{}", version);
+
+    let current = webdav.get_json("latest.json").await?;
+    let previous = webdav
+        .get_json(&format!("v{}/{}", version, PREVIOUS_MANIFEST))
+        .await?;
+
+    match current {
+        // 只恢复该通道的条目，其他通道在此之后的发布保持不变
+        Some(mut manifest) => {
+            if channel_version(&manifest, options.channel).as_deref() != Some(version.as_str()) {
+                return Err(format!(
+                    "v{} 不是 {} 通道当前发布的版本，回滚会覆盖之后的发布",
+                    version,
+                    options.channel.as_str()
+                ));
+            }
+            let previous = previous.unwrap_or_else(|| json!({}));
+            restore_channel(&mut manifest, &previous, options.channel)?;
+
+            if is_empty_manifest(&manifest) {
+                println!("📝 v{} 是首次发布，删除 latest.json", version);
+                webdav.delete("latest.json").await?;
+            } else {
+                println!(
+                    "📝 恢复 {} 通道在发布 v{} 之前的条目（版本 {}）",
+                    options.channel.as_str(),
+                    version,
+                    channel_version(&manifest, options.channel).as_deref().unwrap_or("无")
+                );
+                let content = serde_json::to_vec_pretty(&manifest)
+                    .map_err(|e| format!("序列化版本信息失败: {}", e))?;
+                webdav.put("latest.json", content).await?;
+            }
+        }
+        None => println!("📝 服务器上没有 latest.json，只删除 v{} 的文件", version),
+    }
+    webdav.delete(&format!("v{}/", version)).await?;
+
+    if options.dry_run {
+        println!("✅ 预演完成，未修改服务器");
+    } else {
+        println!("🎉 已回滚版本 v{}", version);
+    }
+    Ok(())
+}
+
+/// 将清单中指定通道的条目恢复为备份中的条目，备份中没有该通道时删除
+///
+/// 正式版恢复顶层的版本字段和 minimumVersion（发布正式版时可能一并修改），预发布版只恢复 channels 中的对应条目
+fn restore_channel(manifest: &mut Value, previous: &Value, channel: UpdateChannel) -> Result<(), String> {
+    let manifest = manifest.as_object_mut().ok_or("服务器上的 latest.json 格式错误")?;
+
+    if channel == UpdateChannel::Stable {
+        for field in RELEASE_FIELDS.iter().chain(["minimumVersion"].iter()) {
+            match previous.get(*field) {
+                Some(value) => manifest.insert(field.to_string(), value.clone()),
+                None => manifest.remove(*field),
+            };
+        }
+    } else if let Some(channels) = manifest.get_mut("channels").and_then(Value::as_object_mut) {
+        match channel_entry(previous, channel) {
+            Some(entry) => channels.insert(channel.as_str().to_string(), entry.clone()),
+            None => channels.remove(channel.as_str()),
+        };
+    }
+    Ok(())
+}
+
+/// 清单中是否已没有任何版本
+fn is_empty_manifest(manifest: &Value) -> bool {
+    manifest.get("version").is_none()
+        && manifest["channels"].as_object().map_or(true, |channels| channels.is_empty())
+}
+
+/// 清单中指定通道的版本条目：正式版在顶层，预发布版在 channels 中
+fn channel_entry(manifest: &Value, channel: UpdateChannel) -> Option<&Value> {
+    if channel == UpdateChannel::Stable {
+        Some(manifest)
+    } else {
+        manifest.get("channels")?.get(channel.as_str())
+    }
+}
+
+/// 清单中指定通道的最新版本
+fn channel_version(manifest: &Value, channel: UpdateChannel) -> Option<String> {
+    channel_entry(manifest, channel)?["version"].as_str().map(|version| version.to_string())
+}
+
+// ==================== WebDAV ====================
+
+/// 发布使用的WebDAV连接，路径均相对于配置中的 remote_path，预演时只打印写操作
+struct Publisher {
+    webdav: WebDavClient,
+    dry_run: bool,
+}
+
+impl Publisher {
+    /// 从环境变量（或 .env 文件）读取WebDAV配置
+    fn from_env(dry_run: bool) -> Result<Self, String> {
+        let config = load_publish_webdav_from_env()
+            .ok_or("缺少WebDAV配置，请设置 WEBDAV_URL、WEBDAV_USERNAME、WEBDAV_PASSWORD、WEBDAV_BASE_URL 环境变量")?;
+
+        Ok(Self {
+            webdav: WebDavClient::from_config(&config),
+            dry_run,
+        })
+    }
+
+    /// 读取文件，不存在时返回 None
+    async fn get(&self, path: &str) -> Result<Option<Vec<u8>>, String> {
+        match self.webdav.get(path).await {
+            Ok(fetched) => Ok(Some(fetched.data)),
+            Err(WebDavError::NotFound) => Ok(None),
+            Err(e) => Err(format!("读取 {} 失败: {}", path, e)),
+        }
+    }
+
+    /// 读取JSON文件，不存在时返回 None
+    async fn get_json(&self, path: &str) -> Result<Option<Value>, String> {
+        match self.get(path).await? {
+            Some(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .map_err(|e| format!("解析 {} 失败: {}", path, e)),
+            None => Ok(None),
+        }
+    }
+
+    /// 上传文件，先逐级创建所在目录
+    async fn put(&self, path: &str, data: Vec<u8>) -> Result<(), String> {
+        println!("📤 PUT {} ({})", self.webdav.url(path), crate::format_file_size(data.len() as u64));
+        if self.dry_run {
+            return Ok(());
+        }
+
+        if let Some((dir, _)) = path.rsplit_once('/') {
+            self.webdav.mkcol_all(dir).await?;
+        }
+        self.webdav.put(path, data, Condition::None).await?;
+        Ok(())
+    }
+
+    /// 上传新文件，文件已存在时报错而不覆盖
+    async fn create(&self, path: &str, data: Vec<u8>) -> Result<(), String> {
+        println!("📤 PUT {} ({}，不覆盖)", self.webdav.url(path), crate::format_file_size(data.len() as u64));
+        if self.dry_run {
+            return Ok(());
+        }
+
+        if let Some((dir, _)) = path.rsplit_once('/') {
+            self.webdav.mkcol_all(dir).await?;
+        }
+        match self.webdav.put(path, data, Condition::IfNoneMatch).await {
+            Ok(_) => Ok(()),
+            Err(WebDavError::PreconditionFailed) => Err(format!("{} 已存在，不能覆盖", path)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 删除文件或目录，不存在时视为成功
+    async fn delete(&self, path: &str) -> Result<(), String> {
+        println!("🗑️ DELETE {}", self.webdav.url(path));
+        if self.dry_run {
+            return Ok(());
+        }
+
+        match self.webdav.delete(path, None).await {
+            Ok(()) | Err(WebDavError::NotFound) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_args_reads_options() {
+        let options = parse_args(&args(&[
+            "publish",
+            "--version",
+            "v1.2.0-beta.1",
+            "--channel",
+            "beta",
+            "--bundle-dir",
+            "out/bundle",
+            "--rollout",
+            "25",
+            "--minimum-version",
+            "1.0.0",
+            "--no-delta",
+            "--dry-run",
+        ]))
+        .unwrap();
+
+        assert_eq!(options.command, "publish");
+        assert_eq!(options.version.as_deref(), Some("1.2.0-beta.1"), "去掉 v 前缀");
+        assert_eq!(options.channel, UpdateChannel::Beta);
+        assert_eq!(options.bundle_dir, PathBuf::from("out/bundle"));
+        assert_eq!(options.rollout, Some(25));
+        assert_eq!(options.minimum_version.as_deref(), Some("1.0.0"));
+        assert!(options.no_delta);
+        assert!(options.dry_run);
+    }
+
+    #[test]
+    fn parse_args_defaults() {
+        let options = parse_args(&[]).unwrap();
+        assert_eq!(options.command, "help");
+        assert_eq!(options.channel, UpdateChannel::Stable);
+        assert!(options.version.is_none());
+        assert!(!options.dry_run);
+    }
+
+    #[test]
+    fn parse_args_rejects_invalid_arguments() {
+        assert!(parse_args(&args(&["publish", "--version"])).unwrap_err().contains("缺少取值"));
+        assert!(parse_args(&args(&["publish", "--channel", "alpha"])).unwrap_err().contains("未知的更新通道"));
+        assert!(parse_args(&args(&["publish", "--rollout", "101"])).unwrap_err().contains("灰度比例无效"));
+        assert!(parse_args(&args(&["publish", "--rollout", "-1"])).is_err());
+        assert!(parse_args(&args(&["publish", "--force"])).unwrap_err().contains("未知参数"));
+    }
+
+    #[test]
+    fn apply_release_replaces_stable_fields() {
+        let mut manifest = json!({
+            "version": "1.1.0",
+            "rolloutPercentage": 10,
+            "downloads": {"windows": "old.exe"},
+            "minimumVersion": "1.0.0",
+            "channels": {"beta": {"version": "1.2.0-beta.1"}},
+        });
+
+        apply_release(&mut manifest, UpdateChannel::Stable, json!({"version": "1.2.0", "artifacts": []})).unwrap();
+
+        assert_eq!(manifest["version"], "1.2.0");
+        assert!(manifest.get("rolloutPercentage").is_none(), "上一个版本的灰度比例不应保留");
+        assert!(manifest.get("downloads").is_none(), "旧格式字段应被移除");
+        assert_eq!(manifest["minimumVersion"], "1.0.0", "清单级字段保留");
+        assert_eq!(manifest["channels"]["beta"]["version"], "1.2.0-beta.1", "其他通道保留");
+    }
+
+    #[test]
+    fn apply_release_writes_prerelease_channel() {
+        let mut manifest = json!({"version": "1.1.0"});
+
+        apply_release(&mut manifest, UpdateChannel::Nightly, json!({"version": "1.2.0-nightly.3"})).unwrap();
+        apply_release(&mut manifest, UpdateChannel::Beta, json!({"version": "1.2.0-beta.1"})).unwrap();
+
+        assert_eq!(manifest["version"], "1.1.0", "正式版条目不变");
+        assert_eq!(manifest["channels"]["nightly"]["version"], "1.2.0-nightly.3");
+        assert_eq!(manifest["channels"]["beta"]["version"], "1.2.0-beta.1");
+
+        assert!(apply_release(&mut json!([]), UpdateChannel::Stable, json!({})).is_err());
+        assert!(apply_release(&mut json!({"channels": 1}), UpdateChannel::Beta, json!({})).is_err());
+    }
+
+    #[test]
+    fn channel_entry_reads_top_level_or_channels() {
+        let manifest = json!({
+            "version": "1.1.0",
+            "channels": {"beta": {"version": "1.2.0-beta.1"}},
+        });
+
+        assert_eq!(channel_version(&manifest, UpdateChannel::Stable).as_deref(), Some("1.1.0"));
+        assert_eq!(channel_version(&manifest, UpdateChannel::Beta).as_deref(), Some("1.2.0-beta.1"));
+        assert!(channel_entry(&manifest, UpdateChannel::Nightly).is_none());
+        assert!(channel_version(&json!({}), UpdateChannel::Stable).is_none());
+    }
+
+    #[test]
+    fn restore_channel_only_touches_rolled_back_channel() {
+        let previous = json!({
+            "version": "1.1.0",
+            "artifacts": [{"url": "v1.1.0/windows/a.exe"}],
+            "channels": {"beta": {"version": "1.2.0-beta.1"}},
+        });
+        // 备份之后又发布了 nightly 版本
+        let current = json!({
+            "version": "1.2.0",
+            "artifacts": [{"url": "v1.2.0/windows/a.exe"}],
+            "rolloutPercentage": 20,
+            "minimumVersion": "1.1.0",
+            "channels": {"beta": {"version": "1.2.0-beta.1"}, "nightly": {"version": "1.3.0-nightly.1"}},
+        });
+
+        let mut manifest = current.clone();
+        restore_channel(&mut manifest, &previous, UpdateChannel::Stable).unwrap();
+        assert_eq!(manifest["version"], "1.1.0");
+        assert_eq!(manifest["artifacts"][0]["url"], "v1.1.0/windows/a.exe");
+        assert!(manifest.get("rolloutPercentage").is_none());
+        assert!(manifest.get("minimumVersion").is_none());
+        assert_eq!(manifest["channels"]["nightly"]["version"], "1.3.0-nightly.1", "之后发布的 nightly 保留");
+
+        // 回滚首次发布的 nightly 通道，备份中没有该通道
+        let mut manifest = current.clone();
+        restore_channel(&mut manifest, &previous, UpdateChannel::Nightly).unwrap();
+        assert!(manifest["channels"].get("nightly").is_none());
+        assert_eq!(manifest["version"], "1.2.0", "正式版不受影响");
+        assert_eq!(manifest["channels"]["beta"]["version"], "1.2.0-beta.1");
+    }
+
+    #[test]
+    fn empty_manifest_after_rolling_back_first_release() {
+        let mut manifest = json!({"version": "1.0.0", "artifacts": [], "channels": {}});
+        restore_channel(&mut manifest, &json!({}), UpdateChannel::Stable).unwrap();
+        assert!(is_empty_manifest(&manifest));
+
+        assert!(!is_empty_manifest(&json!({"channels": {"beta": {"version": "1.0.0-beta.1"}}})));
+    }
+
+    #[test]
+    fn file_name_version_reads_tauri_bundle_names() {
+        assert_eq!(file_name_version("WorkHelper_1.2.1_x64-setup.exe"), Some("1.2.1"));
+        assert_eq!(file_name_version("WorkHelper_1.2.10_x64_en-US.msi"), Some("1.2.10"));
+        assert_eq!(file_name_version("work-helper_1.2.0-beta.1_amd64.AppImage"), Some("1.2.0-beta.1"));
+        assert_eq!(file_name_version("WorkHelper_1.2.0_aarch64.dmg"), Some("1.2.0"));
+        assert_eq!(file_name_version("WorkHelper.exe"), None);
+    }
+
+    #[test]
+    fn collect_artifacts_matches_exact_version() {
+        let dir = std::env::temp_dir().join(format!("workhelper-bundle-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("nsis")).unwrap();
+        fs::create_dir_all(dir.join("deb")).unwrap();
+        fs::write(dir.join("nsis/WorkHelper_1.2.1_x64-setup.exe"), b"1.2.1").unwrap();
+        fs::write(dir.join("nsis/WorkHelper_1.2.10_x64-setup.exe"), b"1.2.10").unwrap();
+        fs::write(dir.join("nsis/WorkHelper_1.2.1-beta.1_x64-setup.exe"), b"beta").unwrap();
+        fs::write(dir.join("deb/work-helper_1.2.1_amd64.deb"), b"deb").unwrap();
+        fs::write(dir.join("deb/work-helper_1.2.1_amd64.deb.sig"), b"signature\n").unwrap();
+
+        let result = collect_artifacts(&dir, "1.2.1", false, None);
+        let missing = collect_artifacts(&dir, "1.3.0", false, None);
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut artifacts: Vec<Artifact> = result.unwrap().into_iter().map(|(_, artifact)| artifact).collect();
+        artifacts.sort_by(|a, b| a.url.cmp(&b.url));
+        let urls: Vec<&str> = artifacts.iter().map(|artifact| artifact.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            ["v1.2.1/linux/work-helper_1.2.1_amd64.deb", "v1.2.1/windows/WorkHelper_1.2.1_x64-setup.exe"]
+        );
+        assert_eq!(artifacts[0].signature.as_deref(), Some("signature"));
+        assert_eq!(artifacts[1].file_size, Some(5));
+        assert!(missing.unwrap_err().contains("未在"));
+    }
+}
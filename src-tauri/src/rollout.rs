@@ -0,0 +1,308 @@
+/*!
+ * 灰度发布模块
+ * 负责按安装实例分桶控制新版本的推送比例，并判断当前版本是否低于最低版本或已被禁用而必须更新
+ */
+
+use crate::version::{compare_versions, is_newer_version, parse_version};
+use semver::VersionReq;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
+
+/// 检查更新的结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateDecision {
+    /// 是否向当前安装实例提供新版本
+    pub has_update: bool,
+    /// 是否必须更新
+    pub mandatory: bool,
+    /// 是否在灰度推送范围内
+    pub rollout_eligible: bool,
+}
+
+/// 更新清单中的发布策略
+#[derive(Debug, Clone)]
+pub struct UpdatePolicy {
+    /// 推送比例（0-100），缺省为100即全量推送
+    pub rollout_percentage: u32,
+    /// 低于该版本时必须更新
+    pub minimum_version: Option<String>,
+    /// 必须立即升级的版本，支持具体版本号或范围（如 "<0.0.3"）
+    pub blocked_versions: Vec<String>,
+}
+
+impl UpdatePolicy {
+    /// 从版本条目读取发布策略，条目中缺失的字段使用清单顶层的值
+    pub fn from_manifest(manifest: &Value, release: &Value) -> Self {
+        let field = |name: &str| -> Option<&Value> {
+            release.get(name).or_else(|| manifest.get(name))
+        };
+
+        let rollout_percentage = field("rolloutPercentage")
+            .and_then(|v| v.as_u64())
+            .map(|v| v.min(100) as u32)
+            .unwrap_or(100);
+
+        let minimum_version = field("minimumVersion")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string());
+
+        let blocked_versions = field("blockedVersions")
+            .and_then(|v| v.as_array())
+            .map(|versions| {
+                versions
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|v| v.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            rollout_percentage,
+            minimum_version,
+            blocked_versions,
+        }
+    }
+
+    /// 当前版本是否必须更新（低于最低版本或在禁用列表中）
+    pub fn is_mandatory(&self, current: &str) -> bool {
+        let Some(current_version) = parse_version(current) else {
+            return false;
+        };
+
+        if let Some(minimum) = self.minimum_version.as_deref().and_then(parse_version) {
+            if compare_versions(&current_version, &minimum) == Ordering::Less {
+                log::info!("当前版本 {} 低于最低版本 {}，必须更新", current, minimum);
+                return true;
+            }
+        }
+
+        let blocked = self.blocked_versions.iter().any(|blocked| match parse_version(blocked) {
+            Some(version) => compare_versions(&current_version, &version) == Ordering::Equal,
+            None => VersionReq::parse(blocked)
+                .map(|req| req.matches(&current_version))
+                .unwrap_or(false),
+        });
+        if blocked {
+            log::info!("当前版本 {} 已被禁用，必须更新", current);
+        }
+        blocked
+    }
+
+    /// 判断是否向当前安装实例推送最新版本
+    ///
+    /// 低于最低版本或当前版本被禁用时必须更新，且不受灰度比例限制
+    pub fn evaluate(&self, current: &str, latest: &str, install_id: &str) -> UpdateDecision {
+        let is_newer = is_newer_version(current, latest);
+        let mandatory = is_newer && self.is_mandatory(current);
+        let rollout_eligible = mandatory || self.is_rollout_eligible(install_id, latest);
+        UpdateDecision {
+            has_update: is_newer && rollout_eligible,
+            mandatory,
+            rollout_eligible,
+        }
+    }
+
+    /// 当前安装实例是否在本次灰度推送范围内
+    pub fn is_rollout_eligible(&self, install_id: &str, version: &str) -> bool {
+        if self.rollout_percentage >= 100 {
+            return true;
+        }
+        rollout_bucket(install_id, version) < self.rollout_percentage
+    }
+}
+
+/// 计算安装实例在某个版本灰度中的分桶（0-99）
+///
+/// 分桶由安装ID和版本号共同决定，同一安装实例对同一版本的结果始终一致，
+/// 不同版本的灰度则会落到不同的用户群。
+pub fn rollout_bucket(install_id: &str, version: &str) -> u32 {
+    // FNV-1a，保证不同编译器版本下结果一致
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in install_id.bytes().chain(std::iter::once(b':')).chain(version.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    (hash % 100) as u32
+}
+
+/// 获取本机安装ID，首次调用时生成并保存
+pub fn install_id() -> String {
+    let path = match install_id_path() {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("{} - 使用临时安装ID", e);
+            return uuid::Uuid::new_v4().to_string();
+        }
+    };
+
+    if let Ok(id) = fs::read_to_string(&path) {
+        let id = id.trim();
+        if !id.is_empty() {
+            return id.to_string();
+        }
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    if let Err(e) = fs::write(&path, &id) {
+        log::warn!("保存安装ID失败: {}", e);
+    }
+    id
+}
+
+fn install_id_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_local_dir()
+        .ok_or("无法获取应用数据目录")?
+        .join("WorkHelper");
+
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| format!("创建应用数据目录失败: {}", e))?;
+    }
+
+    Ok(data_dir.join("install_id"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const INSTALL_ID: &str = "00000000-0000-0000-0000-000000000000";
+
+    fn policy(rollout_percentage: u32, minimum_version: Option<&str>, blocked_versions: &[&str]) -> UpdatePolicy {
+        UpdatePolicy {
+            rollout_percentage,
+            minimum_version: minimum_version.map(str::to_string),
+            blocked_versions: blocked_versions.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn rollout_bucket_is_pinned() {
+        // 分桶结果写入了已发布版本的灰度，算法变化会让已推送的用户群发生变化
+        let cases = [
+            (INSTALL_ID, "1.2.0", 62),
+            (INSTALL_ID, "1.3.0", 25),
+            ("4f1c2a9e-7b3d-4e8a-9c1f-2d6e5a8b0c7d", "1.2.0", 17),
+            ("", "", 89),
+        ];
+
+        for (install_id, version, expected) in cases {
+            assert_eq!(rollout_bucket(install_id, version), expected, "{} / {}", install_id, version);
+        }
+    }
+
+    #[test]
+    fn rollout_edges() {
+        let ids: Vec<String> = (0..200).map(|i| format!("install-{}", i)).collect();
+        for id in &ids {
+            assert!(!policy(0, None, &[]).is_rollout_eligible(id, "1.2.0"), "0% 时不推送给 {}", id);
+            assert!(policy(100, None, &[]).is_rollout_eligible(id, "1.2.0"), "100% 时推送给 {}", id);
+        }
+
+        // 分桶为62的实例在62%时不推送，63%时推送
+        assert!(!policy(62, None, &[]).is_rollout_eligible(INSTALL_ID, "1.2.0"));
+        assert!(policy(63, None, &[]).is_rollout_eligible(INSTALL_ID, "1.2.0"));
+    }
+
+    #[test]
+    fn from_manifest_fields() {
+        let manifest = json!({
+            "version": "1.2.0",
+            "rolloutPercentage": 30,
+            "minimumVersion": "1.0.0",
+            "blockedVersions": ["1.1.0"],
+        });
+
+        // 条目中缺失的字段使用顶层的值
+        let parsed = UpdatePolicy::from_manifest(&manifest, &json!({"version": "1.2.0"}));
+        assert_eq!(parsed.rollout_percentage, 30);
+        assert_eq!(parsed.minimum_version.as_deref(), Some("1.0.0"));
+        assert_eq!(parsed.blocked_versions, vec!["1.1.0"]);
+
+        // 条目中的字段优先
+        let release = json!({"version": "1.3.0-beta.1", "rolloutPercentage": 5, "blockedVersions": []});
+        let parsed = UpdatePolicy::from_manifest(&manifest, &release);
+        assert_eq!(parsed.rollout_percentage, 5);
+        assert_eq!(parsed.minimum_version.as_deref(), Some("1.0.0"));
+        assert!(parsed.blocked_versions.is_empty());
+
+        // 缺省为全量推送，超过100按100处理
+        let parsed = UpdatePolicy::from_manifest(&json!({}), &json!({}));
+        assert_eq!(parsed.rollout_percentage, 100);
+        assert!(parsed.minimum_version.is_none() && parsed.blocked_versions.is_empty());
+        let parsed = UpdatePolicy::from_manifest(&json!({"rolloutPercentage": 250}), &json!({}));
+        assert_eq!(parsed.rollout_percentage, 100);
+    }
+
+    #[test]
+    fn minimum_version_cases() {
+        let cases = [
+            ("0.9.9", true),
+            ("1.0.0-beta.1", true),
+            ("1.0.0", false),
+            ("v1.0.1", false),
+            ("invalid", false),
+        ];
+
+        let minimum = policy(100, Some("1.0.0"), &[]);
+        for (current, expected) in cases {
+            assert_eq!(minimum.is_mandatory(current), expected, "最低版本 1.0.0，当前 {}", current);
+        }
+
+        // 最低版本无法解析时忽略
+        assert!(!policy(100, Some("latest"), &[]).is_mandatory("0.1.0"));
+    }
+
+    #[test]
+    fn blocked_versions_cases() {
+        let blocked = policy(100, None, &["0.1.5", "<0.0.3", ">=0.2.0, <0.2.4", "not a version"]);
+        let cases = [
+            ("0.1.5", true),
+            ("v0.1.5", true),
+            ("0.1.5+build.2", true),
+            ("0.1.6", false),
+            ("0.0.2", true),
+            ("0.0.3", false),
+            ("0.2.0", true),
+            ("0.2.3", true),
+            ("0.2.4", false),
+        ];
+
+        for (current, expected) in cases {
+            assert_eq!(blocked.is_mandatory(current), expected, "当前 {}", current);
+        }
+    }
+
+    #[test]
+    fn evaluate_flags() {
+        let decision = |has_update, mandatory, rollout_eligible| UpdateDecision {
+            has_update,
+            mandatory,
+            rollout_eligible,
+        };
+        // INSTALL_ID 在 1.2.0 灰度中的分桶为62
+        let cases = [
+            // 全量推送
+            (policy(100, None, &[]), "1.1.0", decision(true, false, true)),
+            // 不在灰度范围内
+            (policy(10, None, &[]), "1.1.0", decision(false, false, false)),
+            // 在灰度范围内
+            (policy(80, None, &[]), "1.1.0", decision(true, false, true)),
+            // 低于最低版本时不受灰度限制
+            (policy(0, Some("1.1.5"), &[]), "1.1.0", decision(true, true, true)),
+            // 当前版本被禁用时不受灰度限制
+            (policy(0, None, &["1.1.0"]), "1.1.0", decision(true, true, true)),
+            // 已是最新版本时不强制更新
+            (policy(100, Some("1.3.0"), &["1.2.0"]), "1.2.0", decision(false, false, true)),
+            (policy(0, Some("1.3.0"), &[]), "1.3.0", decision(false, false, false)),
+        ];
+
+        for (policy, current, expected) in cases {
+            assert_eq!(policy.evaluate(current, "1.2.0", INSTALL_ID), expected, "{:?}，当前 {}", policy, current);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+/*!
+ * 后台更新检查模块
+ * 启动时及按配置的间隔在后台检查更新，遵循"跳过此版本"和"稍后提醒"的选择，
+ * 发现新版本时发送 update-available 事件，并可在后台限速预先下载更新包
+ */
+
+use crate::config::load_config;
+use crate::{download, DownloadStatus, UpdateCheckResult, VersionInfo};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use std::sync::OnceLock;
+use tokio::sync::Notify;
+use tokio::time::{sleep, Duration};
+
+/// 启动后延迟检查的时间，避免与界面初始化争抢资源
+const STARTUP_DELAY: Duration = Duration::from_secs(10);
+
+/// 未启用定时检查时重新读取配置的间隔
+const IDLE_RECHECK: Duration = Duration::from_secs(3600);
+
+/// 配置文件修改后唤醒等待中的检查任务，按新的检查间隔重新计时
+fn reschedule_signal() -> &'static Notify {
+    static RESCHEDULE: OnceLock<Notify> = OnceLock::new();
+    RESCHEDULE.get_or_init(Notify::new)
+}
+
+/// 配置修改后重新计算下次检查时间
+pub fn reschedule() {
+    reschedule_signal().notify_one();
+}
+
+/// 后台预下载完成事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateDownloadedEvent {
+    pub version: String,
+    pub file_path: String,
+}
+
+/// 启动后台更新检查任务
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if load_config().update.check_on_startup {
+            sleep(STARTUP_DELAY).await;
+            check_once(&app).await;
+        }
+
+        loop {
+            // 每轮重新读取配置，修改检查间隔后无需重启
+            let hours = load_config().update.check_interval_hours;
+            let wait = if hours == 0 { IDLE_RECHECK } else { Duration::from_secs(hours * 3600) };
+
+            tokio::select! {
+                _ = sleep(wait) => {}
+                _ = reschedule_signal().notified() => continue,
+            }
+            if hours > 0 {
+                check_once(&app).await;
+            }
+        }
+    });
+}
+
+/// 执行一次后台检查
+async fn check_once(app: &AppHandle) {
+    log::info!("后台检查更新");
+    let result = crate::run_update_check().await;
+
+    let Some(version_info) = result.version_info.as_ref().filter(|_| result.has_update) else {
+        return;
+    };
+
+    if !should_notify(&result, version_info) {
+        return;
+    }
+
+    if let Err(e) = app.emit("update-available", &result) {
+        log::warn!("发送新版本事件失败: {}", e);
+    }
+
+    if load_config().update.background_download {
+        pre_download(app, version_info).await;
+    }
+}
+
+/// 根据用户的"跳过此版本"和"稍后提醒"选择判断是否提示，必须更新的版本始终提示
+fn should_notify(result: &UpdateCheckResult, version_info: &VersionInfo) -> bool {
+    if result.mandatory {
+        return true;
+    }
+
+    let config = load_config().update;
+    if config.skipped_version.as_deref() == Some(version_info.version.as_str()) {
+        log::info!("用户已跳过版本 {}，不再提示", version_info.version);
+        return false;
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if config.remind_after.is_some_and(|remind_after| now < remind_after) {
+        log::info!("用户选择稍后提醒，暂不提示版本 {}", version_info.version);
+        return false;
+    }
+
+    true
+}
+
+/// 在后台以低优先级（限速）预先下载更新包
+async fn pre_download(app: &AppHandle, version_info: &VersionInfo) {
+    let Ok(updates_dir) = crate::get_updates_directory() else {
+        return;
+    };
+    match crate::update_file_path(&updates_dir, &version_info.file_name) {
+        Ok(path) if !path.exists() => {}
+        Ok(_) => return,
+        Err(e) => {
+            log::warn!("跳过后台下载: {}", e);
+            return;
+        }
+    }
+
+    let rate_limit = load_config().update.background_download_rate_kb * 1024;
+    let manager = app.state::<download::DownloadManager>();
+    let active = match manager.register(&version_info.version, Some(rate_limit)) {
+        Ok(active) => active,
+        Err(e) => {
+            log::info!("跳过后台下载: {}", e);
+            return;
+        }
+    };
+
+    log::info!("开始后台下载更新包: {}", version_info.file_name);
+    let result = crate::download_update_package(
+        app,
+        &active,
+        &version_info.download_url,
+        &version_info.mirrors,
+        &version_info.file_name,
+        version_info.file_size,
+        version_info.sha256.as_deref(),
+        version_info.delta.as_ref(),
+    )
+    .await;
+    manager.finish(&version_info.version);
+
+    match result {
+        Ok(result) => match (result.status, result.file_path) {
+            (DownloadStatus::Completed, Some(file_path)) => {
+                let event = UpdateDownloadedEvent {
+                    version: version_info.version.clone(),
+                    file_path,
+                };
+                if let Err(e) = app.emit("update-downloaded", &event) {
+                    log::warn!("发送更新包下载完成事件失败: {}", e);
+                }
+            }
+            (status, _) => log::info!("后台下载未完成: {:?} {}", status, result.error.unwrap_or_default()),
+        },
+        Err(e) => log::warn!("后台下载更新包失败: {}", e),
+    }
+}
@@ -0,0 +1,227 @@
+/*!
+ * 凭据存储模块
+ * 将密码、令牌等敏感信息保存到系统钥匙串（Windows 凭据管理器、macOS 钥匙串、Linux Secret Service），
+ * 系统没有可用的钥匙串时退回到本机的加密文件。配置文件中只保存 `secret://<名称>` 形式的引用
+ */
+
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+
+/// 钥匙串中的服务名
+const SERVICE: &str = "WorkHelper";
+
+/// 配置文件中凭据引用的前缀
+const REFERENCE_PREFIX: &str = "secret://";
+
+/// 加密文件与密钥文件名（钥匙串不可用时使用）
+const FALLBACK_FILE: &str = "secrets.enc";
+const FALLBACK_KEY_FILE: &str = "secrets.key";
+
+/// 已读取的凭据缓存，避免每次加载配置都访问钥匙串
+///
+/// 缓存只是钥匙串的副本，其他线程持有锁时 panic 也继续使用
+fn cache() -> MutexGuard<'static, HashMap<String, String>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 生成配置文件中保存的凭据引用
+pub fn reference(name: &str) -> String {
+    format!("{}{}", REFERENCE_PREFIX, name)
+}
+
+/// 解析凭据引用，不是引用时返回 None
+pub fn parse_reference(value: &str) -> Option<&str> {
+    value.strip_prefix(REFERENCE_PREFIX)
+}
+
+/// 保存凭据，值为空时删除
+pub fn set_secret(name: &str, value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return delete_secret(name);
+    }
+    if cache().get(name).map(String::as_str) == Some(value) {
+        return Ok(());
+    }
+
+    match keyring_entry(name).and_then(|entry| entry.set_password(value)) {
+        Ok(()) => {
+            // 钥匙串恢复可用后，清理之前写入加密文件的同名凭据
+            if let Err(e) = update_fallback(|secrets| secrets.remove(name).is_some()) {
+                log::warn!("清理加密文件中的凭据失败: {}", e);
+            }
+        }
+        Err(e) if keyring_unavailable(&e) => {
+            log::warn!("系统钥匙串不可用，凭据 {} 保存到加密文件: {}", name, e);
+            update_fallback(|secrets| {
+                secrets.insert(name.to_string(), value.to_string());
+                true
+            })?;
+        }
+        Err(e) => return Err(format!("保存凭据 {} 失败: {}", name, e)),
+    }
+
+    cache().insert(name.to_string(), value.to_string());
+    log::info!("凭据已保存: {}", name);
+    Ok(())
+}
+
+/// 读取凭据，不存在时返回 None
+pub fn get_secret(name: &str) -> Result<Option<String>, String> {
+    if let Some(value) = cache().get(name) {
+        return Ok(Some(value.clone()));
+    }
+
+    let value = match keyring_entry(name).and_then(|entry| entry.get_password()) {
+        Ok(value) => Some(value),
+        Err(keyring::Error::NoEntry) => load_fallback()?.remove(name),
+        Err(e) if keyring_unavailable(&e) => {
+            log::debug!("系统钥匙串不可用，从加密文件读取凭据 {}: {}", name, e);
+            load_fallback()?.remove(name)
+        }
+        Err(e) => return Err(format!("读取凭据 {} 失败: {}", name, e)),
+    };
+
+    if let Some(value) = &value {
+        cache().insert(name.to_string(), value.clone());
+    }
+    Ok(value)
+}
+
+/// 删除凭据，不存在时忽略
+pub fn delete_secret(name: &str) -> Result<(), String> {
+    cache().remove(name);
+
+    match keyring_entry(name).and_then(|entry| entry.delete_credential()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) if keyring_unavailable(&e) => {}
+        Err(e) => return Err(format!("删除凭据 {} 失败: {}", name, e)),
+    }
+    update_fallback(|secrets| secrets.remove(name).is_some())?;
+
+    log::info!("凭据已删除: {}", name);
+    Ok(())
+}
+
+fn keyring_entry(name: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, name)
+}
+
+/// 是否为钥匙串服务本身不可用（未安装、未解锁等）
+fn keyring_unavailable(error: &keyring::Error) -> bool {
+    matches!(error, keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_))
+}
+
+// ==================== 加密文件 ====================
+//
+// 密钥文件与加密文件都保存在本机数据目录且仅当前用户可读，
+// 可以防止凭据随配置文件被复制、备份或同步后泄露，但无法防御能读取当前用户文件的程序
+
+/// 加密文件内容
+#[derive(Debug, Serialize, Deserialize)]
+struct FallbackFile {
+    nonce: String,
+    ciphertext: String,
+}
+
+fn fallback_dir() -> Result<PathBuf, String> {
+    dirs::data_local_dir()
+        .map(|dir| dir.join("WorkHelper"))
+        .ok_or_else(|| "无法获取本地数据目录".to_string())
+}
+
+/// 读取加密文件的密钥，不存在时生成
+fn fallback_cipher() -> Result<XChaCha20Poly1305, String> {
+    let path = fallback_dir()?.join(FALLBACK_KEY_FILE);
+
+    if let Ok(content) = fs::read_to_string(&path) {
+        let key = general_purpose::STANDARD
+            .decode(content.trim())
+            .map_err(|e| format!("凭据密钥文件已损坏: {}", e))?;
+        return XChaCha20Poly1305::new_from_slice(&key).map_err(|_| "凭据密钥文件已损坏".to_string());
+    }
+
+    let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+    write_private(&path, general_purpose::STANDARD.encode(key).as_bytes())?;
+    Ok(XChaCha20Poly1305::new(&key))
+}
+
+fn load_fallback() -> Result<BTreeMap<String, String>, String> {
+    let path = fallback_dir()?.join(FALLBACK_FILE);
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Ok(BTreeMap::new());
+    };
+
+    let file: FallbackFile = serde_json::from_str(&content).map_err(|e| format!("凭据文件已损坏: {}", e))?;
+    let nonce = general_purpose::STANDARD
+        .decode(&file.nonce)
+        .map_err(|e| format!("凭据文件已损坏: {}", e))?;
+    if nonce.len() != 24 {
+        return Err("凭据文件已损坏".to_string());
+    }
+    let ciphertext = general_purpose::STANDARD
+        .decode(&file.ciphertext)
+        .map_err(|e| format!("凭据文件已损坏: {}", e))?;
+
+    let plaintext = fallback_cipher()?
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| "凭据文件解密失败，密钥文件可能已被替换".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("凭据文件已损坏: {}", e))
+}
+
+/// 修改加密文件中的凭据，`change` 返回 false 表示没有修改
+fn update_fallback(change: impl FnOnce(&mut BTreeMap<String, String>) -> bool) -> Result<(), String> {
+    let mut secrets = load_fallback()?;
+    if !change(&mut secrets) {
+        return Ok(());
+    }
+
+    let path = fallback_dir()?.join(FALLBACK_FILE);
+    if secrets.is_empty() {
+        let _ = fs::remove_file(&path);
+        return Ok(());
+    }
+
+    let plaintext = serde_json::to_vec(&secrets).map_err(|e| format!("序列化凭据失败: {}", e))?;
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = fallback_cipher()?
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| "加密凭据失败".to_string())?;
+
+    let file = FallbackFile {
+        nonce: general_purpose::STANDARD.encode(nonce),
+        ciphertext: general_purpose::STANDARD.encode(ciphertext),
+    };
+    let content = serde_json::to_string_pretty(&file).map_err(|e| format!("序列化凭据失败: {}", e))?;
+    write_private(&path, content.as_bytes())
+}
+
+/// 写入仅当前用户可读写的文件
+fn write_private(path: &Path, content: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    use std::io::Write;
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(content))
+        .map_err(|e| format!("写入文件 {} 失败: {}", path.display(), e))
+}
@@ -0,0 +1,393 @@
+/*!
+ * 基础设置模块
+ * 定义大模型接口与令牌、Git 用户名、OA 地址与账号、日报周报模板等基础设置，
+ * 负责字段校验、部分更新，以及从旧版前端 localStorage 导入设置
+ */
+
+use crate::config::{load_config, save_config};
+use crate::secrets;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// 默认日报模板
+const DEFAULT_DAILY_TEMPLATE: &str = r#"你是一位专业的团队领导，你的任务是分析 Git commit 日志并生成一份清晰的工作总结。
+请遵循以下规则:
+1. 仔细阅读下面提供的 Commit 日志。
+2. 根据日志内容，将工作内容按功能模块进行归类。
+3. 以数字列表的形式输出总结，每个列表项代表一个模块的工作。
+4. 每个列表项的格式必须为："[序号]、[模块名]：[具体的修改内容]"。例如："1、用户管理模块：修复了无法删除用户的bug。"
+5. 列表项之间不要有任何空行，但是每个列表项必须单独占一行。
+6. 总结内容必须简洁、清晰，直接描述完成的工作，限制100字以内。
+7. 倒叙输出日志,但是序号是正序。
+8. 绝对不要在你的回复中使用任何 Markdown 格式（例如 ###、** 或 *）。
+
+Commit 日志:
+{commit_logs}
+
+请开始生成工作总结:"#;
+
+/// 默认周报模板
+const DEFAULT_WEEKLY_TEMPLATE: &str = r#"你是一个顶级的项目经理和技术领导。请根据下面按天和项目分组的 Git 提交记录，生成一份高度概括、重点突出、语言流畅的中文周报。
+
+你的任务是：
+1.  仔细分析每一天的 commit 记录。
+2.  将零散的 commit 信息提炼成对当天工作核心内容的总结，而不是简单罗列。
+3.  如果某天没有提交记录，请明确指出"暂无提交记录"。
+4.  最终的输出必须严格遵循下面的格式，以星期为单位进行组织。
+5.  每个项目的工作总结必须以项目名开头，以冒号分隔，每天的完成工作不可换行和换列，也不可用-号开头。
+6.  总结内容必须简洁、清晰，直接描述完成的工作，字数不要超过200字。
+7.  绝对不要在你的回复中使用任何 Markdown 格式（例如 ###、** 或 *）。
+
+[高质量输出范例]
+星期一：暂无提交记录
+星期二：[项目名]：大量功能优化包括首页分类导航、订单管理、搜索功能、店铺详情等多个模块的完善和bug修复
+星期三：[项目名]：主要进行订单物流显示、搜索结果页面、购物车样式、收藏功能等的优化和新增推荐商品页面
+星期四：[项目名]：重点开发积分系统功能、批量订单处理、商品详情页轮播图优化、评价系统改进等大量功能更新
+星期五：[项目名]：主要更新接口地址配置、VIP页面会员功能和积分订单流程优化
+星期六：[项目名]：修复米林APPbug
+
+[待总结的 Commit 记录]
+{commit_logs}"#;
+
+/// 默认 OA 接口地址，公司可通过系统配置文件统一修改
+const DEFAULT_OA_URL: &str = "https://ai.mufengweilai.com/api/oa";
+
+/// 默认大模型接口（兼容 OpenAI Chat Completions 接口）与模型
+const DEFAULT_LLM_BASE_URL: &str = "https://api.deepseek.com";
+const DEFAULT_LLM_MODEL: &str = "deepseek-chat";
+
+/// 模板最大长度（字符数）
+const MAX_TEMPLATE_CHARS: usize = 20_000;
+
+/// 设置修改后广播给所有窗口的事件名
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+/// 旧版 localStorage 中保存在钥匙串里的凭据名称
+const LEGACY_SECRETS: [(&str, &str); 2] = [("deepseekToken", "deepseek.token"), ("oaPassword", "oa.password")];
+
+/// 基础设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GeneralSettings {
+    /// 大模型 API Token（保存在钥匙串中），字段名沿用只支持 DeepSeek 时的名称
+    pub deepseek_token: String,
+    /// 大模型接口地址，需兼容 OpenAI Chat Completions 接口
+    pub llm_base_url: String,
+    /// 大模型名称
+    pub llm_model: String,
+    /// Git 用户名，用于筛选提交记录
+    pub git_user: String,
+    /// OA 接口地址
+    pub oa_url: String,
+    /// OA 系统账号
+    pub oa_account: String,
+    /// OA 系统密码（保存在钥匙串中）
+    pub oa_password: String,
+    /// 日报生成模板
+    pub daily_template: String,
+    /// 周报生成模板
+    pub weekly_template: String,
+    /// 订阅的团队共享配置包地址（http(s) 地址或本机文件路径），为空表示不订阅
+    pub bundle_subscription: String,
+}
+
+impl Default for GeneralSettings {
+    fn default() -> Self {
+        Self {
+            deepseek_token: String::new(),
+            llm_base_url: DEFAULT_LLM_BASE_URL.to_string(),
+            llm_model: DEFAULT_LLM_MODEL.to_string(),
+            git_user: String::new(),
+            oa_url: DEFAULT_OA_URL.to_string(),
+            oa_account: String::new(),
+            oa_password: String::new(),
+            daily_template: DEFAULT_DAILY_TEMPLATE.to_string(),
+            weekly_template: DEFAULT_WEEKLY_TEMPLATE.to_string(),
+            bundle_subscription: String::new(),
+        }
+    }
+}
+
+/// 字段校验错误
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    /// 字段名（与前端一致的 camelCase）
+    pub field: String,
+    pub message: String,
+}
+
+/// 更新设置失败的原因
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SettingsError {
+    /// 字段校验未通过
+    Invalid { fields: Vec<FieldError> },
+    /// 读写配置失败
+    Failed { message: String },
+}
+
+impl std::fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::Invalid { fields } => {
+                let messages: Vec<&str> = fields.iter().map(|field| field.message.as_str()).collect();
+                write!(f, "设置校验失败: {}", messages.join("；"))
+            }
+            SettingsError::Failed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<String> for SettingsError {
+    fn from(message: String) -> Self {
+        SettingsError::Failed { message }
+    }
+}
+
+impl GeneralSettings {
+    /// 设备间同步的字段，不含令牌和密码
+    pub fn shared(&self) -> Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("deepseekToken");
+            fields.remove("oaPassword");
+        }
+        value
+    }
+
+    /// 将部分字段合并到当前设置，返回新的设置
+    pub fn patched(&self, patch: &Map<String, Value>) -> Result<Self, Vec<FieldError>> {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        let mut errors = Vec::new();
+
+        if let Some(fields) = value.as_object_mut() {
+            for (field, new_value) in patch {
+                if !fields.contains_key(field) {
+                    errors.push(field_error(field, "未知的设置项"));
+                } else if !new_value.is_string() {
+                    errors.push(field_error(field, "设置值必须是字符串"));
+                } else {
+                    fields.insert(field.clone(), new_value.clone());
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        serde_json::from_value(value).map_err(|e| vec![field_error("", &format!("设置无效: {}", e))])
+    }
+
+    /// 校验所有字段
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let errors: Vec<FieldError> = [
+            ("deepseekToken", validate_token(&self.deepseek_token, &self.llm_base_url)),
+            ("llmBaseUrl", validate_http_url(&self.llm_base_url, "大模型接口地址")),
+            ("llmModel", validate_llm_model(&self.llm_model)),
+            ("gitUser", validate_git_user(&self.git_user)),
+            ("oaUrl", validate_http_url(&self.oa_url, "OA 地址")),
+            ("oaAccount", validate_oa_account(&self.oa_account)),
+            ("oaPassword", validate_oa_password(&self.oa_password)),
+            ("dailyTemplate", validate_template(&self.daily_template)),
+            ("weeklyTemplate", validate_template(&self.weekly_template)),
+            ("bundleSubscription", validate_subscription(&self.bundle_subscription)),
+        ]
+        .into_iter()
+        .filter_map(|(field, result)| result.err().map(|message| field_error(field, &message)))
+        .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn field_error(field: &str, message: &str) -> FieldError {
+    FieldError {
+        field: field.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn validate_token(token: &str, llm_base_url: &str) -> Result<(), String> {
+    // 钥匙串暂时无法读取时保留凭据引用，不作为令牌校验
+    if token.is_empty() || secrets::parse_reference(token).is_some() {
+        return Ok(());
+    }
+    if token.chars().any(char::is_whitespace) {
+        return Err("API Token 不能包含空格或换行".to_string());
+    }
+    // 其他服务商的令牌格式各不相同，只检查 DeepSeek 的令牌
+    if llm_base_url == DEFAULT_LLM_BASE_URL && !token.starts_with("sk-") {
+        return Err("DeepSeek Token 应以 sk- 开头".to_string());
+    }
+    Ok(())
+}
+
+fn validate_llm_model(model: &str) -> Result<(), String> {
+    if model.is_empty() {
+        return Err("模型名称不能为空".to_string());
+    }
+    if model.chars().any(char::is_whitespace) {
+        return Err("模型名称不能包含空格".to_string());
+    }
+    if model.chars().count() > 100 {
+        return Err("模型名称不能超过100个字符".to_string());
+    }
+    Ok(())
+}
+
+fn validate_git_user(git_user: &str) -> Result<(), String> {
+    // Git 用户名会拼接到 git log --author="..." 命令中
+    if git_user.chars().any(|c| matches!(c, '"' | '`' | '$' | '\\') || c.is_control()) {
+        return Err("Git 用户名不能包含引号、反引号、$、\\ 或换行".to_string());
+    }
+    if git_user.chars().count() > 100 {
+        return Err("Git 用户名不能超过100个字符".to_string());
+    }
+    Ok(())
+}
+
+fn validate_http_url(url: &str, label: &str) -> Result<(), String> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.has_host() => Ok(()),
+        _ => Err(format!("{}必须是有效的 http 或 https 地址", label)),
+    }
+}
+
+fn validate_oa_account(account: &str) -> Result<(), String> {
+    if account.chars().any(char::is_whitespace) {
+        return Err("OA 账号不能包含空格".to_string());
+    }
+    if account.chars().count() > 64 {
+        return Err("OA 账号不能超过64个字符".to_string());
+    }
+    Ok(())
+}
+
+fn validate_oa_password(password: &str) -> Result<(), String> {
+    if password.chars().count() > 128 {
+        return Err("OA 密码不能超过128个字符".to_string());
+    }
+    Ok(())
+}
+
+fn validate_subscription(source: &str) -> Result<(), String> {
+    if source.is_empty() || std::path::Path::new(source).is_absolute() {
+        return Ok(());
+    }
+    validate_http_url(source, "共享配置地址").map_err(|_| "共享配置地址必须是 http(s) 地址或文件的完整路径".to_string())
+}
+
+fn validate_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("模板不能为空".to_string());
+    }
+    if template.chars().count() > MAX_TEMPLATE_CHARS {
+        return Err(format!("模板不能超过{}个字符", MAX_TEMPLATE_CHARS));
+    }
+    Ok(())
+}
+
+// ==================== 读取与更新 ====================
+
+/// 获取基础设置
+pub fn get_settings() -> GeneralSettings {
+    load_config().general
+}
+
+/// 更新部分字段，校验通过后保存
+///
+/// 返回更新后的设置，以及设置是否有变化
+pub fn update_settings(patch: &Map<String, Value>) -> Result<(GeneralSettings, bool), SettingsError> {
+    let mut config = load_config();
+    let settings = config
+        .general
+        .patched(patch)
+        .map_err(|fields| SettingsError::Invalid { fields })?;
+    settings.validate().map_err(|fields| SettingsError::Invalid { fields })?;
+
+    if settings == config.general {
+        return Ok((settings, false));
+    }
+
+    config.general = settings.clone();
+    save_config(&config)?;
+    log::info!("基础设置已更新: {:?}", patch.keys().collect::<Vec<_>>());
+    Ok((settings, true))
+}
+
+/// 导入旧版前端保存在 localStorage（githelper-settings）中的设置
+///
+/// 只填充尚未设置的字段，无效的值会被跳过。旧版已迁移到钥匙串的令牌和密码也一并导入
+pub fn import_local_settings(local: &Map<String, Value>) -> Result<(GeneralSettings, bool), SettingsError> {
+    let mut config = load_config();
+    let defaults = GeneralSettings::default();
+    let current = serde_json::to_value(&config.general).unwrap_or_default();
+    let default_values = serde_json::to_value(&defaults).unwrap_or_default();
+
+    // 旧版字段名与新版的对应关系，DeepSeek Token 旧字段名为 token
+    let mut candidates: Map<String, Value> = local
+        .iter()
+        .map(|(key, value)| {
+            let field = if key == "token" { "deepseekToken" } else { key.as_str() };
+            (field.to_string(), value.clone())
+        })
+        .collect();
+    for (field, secret_name) in LEGACY_SECRETS {
+        if candidates.get(field).and_then(Value::as_str).map_or(true, str::is_empty) {
+            if let Ok(Some(secret)) = secrets::get_secret(secret_name) {
+                candidates.insert(field.to_string(), Value::String(secret));
+            }
+        }
+    }
+
+    let mut patch = Map::new();
+    for (field, value) in candidates {
+        let Some(text) = value.as_str().filter(|text| !text.is_empty()) else {
+            continue;
+        };
+        // 已经设置过的字段以当前设置为准
+        if current.get(&field) != default_values.get(&field) {
+            continue;
+        }
+
+        let mut single = Map::new();
+        single.insert(field.clone(), Value::String(text.to_string()));
+        match config.general.patched(&single).map(|settings| settings.validate()) {
+            Ok(Ok(())) => {
+                patch.insert(field, Value::String(text.to_string()));
+            }
+            Ok(Err(errors)) | Err(errors) => {
+                let messages: Vec<String> = errors.into_iter().map(|error| error.message).collect();
+                log::warn!("跳过无效的旧版设置 {}: {}", field, messages.join("；"));
+            }
+        }
+    }
+
+    let changed = !patch.is_empty();
+    if changed {
+        config.general = config
+            .general
+            .patched(&patch)
+            .map_err(|fields| SettingsError::Invalid { fields })?;
+        save_config(&config)?;
+        log::info!("已导入旧版本地设置: {:?}", patch.keys().collect::<Vec<_>>());
+    }
+
+    // 令牌和密码已保存到新的凭据名称下，删除旧版的凭据
+    let imported = serde_json::to_value(&config.general).unwrap_or_default();
+    for (field, secret_name) in LEGACY_SECRETS {
+        if imported.get(field).and_then(Value::as_str).map_or(true, str::is_empty) {
+            continue;
+        }
+        if let Err(e) = secrets::delete_secret(secret_name) {
+            log::warn!("{}", e);
+        }
+    }
+
+    Ok((config.general, changed))
+}
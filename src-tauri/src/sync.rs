@@ -0,0 +1,692 @@
+/*!
+ * 设置同步模块
+ * 将项目列表、模板、账号设置等前端数据连同更新设置打包，使用用户口令加密
+ * （Argon2id 派生密钥 + XChaCha20-Poly1305 认证加密）后上传到用户配置的WebDAV目录，
+ * 通过 ETag 条件请求检测多台设备间的冲突，支持以本机为准和合并两种处理方式
+ */
+
+use crate::config::{load_config, save_config, SyncConfig, UpdateConfig};
+use crate::http_client;
+use crate::webdav::{basic_authorization, Condition, WebDavClient, WebDavError};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// 服务器上的同步文件名
+const SYNC_FILE: &str = "workhelper-sync.json";
+
+/// 本地同步状态文件名
+const STATE_FILE: &str = "sync_state.json";
+
+const ENVELOPE_FORMAT: &str = "workhelper-sync";
+const ENVELOPE_VERSION: u32 = 1;
+
+/// 认证加密的附加数据，防止密文被挪作其他用途
+const AAD: &[u8] = b"workhelper-sync-v1";
+
+/// Argon2id 参数（19 MiB 内存、2 轮迭代），派生一次约需数十毫秒
+const KDF_MEMORY_KIB: u32 = 19 * 1024;
+const KDF_ITERATIONS: u32 = 2;
+const KDF_PARALLELISM: u32 = 1;
+
+/// 解密时接受的密钥派生参数上限，同步文件来自服务器，避免被篡改的参数耗尽内存或CPU
+const MAX_KDF_MEMORY_KIB: u32 = 256 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 16;
+const MAX_KDF_PARALLELISM: u32 = 8;
+
+/// 同步的数据包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncBundle {
+    /// 上传数据包的设备
+    pub device: String,
+    /// 上传时间（Unix时间戳，秒）
+    pub updated_at: u64,
+    /// 前端 localStorage 中需要同步的键值
+    pub local_storage: BTreeMap<String, String>,
+    /// 更新设置（不含跳过版本等本机状态）
+    pub update_settings: Option<Value>,
+    /// 基础设置（不含令牌和密码）
+    #[serde(default)]
+    pub general_settings: Option<Value>,
+}
+
+impl SyncBundle {
+    /// 数据内容的哈希，不含设备和时间，用于判断两边数据是否有变化
+    fn content_hash(&self) -> String {
+        let content = serde_json::json!({
+            "localStorage": self.local_storage,
+            "updateSettings": self.update_settings,
+            "generalSettings": self.general_settings,
+        });
+        crate::delta::sha256_hex(content.to_string().as_bytes())
+    }
+}
+
+/// 加密后的同步文件
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    format: String,
+    version: u32,
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// 本机的同步状态
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncState {
+    /// 上次同步完成时双方一致的数据哈希
+    synced_hash: Option<String>,
+    /// 上次同步完成时的时间
+    synced_at: Option<u64>,
+}
+
+/// 两边都有修改时的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncStrategy {
+    /// 最后写入者优先：以本机数据覆盖云端
+    LastWriter,
+    /// 合并两边数据，同一条目以本机为准
+    Merge,
+}
+
+/// 同步结果状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncStatus {
+    /// 两边数据一致
+    UpToDate,
+    /// 已将本机数据上传
+    Pushed,
+    /// 已下载云端数据，需要写入本机
+    Pulled,
+    /// 已合并两边数据并上传，需要写入本机
+    Merged,
+    /// 两边都有修改，需要选择处理方式
+    Conflict,
+}
+
+/// 同步结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResult {
+    pub status: SyncStatus,
+    /// 需要写入前端 localStorage 的数据（Pulled、Merged 时提供）
+    pub local_storage: Option<BTreeMap<String, String>>,
+    /// 云端数据来自的设备
+    pub remote_device: Option<String>,
+    /// 云端数据的上传时间
+    pub remote_updated_at: Option<u64>,
+}
+
+impl SyncResult {
+    fn new(status: SyncStatus, remote: Option<&SyncBundle>) -> Self {
+        Self {
+            status,
+            local_storage: None,
+            remote_device: remote.map(|bundle| bundle.device.clone()),
+            remote_updated_at: remote.map(|bundle| bundle.updated_at),
+        }
+    }
+}
+
+/// 同步本机和云端数据
+///
+/// 根据上次同步时的数据哈希判断哪一边有修改：只有本机修改时上传，只有云端修改时下载；
+/// 两边都有修改时按 strategy 处理，未指定时返回 Conflict。
+pub async fn sync(
+    passphrase: &str,
+    local_storage: BTreeMap<String, String>,
+    strategy: Option<SyncStrategy>,
+) -> Result<SyncResult, String> {
+    let config = load_config().sync;
+    let client = client(&config)?;
+    let local = local_bundle(&config, local_storage);
+    let local_hash = local.content_hash();
+    let mut state = load_state();
+
+    let (remote, etag) = match fetch(&client, passphrase).await? {
+        Some(remote) => remote,
+        None => {
+            log::info!("云端没有同步数据，上传本机数据");
+            push(&client, passphrase, &local, Condition::IfNoneMatch).await?;
+            return finish(&mut state, &local_hash, SyncResult::new(SyncStatus::Pushed, None));
+        }
+    };
+
+    let remote_hash = remote.content_hash();
+    let condition = etag.map(Condition::IfMatch).unwrap_or(Condition::None);
+
+    match resolve(state.synced_hash.as_deref(), &local_hash, &remote_hash, strategy) {
+        SyncStatus::UpToDate => finish(&mut state, &local_hash, SyncResult::new(SyncStatus::UpToDate, Some(&remote))),
+        SyncStatus::Pushed => {
+            push(&client, passphrase, &local, condition).await?;
+            finish(&mut state, &local_hash, SyncResult::new(SyncStatus::Pushed, Some(&remote)))
+        }
+        SyncStatus::Pulled => {
+            apply_settings(&remote)?;
+            let mut result = SyncResult::new(SyncStatus::Pulled, Some(&remote));
+            result.local_storage = Some(remote.local_storage);
+            finish(&mut state, &remote_hash, result)
+        }
+        SyncStatus::Conflict => {
+            log::info!("本机和云端数据都有修改，等待用户选择处理方式");
+            Ok(SyncResult::new(SyncStatus::Conflict, Some(&remote)))
+        }
+        SyncStatus::Merged => {
+            let merged = merge(&local, &remote);
+            push(&client, passphrase, &merged, condition).await?;
+            apply_settings(&merged)?;
+
+            let mut result = SyncResult::new(SyncStatus::Merged, Some(&remote));
+            let merged_hash = merged.content_hash();
+            result.local_storage = Some(merged.local_storage);
+            finish(&mut state, &merged_hash, result)
+        }
+    }
+}
+
+/// 根据上次同步时的数据哈希判断哪一边有修改，决定同步方式
+///
+/// 只有本机修改时上传，只有云端修改时下载；两边都有修改时按 strategy 处理，未指定时为冲突
+fn resolve(synced_hash: Option<&str>, local_hash: &str, remote_hash: &str, strategy: Option<SyncStrategy>) -> SyncStatus {
+    if local_hash == remote_hash {
+        return SyncStatus::UpToDate;
+    }
+
+    let remote_changed = synced_hash != Some(remote_hash);
+    let local_changed = synced_hash != Some(local_hash);
+    match (remote_changed, local_changed, strategy) {
+        // 只有本机修改
+        (false, _, _) => SyncStatus::Pushed,
+        // 只有云端修改
+        (true, false, _) => SyncStatus::Pulled,
+        (true, true, None) => SyncStatus::Conflict,
+        (true, true, Some(SyncStrategy::LastWriter)) => SyncStatus::Pushed,
+        (true, true, Some(SyncStrategy::Merge)) => SyncStatus::Merged,
+    }
+}
+
+/// 从云端恢复数据，覆盖本机（用于新设备）
+pub async fn restore(passphrase: &str) -> Result<SyncResult, String> {
+    let config = load_config().sync;
+    let client = client(&config)?;
+
+    let (remote, _) = fetch(&client, passphrase).await?.ok_or("云端没有同步数据")?;
+    apply_settings(&remote)?;
+
+    let mut state = load_state();
+    let remote_hash = remote.content_hash();
+    let mut result = SyncResult::new(SyncStatus::Pulled, Some(&remote));
+    result.local_storage = Some(remote.local_storage);
+    finish(&mut state, &remote_hash, result)
+}
+
+fn client(config: &SyncConfig) -> Result<WebDavClient, String> {
+    if config.url.trim().is_empty() {
+        return Err("未配置同步地址".to_string());
+    }
+
+    Ok(WebDavClient::new(
+        http_client::shared().clone(),
+        &config.url,
+        Some(basic_authorization(&config.username, &config.password)),
+    ))
+}
+
+fn local_bundle(config: &SyncConfig, local_storage: BTreeMap<String, String>) -> SyncBundle {
+    let device = if config.device_name.is_empty() {
+        std::env::var("COMPUTERNAME")
+            .or_else(|_| std::env::var("HOSTNAME"))
+            .unwrap_or_else(|_| "未命名设备".to_string())
+    } else {
+        config.device_name.clone()
+    };
+
+    let config = load_config();
+    SyncBundle {
+        device,
+        updated_at: now(),
+        local_storage,
+        update_settings: shared_update_settings(&config.update),
+        general_settings: Some(config.general.shared()),
+    }
+}
+
+/// 需要在设备间同步的更新设置，去掉跳过版本、稍后提醒和更新源等本机相关的字段
+fn shared_update_settings(update: &UpdateConfig) -> Option<Value> {
+    let mut value = serde_json::to_value(update).ok()?;
+    if let Some(settings) = value.as_object_mut() {
+        for field in ["source", "skipped_version", "remind_after"] {
+            settings.remove(field);
+        }
+    }
+    Some(value)
+}
+
+/// 将云端的更新设置和基础设置写入本机配置
+fn apply_settings(bundle: &SyncBundle) -> Result<(), String> {
+    let mut config = load_config();
+
+    if let Some(Value::Object(settings)) = &bundle.update_settings {
+        let mut update = serde_json::to_value(&config.update).map_err(|e| format!("序列化配置失败: {}", e))?;
+        if let Some(update) = update.as_object_mut() {
+            update.extend(settings.clone());
+        }
+        config.update = serde_json::from_value(update).map_err(|e| format!("同步的更新设置无效: {}", e))?;
+    }
+
+    if let Some(Value::Object(settings)) = &bundle.general_settings {
+        let general = config
+            .general
+            .patched(settings)
+            .map_err(|_| "同步的基础设置无效".to_string())?;
+        general.validate().map_err(|_| "同步的基础设置无效".to_string())?;
+        config.general = general;
+    }
+
+    save_config(&config)
+}
+
+/// 合并两边数据：各键取本机的值，两边都是带 id 的对象数组时按 id 合并，都是对象时合并字段
+fn merge(local: &SyncBundle, remote: &SyncBundle) -> SyncBundle {
+    let mut local_storage = remote.local_storage.clone();
+    for (key, local_value) in &local.local_storage {
+        let merged = match remote.local_storage.get(key) {
+            Some(remote_value) if remote_value != local_value => merge_value(local_value, remote_value),
+            _ => local_value.clone(),
+        };
+        local_storage.insert(key.clone(), merged);
+    }
+
+    SyncBundle {
+        device: local.device.clone(),
+        updated_at: now(),
+        local_storage,
+        update_settings: local.update_settings.clone(),
+        general_settings: local.general_settings.clone(),
+    }
+}
+
+fn merge_value(local: &str, remote: &str) -> String {
+    let (Ok(local_json), Ok(remote_json)) = (
+        serde_json::from_str::<Value>(local),
+        serde_json::from_str::<Value>(remote),
+    ) else {
+        return local.to_string();
+    };
+
+    let merged = match (local_json, remote_json) {
+        (Value::Array(local_items), Value::Array(remote_items))
+            if local_items.iter().chain(&remote_items).all(|item| item.get("id").is_some()) =>
+        {
+            let mut items = local_items;
+            for item in remote_items {
+                if !items.iter().any(|existing| existing.get("id") == item.get("id")) {
+                    items.push(item);
+                }
+            }
+            Value::Array(items)
+        }
+        (Value::Object(mut local_fields), Value::Object(remote_fields)) => {
+            for (field, value) in remote_fields {
+                local_fields.entry(field).or_insert(value);
+            }
+            Value::Object(local_fields)
+        }
+        _ => return local.to_string(),
+    };
+    merged.to_string()
+}
+
+/// 下载并解密云端数据，不存在时返回 None
+async fn fetch(client: &WebDavClient, passphrase: &str) -> Result<Option<(SyncBundle, Option<String>)>, String> {
+    let fetched = match client.get(SYNC_FILE).await {
+        Ok(fetched) => fetched,
+        Err(WebDavError::NotFound) => return Ok(None),
+        Err(e) => return Err(format!("下载同步数据失败: {}", e)),
+    };
+
+    let envelope: Envelope = serde_json::from_slice(&fetched.data)
+        .map_err(|e| format!("同步数据格式错误: {}", e))?;
+    let passphrase = passphrase.to_string();
+    let bundle = tokio::task::spawn_blocking(move || decrypt(&envelope, &passphrase))
+        .await
+        .map_err(|e| format!("解密同步数据失败: {}", e))??;
+
+    Ok(Some((bundle, fetched.etag)))
+}
+
+/// 加密并上传数据，云端在此期间被其他设备修改时返回冲突错误
+async fn push(client: &WebDavClient, passphrase: &str, bundle: &SyncBundle, condition: Condition) -> Result<(), String> {
+    let passphrase = passphrase.to_string();
+    let bundle = bundle.clone();
+    let envelope = tokio::task::spawn_blocking(move || encrypt(&bundle, &passphrase))
+        .await
+        .map_err(|e| format!("加密同步数据失败: {}", e))??;
+    let data = serde_json::to_vec_pretty(&envelope).map_err(|e| format!("序列化同步数据失败: {}", e))?;
+
+    client.mkcol("").await?;
+    match client.put(SYNC_FILE, data, condition).await {
+        Ok(_) => Ok(()),
+        Err(WebDavError::PreconditionFailed) => Err("云端数据刚被其他设备修改，请重新同步".to_string()),
+        Err(e) => Err(format!("上传同步数据失败: {}", e)),
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], memory_kib: u32, iterations: u32, parallelism: u32) -> Result<[u8; 32], String> {
+    let params = Params::new(memory_kib, iterations, parallelism, Some(32))
+        .map_err(|e| format!("密钥派生参数无效: {}", e))?;
+
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("密钥派生失败: {}", e))?;
+    Ok(key)
+}
+
+fn encrypt(bundle: &SyncBundle, passphrase: &str) -> Result<Envelope, String> {
+    if passphrase.is_empty() {
+        return Err("同步口令不能为空".to_string());
+    }
+
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt, KDF_MEMORY_KIB, KDF_ITERATIONS, KDF_PARALLELISM)?;
+
+    let plaintext = serde_json::to_vec(bundle).map_err(|e| format!("序列化同步数据失败: {}", e))?;
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new((&key).into())
+        .encrypt(&nonce, Payload { msg: &plaintext, aad: AAD })
+        .map_err(|_| "加密同步数据失败".to_string())?;
+
+    Ok(Envelope {
+        format: ENVELOPE_FORMAT.to_string(),
+        version: ENVELOPE_VERSION,
+        salt: general_purpose::STANDARD.encode(salt),
+        memory_kib: KDF_MEMORY_KIB,
+        iterations: KDF_ITERATIONS,
+        parallelism: KDF_PARALLELISM,
+        nonce: general_purpose::STANDARD.encode(nonce),
+        ciphertext: general_purpose::STANDARD.encode(ciphertext),
+    })
+}
+
+fn decrypt(envelope: &Envelope, passphrase: &str) -> Result<SyncBundle, String> {
+    if envelope.format != ENVELOPE_FORMAT || envelope.version > ENVELOPE_VERSION {
+        return Err("不支持的同步数据格式，请升级应用".to_string());
+    }
+
+    let decode = |value: &str| {
+        general_purpose::STANDARD
+            .decode(value)
+            .map_err(|e| format!("同步数据格式错误: {}", e))
+    };
+    let salt = decode(&envelope.salt)?;
+    let nonce = decode(&envelope.nonce)?;
+    let ciphertext = decode(&envelope.ciphertext)?;
+    if nonce.len() != 24 {
+        return Err("同步数据格式错误: 随机数长度无效".to_string());
+    }
+    if envelope.memory_kib > MAX_KDF_MEMORY_KIB
+        || envelope.iterations > MAX_KDF_ITERATIONS
+        || envelope.parallelism > MAX_KDF_PARALLELISM
+    {
+        return Err("同步数据格式错误: 密钥派生参数超出允许范围".to_string());
+    }
+
+    let key = derive_key(passphrase, &salt, envelope.memory_kib, envelope.iterations, envelope.parallelism)?;
+    let plaintext = XChaCha20Poly1305::new((&key).into())
+        .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: AAD })
+        .map_err(|_| "同步口令错误或数据已被篡改".to_string())?;
+
+    serde_json::from_slice(&plaintext).map_err(|e| format!("解析同步数据失败: {}", e))
+}
+
+fn finish(state: &mut SyncState, synced_hash: &str, result: SyncResult) -> Result<SyncResult, String> {
+    state.synced_hash = Some(synced_hash.to_string());
+    state.synced_at = Some(now());
+    save_state(state);
+    log::info!("设置同步完成: {:?}", result.status);
+    Ok(result)
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 同步状态保存在当前档案的数据目录中，各档案分别记录
+fn state_path() -> Option<PathBuf> {
+    Some(crate::profiles::profile_dir().ok()?.join(STATE_FILE))
+}
+
+fn load_state() -> SyncState {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &SyncState) {
+    let Some(path) = state_path() else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+
+    match serde_json::to_string_pretty(state) {
+        Ok(content) => {
+            if let Err(e) = fs::write(&path, content) {
+                log::warn!("保存同步状态失败: {}", e);
+            }
+        }
+        Err(e) => log::warn!("序列化同步状态失败: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(device: &str, entries: &[(&str, &str)]) -> SyncBundle {
+        SyncBundle {
+            device: device.to_string(),
+            updated_at: 1,
+            local_storage: entries.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+            update_settings: Some(serde_json::json!({"channel": "beta"})),
+            general_settings: None,
+        }
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trip() {
+        let original = bundle("办公室电脑", &[("projects", r#"[{"id":1,"name":"OA"}]"#)]);
+        let envelope = encrypt(&original, "正确的口令").unwrap();
+
+        assert_eq!(envelope.format, ENVELOPE_FORMAT);
+        let ciphertext = general_purpose::STANDARD.decode(&envelope.ciphertext).unwrap();
+        assert!(
+            !ciphertext.windows(b"projects".len()).any(|window| window == b"projects"),
+            "密文中不应出现明文"
+        );
+        let decrypted = decrypt(&envelope, "正确的口令").unwrap();
+        assert_eq!(decrypted.device, "办公室电脑");
+        assert_eq!(decrypted.content_hash(), original.content_hash());
+
+        // 每次加密使用新的盐和随机数
+        let again = encrypt(&original, "正确的口令").unwrap();
+        assert_ne!(again.salt, envelope.salt);
+        assert_ne!(again.nonce, envelope.nonce);
+    }
+
+    #[test]
+    fn decrypt_rejects_wrong_passphrase_and_tampering() {
+        let envelope = encrypt(&bundle("a", &[("k", "v")]), "passphrase").unwrap();
+
+        let error = decrypt(&envelope, "wrong").unwrap_err();
+        assert!(error.contains("口令错误或数据已被篡改"), "{}", error);
+
+        let mut ciphertext = general_purpose::STANDARD.decode(&envelope.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        let tampered = Envelope {
+            ciphertext: general_purpose::STANDARD.encode(&ciphertext),
+            ..clone_envelope(&envelope)
+        };
+        assert!(decrypt(&tampered, "passphrase").unwrap_err().contains("篡改"));
+
+        let mut salt = general_purpose::STANDARD.decode(&envelope.salt).unwrap();
+        salt[0] ^= 1;
+        let tampered = Envelope {
+            salt: general_purpose::STANDARD.encode(&salt),
+            ..clone_envelope(&envelope)
+        };
+        assert!(decrypt(&tampered, "passphrase").is_err(), "篡改盐值后派生的密钥不同");
+
+        let tampered = Envelope {
+            nonce: general_purpose::STANDARD.encode([0u8; 12]),
+            ..clone_envelope(&envelope)
+        };
+        assert!(decrypt(&tampered, "passphrase").unwrap_err().contains("随机数长度无效"));
+    }
+
+    #[test]
+    fn decrypt_rejects_unknown_format_and_kdf_limits() {
+        let envelope = Envelope {
+            format: ENVELOPE_FORMAT.to_string(),
+            version: ENVELOPE_VERSION,
+            salt: general_purpose::STANDARD.encode([0u8; 16]),
+            memory_kib: KDF_MEMORY_KIB,
+            iterations: KDF_ITERATIONS,
+            parallelism: KDF_PARALLELISM,
+            nonce: general_purpose::STANDARD.encode([0u8; 24]),
+            ciphertext: general_purpose::STANDARD.encode([0u8; 32]),
+        };
+
+        let over_limits = [
+            Envelope { memory_kib: MAX_KDF_MEMORY_KIB + 1, ..clone_envelope(&envelope) },
+            Envelope { iterations: MAX_KDF_ITERATIONS + 1, ..clone_envelope(&envelope) },
+            Envelope { parallelism: MAX_KDF_PARALLELISM + 1, ..clone_envelope(&envelope) },
+        ];
+        for envelope in &over_limits {
+            let error = decrypt(envelope, "passphrase").unwrap_err();
+            assert!(error.contains("密钥派生参数超出允许范围"), "{}", error);
+        }
+
+        let unknown = Envelope { format: "other".to_string(), ..clone_envelope(&envelope) };
+        assert!(decrypt(&unknown, "passphrase").unwrap_err().contains("不支持"));
+        let newer = Envelope { version: ENVELOPE_VERSION + 1, ..clone_envelope(&envelope) };
+        assert!(decrypt(&newer, "passphrase").unwrap_err().contains("请升级应用"));
+    }
+
+    #[test]
+    fn encrypt_requires_passphrase() {
+        assert!(encrypt(&bundle("a", &[]), "").is_err());
+    }
+
+    fn clone_envelope(envelope: &Envelope) -> Envelope {
+        serde_json::from_value(serde_json::to_value(envelope).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn resolve_by_changed_side() {
+        use SyncStatus::*;
+        let cases = [
+            // (上次同步, 本机, 云端, 处理方式, 结果)
+            (Some("a"), "a", "a", None, UpToDate),
+            (None, "a", "a", None, UpToDate),
+            (Some("a"), "b", "a", None, Pushed),
+            (Some("a"), "a", "b", None, Pulled),
+            (Some("a"), "b", "c", None, Conflict),
+            (None, "b", "c", None, Conflict),
+            (Some("a"), "b", "c", Some(SyncStrategy::LastWriter), Pushed),
+            (Some("a"), "b", "c", Some(SyncStrategy::Merge), Merged),
+            // 只有一边修改时不使用处理方式
+            (Some("a"), "a", "b", Some(SyncStrategy::LastWriter), Pulled),
+            (Some("a"), "b", "a", Some(SyncStrategy::Merge), Pushed),
+        ];
+
+        for (synced, local, remote, strategy, expected) in cases {
+            assert_eq!(
+                resolve(synced, local, remote, strategy),
+                expected,
+                "上次同步 {:?}，本机 {}，云端 {}，处理方式 {:?}",
+                synced,
+                local,
+                remote,
+                strategy
+            );
+        }
+    }
+
+    #[test]
+    fn merge_prefers_local_values_and_unions_remote() {
+        let local = bundle(
+            "本机",
+            &[
+                ("projects", r#"[{"id":1,"name":"本机"},{"id":2,"name":"新增"}]"#),
+                ("settings", r#"{"theme":"dark"}"#),
+                ("note", "本机"),
+            ],
+        );
+        let mut remote = bundle(
+            "云端",
+            &[
+                ("projects", r#"[{"id":1,"name":"云端"},{"id":3,"name":"云端新增"}]"#),
+                ("settings", r#"{"theme":"light","font":14}"#),
+                ("note", "云端"),
+                ("remoteOnly", "x"),
+            ],
+        );
+        remote.update_settings = Some(serde_json::json!({"channel": "nightly"}));
+
+        let merged = merge(&local, &remote);
+        assert_eq!(merged.device, "本机");
+        assert_eq!(merged.update_settings, local.update_settings, "设置以本机为准");
+
+        let projects: Value = serde_json::from_str(&merged.local_storage["projects"]).unwrap();
+        assert_eq!(
+            projects,
+            serde_json::json!([{"id":1,"name":"本机"},{"id":2,"name":"新增"},{"id":3,"name":"云端新增"}])
+        );
+        let settings: Value = serde_json::from_str(&merged.local_storage["settings"]).unwrap();
+        assert_eq!(settings, serde_json::json!({"theme":"dark","font":14}));
+        assert_eq!(merged.local_storage["note"], "本机", "非JSON的值取本机");
+        assert_eq!(merged.local_storage["remoteOnly"], "x", "只有云端有的键保留");
+    }
+
+    #[test]
+    fn merge_value_without_ids_keeps_local() {
+        assert_eq!(merge_value("[1,2]", "[3]"), "[1,2]");
+        assert_eq!(merge_value(r#"[{"id":1}]"#, r#"[{"name":"无id"}]"#), r#"[{"id":1}]"#);
+        assert_eq!(merge_value(r#"{"a":1}"#, "[1]"), r#"{"a":1}"#);
+    }
+
+    #[test]
+    fn content_hash_ignores_device_and_time() {
+        let a = bundle("a", &[("k", "v")]);
+        let mut b = bundle("b", &[("k", "v")]);
+        b.updated_at = 99;
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        b.local_storage.insert("k".to_string(), "changed".to_string());
+        assert_ne!(a.content_hash(), b.content_hash());
+    }
+}
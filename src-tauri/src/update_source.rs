@@ -0,0 +1,792 @@
+/*!
+ * 更新源模块
+ * 定义获取更新清单的 UpdateSource 接口，并提供WebDAV服务器、静态HTTPS目录、
+ * GitHub/Gitea Releases 接口以及本地目录/SMB共享四种实现，由 AppConfig 中的 update.source 选择
+ */
+
+use crate::config::{get_webdav_config, load_config, UpdateChannel, UpdateSourceConfig, WebDAVConfig};
+use crate::http_client::{self, HttpClient};
+use crate::manifest::{arch_from_file_name, ArtifactFormat};
+use crate::version::{channel_of, parse_version};
+use crate::webdav::WebDavClient;
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+
+/// 更新源接口
+#[async_trait]
+pub trait UpdateSource: Send + Sync {
+    /// 更新源名称，用于日志
+    fn name(&self) -> &'static str;
+
+    /// 获取更新清单（latest.json 格式）
+    async fn fetch_manifest(&self) -> Result<Value, String>;
+
+    /// 清单中相对地址的基准地址
+    fn base_url(&self) -> String;
+
+    /// 访问该更新源时使用的 Authorization 请求头
+    fn authorization(&self) -> Option<String> {
+        None
+    }
+
+    /// 更新源所在的服务器地址，下载这些服务器上的文件时才会附带认证信息
+    fn origins(&self) -> Vec<String> {
+        vec![self.base_url()]
+    }
+
+    /// 下载指定地址时使用的 Authorization 请求头
+    ///
+    /// 只对与更新源同一服务器（协议、主机、端口均相同）的地址提供认证信息，
+    /// 避免将凭据发送给第三方镜像。
+    fn authorization_for(&self, url: &str) -> Option<String> {
+        if self.origins().iter().any(|origin| same_origin(origin, url)) {
+            self.authorization()
+        } else {
+            None
+        }
+    }
+
+    /// 查询文件大小，清单中没有提供时使用
+    async fn file_size(&self, url: &str) -> (u64, String) {
+        crate::get_file_size(http_client::shared(), url, self.authorization_for(url).as_deref()).await
+    }
+}
+
+/// 根据配置创建更新源
+pub fn from_config(source: &UpdateSourceConfig, webdav: WebDAVConfig) -> Box<dyn UpdateSource> {
+    match source {
+        UpdateSourceConfig::Webdav => Box::new(WebDavSource {
+            http: http_client::shared().clone(),
+            config: webdav,
+        }),
+        UpdateSourceConfig::Static { url } => Box::new(StaticHttpSource {
+            http: http_client::shared().clone(),
+            url: url.clone(),
+        }),
+        UpdateSourceConfig::Github { api_url, owner, repo, token } => Box::new(ReleasesApiSource {
+            http: http_client::shared().clone(),
+            api_url: api_url.clone(),
+            owner: owner.clone(),
+            repo: repo.clone(),
+            authorization: token.as_ref().map(|token| format!("Bearer {}", token)),
+            // 私有仓库的 browser_download_url 不接受令牌，需要通过接口地址下载附件
+            api_assets: token.is_some(),
+        }),
+        UpdateSourceConfig::Gitea { url, owner, repo, token } => Box::new(ReleasesApiSource {
+            http: http_client::shared().clone(),
+            api_url: format!("{}/api/v1", url.trim_end_matches('/')),
+            owner: owner.clone(),
+            repo: repo.clone(),
+            authorization: token.as_ref().map(|token| format!("token {}", token)),
+            api_assets: false,
+        }),
+        UpdateSourceConfig::Local { path } => Box::new(LocalSource { path: PathBuf::from(path) }),
+    }
+}
+
+/// 根据当前配置的更新源，获取下载指定地址时使用的 Authorization 请求头
+pub fn download_authorization(url: &str) -> Option<String> {
+    let config = load_config();
+    from_config(&config.update.source, get_webdav_config()).authorization_for(url)
+}
+
+/// 下载地址指向的本地更新包，http(s) 地址返回 None
+///
+/// 只有更新源为本地目录时才接受本地路径，且路径必须位于该目录内，
+/// 避免前端通过下载命令读取本机的任意文件
+pub fn local_package_path(url: &str) -> Result<Option<PathBuf>, String> {
+    let Some(path) = crate::download::local_path(url) else {
+        return Ok(None);
+    };
+    match load_config().update.source {
+        UpdateSourceConfig::Local { path: root } => {
+            LocalSource { path: PathBuf::from(root) }.contained_path(&path).map(Some)
+        }
+        _ => Err(format!("更新源不是本地目录，不能从本地路径下载: {}", url)),
+    }
+}
+
+/// 两个地址是否属于同一服务器
+fn same_origin(a: &str, b: &str) -> bool {
+    match (reqwest::Url::parse(a), reqwest::Url::parse(b)) {
+        (Ok(a), Ok(b)) => a.origin() == b.origin(),
+        _ => false,
+    }
+}
+
+/// 通过HTTP获取JSON，accept 为 None 时使用默认的 Accept 请求头
+async fn get_json(
+    http: &HttpClient,
+    url: &str,
+    authorization: Option<&str>,
+    accept: Option<&str>,
+) -> Result<Value, String> {
+    let mut request = http
+        .client()
+        .get(url)
+        .header("User-Agent", concat!("WorkHelper/", env!("CARGO_PKG_VERSION")));
+    if let Some(authorization) = authorization {
+        request = request.header("Authorization", authorization);
+    }
+    if let Some(accept) = accept {
+        request = request.header("Accept", accept);
+    }
+
+    let response = http
+        .send(request)
+        .await
+        .map_err(|e| format!("请求版本信息失败: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("获取版本信息失败，状态码: {}", response.status()));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("解析版本信息失败: {}", e))
+}
+
+// ==================== WebDAV服务器 ====================
+
+/// WebDAV服务器，使用只读账号（或匿名）读取 {url}{remote_path}/latest.json
+pub struct WebDavSource {
+    http: HttpClient,
+    config: WebDAVConfig,
+}
+
+#[async_trait]
+impl UpdateSource for WebDavSource {
+    fn name(&self) -> &'static str {
+        "webdav"
+    }
+
+    async fn fetch_manifest(&self) -> Result<Value, String> {
+        let base_url = format!("{}{}", self.config.url, self.config.remote_path);
+        let fetched = WebDavClient::new(self.http.clone(), &base_url, self.config.authorization())
+            .get("latest.json")
+            .await
+            .map_err(|e| format!("获取版本信息失败: {}", e))?;
+
+        serde_json::from_slice(&fetched.data).map_err(|e| format!("解析版本信息失败: {}", e))
+    }
+
+    fn base_url(&self) -> String {
+        self.config.base_url.clone()
+    }
+
+    fn origins(&self) -> Vec<String> {
+        vec![self.config.url.clone(), self.config.base_url.clone()]
+    }
+
+    fn authorization(&self) -> Option<String> {
+        self.config.authorization()
+    }
+}
+
+// ==================== 静态HTTPS目录 ====================
+
+/// 静态HTTPS目录，无需认证，读取 {url}/latest.json
+pub struct StaticHttpSource {
+    http: HttpClient,
+    url: String,
+}
+
+#[async_trait]
+impl UpdateSource for StaticHttpSource {
+    fn name(&self) -> &'static str {
+        "static"
+    }
+
+    async fn fetch_manifest(&self) -> Result<Value, String> {
+        get_json(&self.http, &format!("{}/latest.json", self.url.trim_end_matches('/')), None, None).await
+    }
+
+    fn base_url(&self) -> String {
+        self.url.clone()
+    }
+}
+
+// ==================== GitHub/Gitea Releases ====================
+
+/// GitHub 或 Gitea 的 Releases 接口
+///
+/// 正式版 Release 附带 latest.json 时直接使用；否则根据各 Release 的附件生成清单，
+/// 最新的正式版作为顶层条目，预发布版按版本号归入 beta 或 nightly 通道。
+pub struct ReleasesApiSource {
+    http: HttpClient,
+    api_url: String,
+    owner: String,
+    repo: String,
+    authorization: Option<String>,
+    /// 通过接口地址（附件的 url 字段）而不是 browser_download_url 下载附件，
+    /// 下载时需要附带 Accept: application/octet-stream
+    api_assets: bool,
+}
+
+#[async_trait]
+impl UpdateSource for ReleasesApiSource {
+    fn name(&self) -> &'static str {
+        "releases"
+    }
+
+    async fn fetch_manifest(&self) -> Result<Value, String> {
+        let releases_url = format!(
+            "{}/repos/{}/{}/releases",
+            self.api_url.trim_end_matches('/'),
+            self.owner,
+            self.repo
+        );
+        let releases = get_json(&self.http, &releases_url, self.authorization.as_deref(), None).await?;
+        let releases = releases.as_array().ok_or("Releases 接口返回格式错误")?;
+
+        let mut stable: Option<&Value> = None;
+        let mut channels = Map::new();
+
+        for release in releases.iter().filter(|r| !r["draft"].as_bool().unwrap_or(false)) {
+            let Some(version) = release["tag_name"].as_str().and_then(parse_version) else {
+                continue;
+            };
+
+            if !release["prerelease"].as_bool().unwrap_or(false) && version.pre.is_empty() {
+                if stable.is_none() {
+                    stable = Some(release);
+                }
+            } else {
+                // 标记为预发布但版本号没有预发布标识的，归入 beta 通道
+                let channel = match channel_of(&version) {
+                    UpdateChannel::Stable => UpdateChannel::Beta,
+                    channel => channel,
+                }
+                .as_str();
+                if !channels.contains_key(channel) {
+                    channels.insert(channel.to_string(), release_entry(release, self.api_assets));
+                }
+            }
+        }
+
+        // 正式版附带的 latest.json 优先
+        if let Some(manifest_url) = stable.and_then(|release| asset_url(release, "latest.json", self.api_assets)) {
+            let accept = self.api_assets.then_some(ASSET_ACCEPT);
+            return get_json(&self.http, &manifest_url, self.authorization.as_deref(), accept).await;
+        }
+
+        let mut manifest = match stable {
+            Some(release) => release_entry(release, self.api_assets),
+            None => json!({}),
+        };
+        manifest["channels"] = Value::Object(channels);
+        Ok(manifest)
+    }
+
+    fn base_url(&self) -> String {
+        String::new()
+    }
+
+    fn authorization(&self) -> Option<String> {
+        self.authorization.clone()
+    }
+
+    /// 只向接口所在的服务器发送认证信息；Gitea 的附件地址与接口同属一个服务器
+    fn origins(&self) -> Vec<String> {
+        vec![self.api_url.clone()]
+    }
+}
+
+/// 通过接口地址下载附件时要求返回文件内容而不是附件信息
+pub const ASSET_ACCEPT: &str = "application/octet-stream";
+
+/// 附件的下载地址
+fn asset_download_url(asset: &Value, api_assets: bool) -> Option<&str> {
+    let field = if api_assets { "url" } else { "browser_download_url" };
+    asset[field].as_str()
+}
+
+/// 查找 Release 中指定名称附件的下载地址
+fn asset_url(release: &Value, name: &str, api_assets: bool) -> Option<String> {
+    release["assets"]
+        .as_array()?
+        .iter()
+        .find(|asset| asset["name"].as_str() == Some(name))
+        .and_then(|asset| asset_download_url(asset, api_assets))
+        .map(|url| url.to_string())
+}
+
+/// 将 Release 转换为清单中的版本条目
+fn release_entry(release: &Value, api_assets: bool) -> Value {
+    let version = release["tag_name"].as_str().unwrap_or("");
+    let version = version.strip_prefix('v').unwrap_or(version);
+
+    let artifacts: Vec<Value> = release["assets"]
+        .as_array()
+        .map(|assets| assets.iter().filter_map(|asset| asset_artifact(asset, api_assets)).collect())
+        .unwrap_or_default();
+
+    json!({
+        "version": version,
+        "releaseDate": release["published_at"].as_str().unwrap_or(""),
+        "changelog": release["body"].as_str().unwrap_or(""),
+        "artifacts": artifacts,
+    })
+}
+
+/// 根据附件文件名推断安装包格式、系统和架构
+fn asset_artifact(asset: &Value, api_assets: bool) -> Option<Value> {
+    let name = asset["name"].as_str()?;
+    let format = ArtifactFormat::from_file_name(name)?;
+
+    Some(json!({
+        "os": format.os(),
+        "arch": arch_from_file_name(name),
+        "format": format,
+        "url": asset_download_url(asset, api_assets)?,
+        "fileName": name,
+        "fileSize": asset["size"].as_u64(),
+    }))
+}
+
+// ==================== 本地目录/SMB共享 ====================
+
+/// 本地目录或SMB共享路径（如 \\server\share\releases），用于无法访问外网的办公室
+pub struct LocalSource {
+    path: PathBuf,
+}
+
+impl LocalSource {
+    /// 更新目录内的文件，相对路径相对于更新目录；目录外的路径（包括经 .. 或符号链接跳出的路径）返回错误
+    fn contained_path(&self, path: &Path) -> Result<PathBuf, String> {
+        let root = std::fs::canonicalize(&self.path)
+            .map_err(|e| format!("无法访问本地更新目录: {} - {}", self.path.display(), e))?;
+        let path = self.path.join(path);
+        let resolved = std::fs::canonicalize(&path).map_err(|e| format!("无法访问更新包: {} - {}", path.display(), e))?;
+
+        if !resolved.starts_with(&root) {
+            return Err(format!("更新包不在本地更新目录中: {}", path.display()));
+        }
+        Ok(resolved)
+    }
+}
+
+#[async_trait]
+impl UpdateSource for LocalSource {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn fetch_manifest(&self) -> Result<Value, String> {
+        let manifest_path = self.path.join("latest.json");
+        let content = tokio::fs::read_to_string(&manifest_path)
+            .await
+            .map_err(|e| format!("读取版本信息失败: {} - {}", manifest_path.display(), e))?;
+
+        serde_json::from_str(&content).map_err(|e| format!("解析版本信息失败: {}", e))
+    }
+
+    fn base_url(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+
+    async fn file_size(&self, url: &str) -> (u64, String) {
+        let path = crate::download::local_path(url).unwrap_or_else(|| PathBuf::from(url));
+        let Ok(path) = self.contained_path(&path) else {
+            return (0, "未知".to_string());
+        };
+        match tokio::fs::metadata(path).await {
+            Ok(metadata) => (metadata.len(), crate::format_file_size(metadata.len())),
+            Err(_) => (0, "未知".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::{NetworkConfig, ProxyConfig};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 测试用的临时目录，结束时删除
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("workhelper-{}-{}", name, uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// 只响应一次请求的本地HTTP服务器，返回服务器地址和收到的请求
+    async fn serve_once(status: &'static str, body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let read = stream.read(&mut request).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request[..read]).to_string()
+        });
+        (address, handle)
+    }
+
+    /// 按顺序响应多个请求的本地HTTP服务器，响应内容可以引用服务器地址，返回服务器地址和收到的全部请求
+    async fn serve_sequence(
+        responses: impl FnOnce(&str) -> Vec<(&'static str, String)>,
+    ) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let responses = responses(&address);
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let read = stream.read(&mut request).await.unwrap();
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                requests.push(String::from_utf8_lossy(&request[..read]).to_string());
+            }
+            requests
+        });
+        (address, handle)
+    }
+
+    /// 请求中指定请求头的值（不区分大小写）
+    fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+        request.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    fn test_http() -> HttpClient {
+        HttpClient::with_config(NetworkConfig {
+            retries: 0,
+            proxy: ProxyConfig::None,
+            ..NetworkConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn static_source_fetches_latest_json() {
+        let (url, request) = serve_once("200 OK", r#"{"version":"1.2.0"}"#).await;
+        let source = StaticHttpSource {
+            http: test_http(),
+            url: format!("{}/releases/", url),
+        };
+
+        let manifest = source.fetch_manifest().await.unwrap();
+        assert_eq!(manifest["version"], "1.2.0");
+        assert!(request.await.unwrap().starts_with("GET /releases/latest.json "));
+        assert_eq!(source.authorization_for(&format!("{}/releases/a.exe", url)), None);
+    }
+
+    #[tokio::test]
+    async fn static_source_reports_error_status() {
+        let (url, _request) = serve_once("404 Not Found", "{}").await;
+        let source = StaticHttpSource { http: test_http(), url };
+
+        let error = source.fetch_manifest().await.unwrap_err();
+        assert!(error.contains("404"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn local_source_reads_manifest_and_file_size() {
+        let dir = TempDir::new("local-source");
+        std::fs::write(dir.0.join("latest.json"), r#"{"version":"1.2.0"}"#).unwrap();
+        std::fs::write(dir.0.join("setup.exe"), [0u8; 10]).unwrap();
+        let source = LocalSource { path: dir.0.clone() };
+
+        assert_eq!(source.fetch_manifest().await.unwrap()["version"], "1.2.0");
+        let url = crate::manifest::resolve_url("setup.exe", &source.base_url());
+        assert_eq!(source.file_size(&url).await.0, 10);
+        assert_eq!(source.file_size("missing.exe").await.0, 0);
+    }
+
+    #[tokio::test]
+    async fn local_source_without_manifest_fails() {
+        let dir = TempDir::new("local-empty");
+        let source = LocalSource { path: dir.0.clone() };
+
+        assert!(source.fetch_manifest().await.is_err());
+    }
+
+    #[test]
+    fn local_source_rejects_paths_outside_root() {
+        let dir = TempDir::new("local-contained");
+        let root = dir.0.join("releases");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("setup.exe"), b"installer").unwrap();
+        std::fs::write(dir.0.join("secret.txt"), b"secret").unwrap();
+        let source = LocalSource { path: root.clone() };
+
+        assert!(source.contained_path(Path::new("setup.exe")).is_ok());
+        assert!(source.contained_path(&root.join("setup.exe")).is_ok());
+        assert!(source.contained_path(Path::new("../secret.txt")).is_err());
+        assert!(source.contained_path(&dir.0.join("secret.txt")).is_err());
+        assert!(source.contained_path(Path::new("missing.exe")).is_err());
+    }
+
+    fn webdav_config(url: &str, username: &str, password: &str) -> WebDAVConfig {
+        WebDAVConfig {
+            url: url.to_string(),
+            username: username.to_string(),
+            password: password.to_string(),
+            remote_path: "/workHelper".to_string(),
+            base_url: format!("{}/files", url),
+        }
+    }
+
+    #[tokio::test]
+    async fn webdav_source_fetches_latest_json_with_basic_auth() {
+        let (url, request) = serve_once("200 OK", r#"{"version":"1.2.0"}"#).await;
+        let source = WebDavSource {
+            http: test_http(),
+            config: webdav_config(&url, "reader", "secret"),
+        };
+
+        assert_eq!(source.fetch_manifest().await.unwrap()["version"], "1.2.0");
+        let request = request.await.unwrap();
+        assert!(request.starts_with("GET /workHelper/latest.json "), "{}", request);
+        assert_eq!(
+            header(&request, "authorization"),
+            Some(crate::webdav::basic_authorization("reader", "secret").as_str())
+        );
+        // 只读账号只发送给更新服务器及其下载地址
+        assert!(source.authorization_for(&format!("{}/files/a.exe", url)).is_some());
+        assert_eq!(source.authorization_for("https://mirror.example.com/a.exe"), None);
+    }
+
+    #[tokio::test]
+    async fn webdav_source_without_account_is_anonymous() {
+        let (url, request) = serve_once("200 OK", r#"{"version":"1.2.0"}"#).await;
+        let source = WebDavSource {
+            http: test_http(),
+            config: webdav_config(&url, "", ""),
+        };
+
+        source.fetch_manifest().await.unwrap();
+        assert_eq!(header(&request.await.unwrap(), "authorization"), None);
+    }
+
+    #[tokio::test]
+    async fn webdav_source_rejects_invalid_manifest() {
+        let (url, _request) = serve_once("200 OK", "not json").await;
+        let source = WebDavSource {
+            http: test_http(),
+            config: webdav_config(&url, "", ""),
+        };
+
+        let error = source.fetch_manifest().await.unwrap_err();
+        assert!(error.contains("解析版本信息失败"), "{}", error);
+    }
+
+    fn releases_source(api_url: &str, authorization: Option<&str>, api_assets: bool) -> ReleasesApiSource {
+        ReleasesApiSource {
+            http: test_http(),
+            api_url: api_url.to_string(),
+            owner: "acme".to_string(),
+            repo: "work-helper".to_string(),
+            authorization: authorization.map(|value| value.to_string()),
+            api_assets,
+        }
+    }
+
+    /// GitHub 接口格式的附件
+    fn github_asset(address: &str, id: u32, name: &str, size: u64) -> Value {
+        json!({
+            "id": id,
+            "name": name,
+            "size": size,
+            "url": format!("{}/repos/acme/work-helper/releases/assets/{}", address, id),
+            "browser_download_url": format!("https://github.com/acme/work-helper/releases/download/{}", name),
+        })
+    }
+
+    #[tokio::test]
+    async fn github_releases_are_grouped_by_channel() {
+        let (url, requests) = serve_sequence(|address| {
+            let releases = json!([
+                {
+                    "tag_name": "v1.3.0-nightly.5", "prerelease": true, "draft": false,
+                    "assets": [github_asset(address, 1, "WorkHelper_1.3.0-nightly.5_x64-setup.exe", 10)],
+                },
+                {
+                    "tag_name": "v1.3.0", "prerelease": false, "draft": true,
+                    "assets": [github_asset(address, 2, "WorkHelper_1.3.0_x64-setup.exe", 10)],
+                },
+                {
+                    "tag_name": "v1.2.1", "prerelease": true, "draft": false,
+                    "assets": [github_asset(address, 3, "WorkHelper_1.2.1_x64-setup.exe", 10)],
+                },
+                {
+                    "tag_name": "v1.2.0", "prerelease": false, "draft": false,
+                    "published_at": "2026-01-02T00:00:00Z", "body": "修复问题",
+                    "assets": [
+                        github_asset(address, 4, "WorkHelper_1.2.0_x64-setup.exe", 100),
+                        github_asset(address, 5, "WorkHelper_1.2.0_aarch64.dmg", 200),
+                        github_asset(address, 6, "checksums.txt", 1),
+                    ],
+                },
+                {
+                    "tag_name": "v1.1.0", "prerelease": false, "draft": false,
+                    "assets": [github_asset(address, 7, "WorkHelper_1.1.0_x64-setup.exe", 100)],
+                },
+            ]);
+            vec![("200 OK", releases.to_string())]
+        })
+        .await;
+        let source = releases_source(&url, None, false);
+
+        let manifest = source.fetch_manifest().await.unwrap();
+        assert_eq!(manifest["version"], "1.2.0", "最新的正式版作为顶层条目，草稿被忽略");
+        assert_eq!(manifest["changelog"], "修复问题");
+        assert_eq!(manifest["channels"]["nightly"]["version"], "1.3.0-nightly.5");
+        assert_eq!(manifest["channels"]["beta"]["version"], "1.2.1", "没有预发布标识的预发布版归入 beta");
+
+        let artifacts = manifest["artifacts"].as_array().unwrap();
+        assert_eq!(artifacts.len(), 2, "无法识别格式的附件被忽略");
+        assert_eq!(artifacts[0]["os"], "windows");
+        assert_eq!(artifacts[0]["arch"], "x86_64");
+        assert_eq!(artifacts[0]["format"], "nsis");
+        assert_eq!(artifacts[0]["fileSize"], 100);
+        assert_eq!(
+            artifacts[0]["url"],
+            "https://github.com/acme/work-helper/releases/download/WorkHelper_1.2.0_x64-setup.exe",
+            "公开仓库使用 browser_download_url"
+        );
+        assert_eq!(artifacts[1]["os"], "macos");
+        assert_eq!(artifacts[1]["arch"], "aarch64");
+
+        let requests = requests.await.unwrap();
+        assert!(requests[0].starts_with("GET /repos/acme/work-helper/releases "), "{}", requests[0]);
+        assert_eq!(header(&requests[0], "authorization"), None);
+    }
+
+    #[tokio::test]
+    async fn private_github_repository_downloads_assets_through_api() {
+        let (url, requests) = serve_sequence(|address| {
+            let releases = json!([{
+                "tag_name": "v1.2.0", "prerelease": false, "draft": false,
+                "assets": [
+                    github_asset(address, 4, "WorkHelper_1.2.0_x64-setup.exe", 100),
+                    github_asset(address, 9, "latest.json", 50),
+                ],
+            }]);
+            vec![
+                ("200 OK", releases.to_string()),
+                ("200 OK", r#"{"version":"1.2.0","artifacts":[]}"#.to_string()),
+            ]
+        })
+        .await;
+        let source = releases_source(&url, Some("Bearer ghp_token"), true);
+
+        let manifest = source.fetch_manifest().await.unwrap();
+        assert_eq!(manifest["version"], "1.2.0");
+
+        let requests = requests.await.unwrap();
+        assert_eq!(header(&requests[0], "authorization"), Some("Bearer ghp_token"));
+        assert!(
+            requests[1].starts_with("GET /repos/acme/work-helper/releases/assets/9 "),
+            "latest.json 应通过接口地址下载: {}",
+            requests[1]
+        );
+        assert_eq!(header(&requests[1], "authorization"), Some("Bearer ghp_token"));
+        assert_eq!(header(&requests[1], "accept"), Some(ASSET_ACCEPT));
+
+        // 令牌只发送给接口服务器，不发送给 github.com 或其他地址
+        let asset = format!("{}/repos/acme/work-helper/releases/assets/4", url);
+        assert_eq!(source.authorization_for(&asset).as_deref(), Some("Bearer ghp_token"));
+        assert_eq!(
+            source.authorization_for("https://github.com/acme/work-helper/releases/download/a.exe"),
+            None
+        );
+    }
+
+    #[test]
+    fn private_github_artifacts_use_api_asset_url() {
+        let release = json!({
+            "tag_name": "v1.2.0",
+            "assets": [github_asset("https://api.github.com", 4, "WorkHelper_1.2.0_x64-setup.exe", 100)],
+        });
+
+        let entry = release_entry(&release, true);
+        assert_eq!(
+            entry["artifacts"][0]["url"],
+            "https://api.github.com/repos/acme/work-helper/releases/assets/4"
+        );
+    }
+
+    #[tokio::test]
+    async fn gitea_releases_use_token_authorization() {
+        let (url, requests) = serve_sequence(|address| {
+            let releases = json!([{
+                "id": 1,
+                "tag_name": "v1.2.0",
+                "prerelease": false,
+                "draft": false,
+                "published_at": "2026-01-02T00:00:00+08:00",
+                "assets": [{
+                    "id": 11,
+                    "name": "WorkHelper_1.2.0_amd64.deb",
+                    "size": 300,
+                    "uuid": "0d4f0b6e",
+                    "browser_download_url": format!("{}/acme/work-helper/releases/download/v1.2.0/WorkHelper_1.2.0_amd64.deb", address),
+                }],
+            }]);
+            vec![("200 OK", releases.to_string())]
+        })
+        .await;
+        let source = releases_source(&format!("{}/api/v1", url), Some("token gitea_token"), false);
+
+        let manifest = source.fetch_manifest().await.unwrap();
+        let artifact = &manifest["artifacts"][0];
+        assert_eq!(artifact["os"], "linux");
+        assert_eq!(artifact["arch"], "x86_64");
+        assert_eq!(artifact["format"], "deb");
+        assert_eq!(artifact["fileSize"], 300);
+
+        let requests = requests.await.unwrap();
+        assert!(requests[0].starts_with("GET /api/v1/repos/acme/work-helper/releases "), "{}", requests[0]);
+        assert_eq!(header(&requests[0], "authorization"), Some("token gitea_token"));
+        // Gitea 的附件与接口位于同一服务器，下载时附带令牌
+        assert!(source.authorization_for(artifact["url"].as_str().unwrap()).is_some());
+    }
+
+    #[test]
+    fn github_source_from_config_does_not_trust_github_com() {
+        let config = UpdateSourceConfig::Github {
+            api_url: "https://api.github.com".to_string(),
+            owner: "acme".to_string(),
+            repo: "work-helper".to_string(),
+            token: Some("ghp_token".to_string()),
+        };
+        let webdav = webdav_config("https://dav.example.com", "", "");
+        let source = from_config(&config, webdav);
+
+        assert_eq!(
+            source.authorization_for("https://api.github.com/repos/acme/work-helper/releases/assets/4").as_deref(),
+            Some("Bearer ghp_token")
+        );
+        assert_eq!(source.authorization_for("https://github.com/acme/work-helper/releases/download/a.exe"), None);
+    }
+}
@@ -0,0 +1,267 @@
+/*!
+ * 版本号比较模块
+ * 按语义化版本（SemVer）规范比较版本号，支持预发布版本、构建元数据和更新通道
+ */
+
+use crate::config::UpdateChannel;
+use semver::Version;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// 解析版本号
+///
+/// 允许 "v" 前缀，缺失的次版本号或修订号按0补齐（例如 "1.2" 视为 "1.2.0"）
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim();
+    let version = version
+        .strip_prefix('v')
+        .or_else(|| version.strip_prefix('V'))
+        .unwrap_or(version);
+
+    if let Ok(parsed) = Version::parse(version) {
+        return Some(parsed);
+    }
+
+    // 补齐版本号核心部分后重试
+    let split_at = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, suffix) = version.split_at(split_at);
+    let parts = core.split('.').count();
+    if parts >= 3 {
+        return None;
+    }
+
+    let padded = format!("{}{}{}", core, ".0".repeat(3 - parts), suffix);
+    Version::parse(&padded).ok()
+}
+
+/// 比较两个版本号的优先级，构建元数据不参与比较
+pub fn compare_versions(a: &Version, b: &Version) -> Ordering {
+    a.major
+        .cmp(&b.major)
+        .then(a.minor.cmp(&b.minor))
+        .then(a.patch.cmp(&b.patch))
+        .then_with(|| a.pre.cmp(&b.pre))
+}
+
+/// 判断 latest 是否比 current 更新，无法解析的版本号视为没有更新
+pub fn is_newer_version(current: &str, latest: &str) -> bool {
+    match (parse_version(current), parse_version(latest)) {
+        (Some(current), Some(latest)) => compare_versions(&latest, &current) == Ordering::Greater,
+        _ => {
+            log::warn!("无法解析版本号: current={}, latest={}", current, latest);
+            false
+        }
+    }
+}
+
+/// 版本号所属的更新通道
+///
+/// 正式版属于 stable；alpha、dev、nightly 预发布属于 nightly；其他预发布（beta、rc等）属于 beta
+pub fn channel_of(version: &Version) -> UpdateChannel {
+    if version.pre.is_empty() {
+        return UpdateChannel::Stable;
+    }
+
+    let tag = version.pre.as_str().split('.').next().unwrap_or("").to_lowercase();
+    if tag.starts_with("nightly") || tag.starts_with("alpha") || tag.starts_with("dev") {
+        UpdateChannel::Nightly
+    } else {
+        UpdateChannel::Beta
+    }
+}
+
+/// 从 latest.json 中选出当前通道可用的最新版本条目
+///
+/// 顶层字段作为默认（stable）条目，"channels" 对象可按通道名提供额外条目：
+/// `{ "version": "1.0.0", ..., "channels": { "beta": { "version": "1.1.0-beta.1", ... } } }`。
+/// 订阅的通道会同时接收更稳定通道的版本，例如 beta 用户也会收到更新的正式版。
+pub fn select_release(manifest: &Value, channel: UpdateChannel) -> Result<(&Value, Version), String> {
+    let mut candidates: Vec<&Value> = Vec::new();
+
+    if manifest.get("version").is_some() {
+        candidates.push(manifest);
+    }
+
+    if let Some(channels) = manifest.get("channels").and_then(|c| c.as_object()) {
+        for entry_channel in UpdateChannel::ALL {
+            if entry_channel > channel {
+                continue;
+            }
+            if let Some(entry) = channels.get(entry_channel.as_str()) {
+                candidates.push(entry);
+            }
+        }
+    }
+
+    let mut best: Option<(&Value, Version)> = None;
+    for entry in candidates {
+        let Some(version) = entry["version"].as_str().and_then(parse_version) else {
+            log::warn!("忽略版本号无效的更新条目: {}", entry["version"]);
+            continue;
+        };
+
+        if !channel.accepts(channel_of(&version)) {
+            continue;
+        }
+
+        let is_better = best
+            .as_ref()
+            .map_or(true, |(_, current)| compare_versions(&version, current) == Ordering::Greater);
+        if is_better {
+            best = Some((entry, version));
+        }
+    }
+
+    best.ok_or_else(|| format!("版本信息中没有适用于 {} 通道的版本", channel.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_version_cases() {
+        let cases = [
+            ("1.2.3", Some("1.2.3")),
+            ("v1.2.3", Some("1.2.3")),
+            ("V1.2.3", Some("1.2.3")),
+            (" 1.2.3 ", Some("1.2.3")),
+            ("1.2", Some("1.2.0")),
+            ("1", Some("1.0.0")),
+            ("v1.2-beta.1", Some("1.2.0-beta.1")),
+            ("0.1.0-beta.2", Some("0.1.0-beta.2")),
+            ("1.2.3+build.5", Some("1.2.3+build.5")),
+            ("1.2+build.5", Some("1.2.0+build.5")),
+            ("1.2.3.4", None),
+            ("1.x.0", None),
+            ("", None),
+            ("latest", None),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                parse_version(input).map(|version| version.to_string()),
+                expected.map(str::to_string),
+                "解析 {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn compare_versions_cases() {
+        use Ordering::*;
+        let cases = [
+            ("0.1.0-beta.2", "0.1.0", Less),
+            ("0.1.0-beta.2", "0.1.0-beta.10", Less),
+            ("0.1.0-alpha.1", "0.1.0-beta.1", Less),
+            ("0.1.0-rc.1", "0.1.0", Less),
+            ("1.0.0", "0.9.9", Greater),
+            ("1.10.0", "1.9.0", Greater),
+            ("v1.2.0", "1.2.0", Equal),
+            ("1.2", "1.2.0", Equal),
+            // 构建元数据不参与比较
+            ("1.2.0+build.1", "1.2.0+build.2", Equal),
+            ("1.2.0+build.9", "1.2.0", Equal),
+        ];
+
+        for (a, b, expected) in cases {
+            let (va, vb) = (parse_version(a).unwrap(), parse_version(b).unwrap());
+            assert_eq!(compare_versions(&va, &vb), expected, "{} 与 {}", a, b);
+            assert_eq!(compare_versions(&vb, &va), expected.reverse(), "{} 与 {}", b, a);
+        }
+    }
+
+    #[test]
+    fn is_newer_version_cases() {
+        let cases = [
+            ("0.1.0-beta.2", "0.1.0", true),
+            ("0.1.0", "0.1.0-beta.2", false),
+            ("1.2.0", "v1.2.1", true),
+            ("1.2.0", "1.2.0+build.2", false),
+            ("1.2.0", "1.2.0", false),
+            ("1.2.0", "invalid", false),
+            ("invalid", "1.2.0", false),
+        ];
+
+        for (current, latest, expected) in cases {
+            assert_eq!(is_newer_version(current, latest), expected, "{} -> {}", current, latest);
+        }
+    }
+
+    #[test]
+    fn channel_of_cases() {
+        let cases = [
+            ("1.0.0", UpdateChannel::Stable),
+            ("1.0.0+build.1", UpdateChannel::Stable),
+            ("1.0.0-beta.1", UpdateChannel::Beta),
+            ("1.0.0-rc.2", UpdateChannel::Beta),
+            ("1.0.0-nightly.20260101", UpdateChannel::Nightly),
+            ("1.0.0-alpha", UpdateChannel::Nightly),
+            ("1.0.0-dev.3", UpdateChannel::Nightly),
+            ("1.0.0-Nightly.1", UpdateChannel::Nightly),
+        ];
+
+        for (version, expected) in cases {
+            assert_eq!(channel_of(&parse_version(version).unwrap()), expected, "{}", version);
+        }
+    }
+
+    #[test]
+    fn select_release_per_channel() {
+        let manifest = json!({
+            "version": "1.2.0",
+            "channels": {
+                "beta": {"version": "1.3.0-beta.2"},
+                "nightly": {"version": "1.4.0-nightly.7"},
+            },
+        });
+
+        let cases = [
+            (UpdateChannel::Stable, "1.2.0"),
+            (UpdateChannel::Beta, "1.3.0-beta.2"),
+            (UpdateChannel::Nightly, "1.4.0-nightly.7"),
+        ];
+        for (channel, expected) in cases {
+            let (entry, version) = select_release(&manifest, channel).unwrap();
+            assert_eq!(version.to_string(), expected, "{:?} 通道", channel);
+            assert_eq!(entry["version"], expected);
+        }
+    }
+
+    #[test]
+    fn select_release_prefers_newer_stable_for_prerelease_channels() {
+        // 正式版已超过预发布通道中的版本
+        let manifest = json!({
+            "version": "1.3.0",
+            "channels": {
+                "beta": {"version": "1.3.0-beta.2"},
+                "nightly": {"version": "1.3.0-nightly.1"},
+            },
+        });
+
+        for channel in [UpdateChannel::Beta, UpdateChannel::Nightly] {
+            let (_, version) = select_release(&manifest, channel).unwrap();
+            assert_eq!(version.to_string(), "1.3.0", "{:?} 通道也接收更新的正式版", channel);
+        }
+    }
+
+    #[test]
+    fn select_release_ignores_unsuitable_entries() {
+        // 顶层条目为预发布版时，正式版通道不接收
+        let manifest = json!({"version": "0.1.0-beta.2", "channels": {"nightly": {"version": "0.2.0-nightly.1"}}});
+        assert!(select_release(&manifest, UpdateChannel::Stable).is_err());
+        assert_eq!(select_release(&manifest, UpdateChannel::Beta).unwrap().1.to_string(), "0.1.0-beta.2");
+
+        // 版本号无效的条目被忽略
+        let manifest = json!({"version": "1.0.0", "channels": {"beta": {"version": "not-a-version"}}});
+        assert_eq!(select_release(&manifest, UpdateChannel::Beta).unwrap().1.to_string(), "1.0.0");
+
+        // 放在 beta 通道中的 nightly 版本不提供给 beta 用户
+        let manifest = json!({"version": "1.0.0", "channels": {"beta": {"version": "1.1.0-nightly.1"}}});
+        assert_eq!(select_release(&manifest, UpdateChannel::Beta).unwrap().1.to_string(), "1.0.0");
+
+        assert!(select_release(&json!({}), UpdateChannel::Nightly).is_err());
+    }
+}
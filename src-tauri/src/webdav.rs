@@ -0,0 +1,711 @@
+/*!
+ * WebDAV客户端模块
+ * 提供 PROPFIND（Depth 0/1）、GET、PUT、MKCOL、DELETE、MOVE、COPY 操作，
+ * 使用 XML 解析器按命名空间读取 multistatus 响应，支持基于 ETag 的条件请求，
+ * 供更新检查、发布命令和设置同步共用
+ */
+
+use crate::config::WebDAVConfig;
+use crate::http_client::{self, HttpClient};
+use quick_xml::events::Event;
+use quick_xml::name::{Namespace, ResolveResult};
+use quick_xml::NsReader;
+use reqwest::{Method, StatusCode};
+use std::fmt;
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propfind xmlns:D="DAV:">
+    <D:prop>
+        <D:resourcetype/>
+        <D:getcontentlength/>
+        <D:getcontenttype/>
+        <D:getetag/>
+        <D:getlastmodified/>
+    </D:prop>
+</D:propfind>"#;
+
+/// WebDAV操作错误
+#[derive(Debug)]
+pub enum WebDavError {
+    /// 资源不存在（404）
+    NotFound,
+    /// 条件请求失败（412），通常是 ETag 已变化或目标已存在
+    PreconditionFailed,
+    /// 网络错误、其他状态码或 multistatus 中的失败项
+    Other(String),
+}
+
+impl fmt::Display for WebDavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebDavError::NotFound => write!(f, "WebDAV资源不存在"),
+            WebDavError::PreconditionFailed => write!(f, "WebDAV资源已被修改或已存在"),
+            WebDavError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<WebDavError> for String {
+    fn from(error: WebDavError) -> Self {
+        error.to_string()
+    }
+}
+
+/// PROPFIND 深度
+#[derive(Debug, Clone, Copy)]
+pub enum Depth {
+    /// 只查询资源本身
+    Zero,
+    /// 查询资源及其直接子资源
+    One,
+}
+
+/// PUT 的前置条件
+#[derive(Debug, Clone)]
+pub enum Condition {
+    /// 无条件覆盖
+    None,
+    /// 仅当服务器上的 ETag 与给定值一致时写入
+    IfMatch(String),
+    /// 仅当资源不存在时写入
+    IfNoneMatch,
+}
+
+/// PROPFIND 返回的资源属性
+#[derive(Debug, Clone, Default)]
+pub struct Resource {
+    pub href: String,
+    pub is_collection: bool,
+    pub content_length: Option<u64>,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Resource {
+    /// 资源名称（href 的最后一段，已解码）
+    pub fn name(&self) -> String {
+        let name = self
+            .href
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or("");
+        percent_decode(name)
+    }
+
+    fn merge(&mut self, other: Resource) {
+        self.is_collection |= other.is_collection;
+        self.content_length = other.content_length.or(self.content_length);
+        self.content_type = other.content_type.or(self.content_type.take());
+        self.etag = other.etag.or(self.etag.take());
+        self.last_modified = other.last_modified.or(self.last_modified.take());
+    }
+}
+
+/// GET 获取的文件内容
+#[derive(Debug)]
+pub struct Fetched {
+    pub data: Vec<u8>,
+    pub etag: Option<String>,
+}
+
+/// WebDAV客户端，相对路径基于 base_url，以 http(s):// 开头的路径直接使用
+#[derive(Clone)]
+pub struct WebDavClient {
+    http: HttpClient,
+    client: reqwest::Client,
+    base_url: String,
+    authorization: Option<String>,
+}
+
+impl WebDavClient {
+    pub fn new(http: HttpClient, base_url: &str, authorization: Option<String>) -> Self {
+        Self {
+            client: http.client(),
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            authorization,
+        }
+    }
+
+    /// 使用WebDAV配置创建客户端，基准地址为 {url}{remote_path}，设置了账号时使用Basic认证
+    pub fn from_config(config: &WebDAVConfig) -> Self {
+        Self::new(
+            http_client::shared().clone(),
+            &format!("{}{}", config.url, config.remote_path),
+            config.authorization(),
+        )
+    }
+
+    /// 完整的资源地址
+    pub fn url(&self, path: &str) -> String {
+        if path.starts_with("http://") || path.starts_with("https://") {
+            path.to_string()
+        } else {
+            format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, self.url(path));
+        match &self.authorization {
+            Some(authorization) => request.header("Authorization", authorization),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder, action: &str, path: &str) -> Result<reqwest::Response, WebDavError> {
+        check_status(self.http.send(request).await, action, path)
+    }
+
+    /// 发送作用于整个目录的请求（DELETE、MOVE、COPY），207 响应中有失败项时返回错误
+    async fn send_checked(&self, request: reqwest::RequestBuilder, action: &str, path: &str) -> Result<(), WebDavError> {
+        let response = self.send(request, action, path).await?;
+        if response.status() != StatusCode::MULTI_STATUS {
+            return Ok(());
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| WebDavError::Other(format!("读取 {} 响应失败: {}", action, e)))?;
+        check_multistatus(&body, action)
+    }
+
+    /// 查询资源属性，Depth 为 One 时同时返回直接子资源（第一项为资源本身）
+    pub async fn propfind(&self, path: &str, depth: Depth) -> Result<Vec<Resource>, WebDavError> {
+        let depth = match depth {
+            Depth::Zero => "0",
+            Depth::One => "1",
+        };
+        let request = self
+            .request(method("PROPFIND"), path)
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .header("Depth", depth)
+            .body(PROPFIND_BODY);
+
+        let body = self
+            .send(request, "PROPFIND", path)
+            .await?
+            .text()
+            .await
+            .map_err(|e| WebDavError::Other(format!("读取 PROPFIND 响应失败: {}", e)))?;
+
+        Ok(parse_multistatus(&body)?
+            .into_iter()
+            .filter(|entry| entry.status.map_or(true, is_success))
+            .map(|entry| entry.resource)
+            .collect())
+    }
+
+    /// 查询单个资源的属性
+    pub async fn stat(&self, path: &str) -> Result<Resource, WebDavError> {
+        self.propfind(path, Depth::Zero)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| WebDavError::Other(format!("PROPFIND {} 未返回资源信息", path)))
+    }
+
+    /// 列出目录下的直接子资源（不含目录本身）
+    ///
+    /// 服务器不保证目录本身是第一个 response，按 href 与请求地址比较后排除
+    pub async fn list(&self, path: &str) -> Result<Vec<Resource>, WebDavError> {
+        let collection = href_path(&self.url(path));
+        let mut resources = self.propfind(path, Depth::One).await?;
+        resources.retain(|resource| href_path(&resource.href) != collection);
+        Ok(resources)
+    }
+
+    /// 下载文件
+    pub async fn get(&self, path: &str) -> Result<Fetched, WebDavError> {
+        let response = self.send(self.request(Method::GET, path), "GET", path).await?;
+        let etag = header_etag(&response);
+        let data = response
+            .bytes()
+            .await
+            .map_err(|e| WebDavError::Other(format!("下载 {} 失败: {}", path, e)))?
+            .to_vec();
+
+        Ok(Fetched { data, etag })
+    }
+
+    /// 上传文件，返回服务器提供的新 ETag；上传安装包等大文件时不限制总时间
+    pub async fn put(&self, path: &str, data: Vec<u8>, condition: Condition) -> Result<Option<String>, WebDavError> {
+        let mut request = self
+            .request(Method::PUT, path)
+            .header("Content-Type", "application/octet-stream")
+            .body(data);
+        request = match condition {
+            Condition::None => request,
+            Condition::IfMatch(etag) => request.header("If-Match", etag),
+            Condition::IfNoneMatch => request.header("If-None-Match", "*"),
+        };
+
+        let response = check_status(self.http.send_streaming(request).await, "PUT", path)?;
+        Ok(header_etag(&response))
+    }
+
+    /// 创建目录，目录已存在（405）时视为成功
+    pub async fn mkcol(&self, path: &str) -> Result<(), WebDavError> {
+        let response = self
+            .http
+            .send(self.request(method("MKCOL"), path))
+            .await
+            .map_err(|e| WebDavError::Other(format!("MKCOL {} 失败: {}", path, e)))?;
+
+        let status = response.status();
+        if status.is_success() || status == StatusCode::METHOD_NOT_ALLOWED {
+            Ok(())
+        } else {
+            Err(WebDavError::Other(format!("创建目录 {} 失败，状态码: {}", path, status)))
+        }
+    }
+
+    /// 逐级创建相对于基准地址的目录
+    pub async fn mkcol_all(&self, path: &str) -> Result<(), WebDavError> {
+        let mut current = String::new();
+        for part in path.split('/').filter(|part| !part.is_empty()) {
+            current = format!("{}{}/", current, part);
+            self.mkcol(&current).await?;
+        }
+        Ok(())
+    }
+
+    /// 删除文件或目录，提供 ETag 时仅在未被修改的情况下删除
+    pub async fn delete(&self, path: &str, if_match: Option<&str>) -> Result<(), WebDavError> {
+        let mut request = self.request(Method::DELETE, path);
+        if let Some(etag) = if_match {
+            request = request.header("If-Match", etag);
+        }
+        self.send_checked(request, "DELETE", path).await
+    }
+
+    /// 移动（重命名）资源
+    pub async fn move_to(&self, from: &str, to: &str, overwrite: bool) -> Result<(), WebDavError> {
+        self.transfer("MOVE", from, to, overwrite).await
+    }
+
+    /// 复制资源
+    pub async fn copy(&self, from: &str, to: &str, overwrite: bool) -> Result<(), WebDavError> {
+        self.transfer("COPY", from, to, overwrite).await
+    }
+
+    async fn transfer(&self, action: &str, from: &str, to: &str, overwrite: bool) -> Result<(), WebDavError> {
+        let request = self
+            .request(method(action), from)
+            .header("Destination", self.url(to))
+            .header("Overwrite", if overwrite { "T" } else { "F" });
+        self.send_checked(request, action, from).await
+    }
+}
+
+/// 生成 Basic 认证的 Authorization 请求头
+pub fn basic_authorization(username: &str, password: &str) -> String {
+    use base64::{engine::general_purpose, Engine as _};
+    let auth = general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+    format!("Basic {}", auth)
+}
+
+fn method(name: &str) -> Method {
+    Method::from_bytes(name.as_bytes()).expect("WebDAV方法名有效")
+}
+
+/// 检查请求结果的状态码，找不到资源和条件不满足时返回对应的错误
+fn check_status(result: reqwest::Result<reqwest::Response>, action: &str, path: &str) -> Result<reqwest::Response, WebDavError> {
+    let response = result.map_err(|e| WebDavError::Other(format!("{} {} 失败: {}", action, path, e)))?;
+
+    match response.status() {
+        StatusCode::NOT_FOUND => Err(WebDavError::NotFound),
+        StatusCode::PRECONDITION_FAILED => Err(WebDavError::PreconditionFailed),
+        status if status.is_success() => Ok(response),
+        status => Err(WebDavError::Other(format!("{} {} 失败，状态码: {}", action, path, status))),
+    }
+}
+
+fn header_etag(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+fn is_success(status: u16) -> bool {
+    (200..300).contains(&status)
+}
+
+// ==================== multistatus 解析 ====================
+
+/// multistatus 中的单个 response
+#[derive(Debug, Default)]
+struct Entry {
+    resource: Resource,
+    /// response 级别的状态码（DELETE、COPY 等操作失败时出现）
+    status: Option<u16>,
+    description: Option<String>,
+}
+
+/// propstat 中的属性及其状态
+#[derive(Debug, Default)]
+struct PropStat {
+    props: Resource,
+    status: Option<u16>,
+}
+
+/// 解析 207 Multi-Status 响应，只接受 DAV: 命名空间（或未声明命名空间）的元素
+fn parse_multistatus(body: &str) -> Result<Vec<Entry>, WebDavError> {
+    let mut reader = NsReader::from_str(body);
+    reader.config_mut().trim_text(true);
+
+    let mut path: Vec<Option<String>> = Vec::new();
+    let mut entries = Vec::new();
+    let mut entry: Option<Entry> = None;
+    let mut propstat: Option<PropStat> = None;
+
+    loop {
+        let (namespace, event) = reader
+            .read_resolved_event()
+            .map_err(|e| WebDavError::Other(format!("解析WebDAV响应失败: {}", e)))?;
+
+        match &event {
+            Event::Start(element) | Event::Empty(element) => {
+                let name = dav_name(&namespace, element.local_name().as_ref());
+                match name.as_deref() {
+                    Some("response") => entry = Some(Entry::default()),
+                    Some("propstat") => propstat = Some(PropStat::default()),
+                    Some("collection") if path.iter().any(|n| n.as_deref() == Some("resourcetype")) => {
+                        if let Some(propstat) = propstat.as_mut() {
+                            propstat.props.is_collection = true;
+                        }
+                    }
+                    _ => {}
+                }
+
+                if matches!(event, Event::Start(_)) {
+                    path.push(name);
+                } else {
+                    end_element(name.as_deref(), &mut entry, &mut propstat, &mut entries);
+                }
+            }
+            Event::End(_) => {
+                let name = path.pop().flatten();
+                end_element(name.as_deref(), &mut entry, &mut propstat, &mut entries);
+            }
+            Event::Text(text) => {
+                let text = text
+                    .unescape()
+                    .map_err(|e| WebDavError::Other(format!("解析WebDAV响应失败: {}", e)))?
+                    .to_string();
+                let Some(Some(current)) = path.last() else {
+                    continue;
+                };
+
+                match (current.as_str(), propstat.as_mut(), entry.as_mut()) {
+                    ("href", None, Some(entry)) => entry.resource.href = text,
+                    ("status", Some(propstat), _) => propstat.status = parse_status(&text),
+                    ("status", None, Some(entry)) => entry.status = parse_status(&text),
+                    ("responsedescription", None, Some(entry)) => entry.description = Some(text),
+                    ("getcontentlength", Some(propstat), _) => propstat.props.content_length = text.parse().ok(),
+                    ("getcontenttype", Some(propstat), _) => propstat.props.content_type = Some(text),
+                    ("getetag", Some(propstat), _) => propstat.props.etag = Some(text),
+                    ("getlastmodified", Some(propstat), _) => propstat.props.last_modified = Some(text),
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+fn end_element(
+    name: Option<&str>,
+    entry: &mut Option<Entry>,
+    propstat: &mut Option<PropStat>,
+    entries: &mut Vec<Entry>,
+) {
+    match name {
+        // 只采用状态为成功的 propstat 中的属性
+        Some("propstat") => {
+            if let (Some(propstat), Some(entry)) = (propstat.take(), entry.as_mut()) {
+                if propstat.status.map_or(true, is_success) {
+                    entry.resource.merge(propstat.props);
+                }
+            }
+        }
+        Some("response") => entries.extend(entry.take()),
+        _ => {}
+    }
+}
+
+/// DAV: 命名空间中元素的本地名称，其他命名空间返回 None
+fn dav_name(namespace: &ResolveResult, local_name: &[u8]) -> Option<String> {
+    match namespace {
+        ResolveResult::Bound(Namespace(b"DAV:")) | ResolveResult::Unbound => {
+            Some(String::from_utf8_lossy(local_name).to_string())
+        }
+        _ => None,
+    }
+}
+
+/// 解析 "HTTP/1.1 200 OK" 形式的状态行
+fn parse_status(line: &str) -> Option<u16> {
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// 检查 multistatus 中是否有失败的资源，有则汇总为错误
+fn check_multistatus(body: &str, action: &str) -> Result<(), WebDavError> {
+    let failures: Vec<String> = parse_multistatus(body)?
+        .into_iter()
+        .filter_map(|entry| {
+            let status = entry.status.filter(|status| !is_success(*status))?;
+            Some(match entry.description {
+                Some(description) => format!("{} ({}: {})", entry.resource.href, status, description),
+                None => format!("{} ({})", entry.resource.href, status),
+            })
+        })
+        .collect();
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(WebDavError::Other(format!("{} 部分资源失败: {}", action, failures.join(", "))))
+    }
+}
+
+/// href 或请求地址对应的资源路径，用于比较两者是否指向同一资源
+///
+/// 完整地址只取路径部分，解码百分号编码并去掉末尾的 /
+fn href_path(href: &str) -> String {
+    let path = match reqwest::Url::parse(href) {
+        Ok(url) => url.path().to_string(),
+        Err(_) => href.to_string(),
+    };
+    percent_decode(&path).trim_end_matches('/').to_string()
+}
+
+/// 解码 href 中的百分号编码
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按 href 查找解析结果中的资源
+    fn find<'a>(entries: &'a [Entry], href: &str) -> &'a Resource {
+        &entries
+            .iter()
+            .find(|entry| entry.resource.href == href)
+            .unwrap_or_else(|| panic!("缺少资源 {}", href))
+            .resource
+    }
+
+    #[test]
+    fn parses_common_namespace_prefixes() {
+        let bodies = [
+            // 大写前缀（IIS、Apache mod_dav）
+            r#"<?xml version="1.0"?><D:multistatus xmlns:D="DAV:"><D:response><D:href>/dav/a.json</D:href>
+               <D:propstat><D:prop><D:getcontentlength>12</D:getcontentlength><D:getetag>"e1"</D:getetag></D:prop>
+               <D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response></D:multistatus>"#,
+            // 小写前缀（Nextcloud、nginx）
+            r#"<d:multistatus xmlns:d="DAV:"><d:response><d:href>/dav/a.json</d:href>
+               <d:propstat><d:prop><d:getcontentlength>12</d:getcontentlength><d:getetag>"e1"</d:getetag></d:prop>
+               <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response></d:multistatus>"#,
+            // 默认命名空间
+            r#"<multistatus xmlns="DAV:"><response><href>/dav/a.json</href>
+               <propstat><prop><getcontentlength>12</getcontentlength><getetag>"e1"</getetag></prop>
+               <status>HTTP/1.1 200 OK</status></propstat></response></multistatus>"#,
+            // Apache 对活属性使用 lp1: 等前缀，同样绑定到 DAV:
+            r#"<D:multistatus xmlns:D="DAV:"><D:response xmlns:lp1="DAV:"><D:href>/dav/a.json</D:href>
+               <D:propstat><D:prop><lp1:getcontentlength>12</lp1:getcontentlength><lp1:getetag>"e1"</lp1:getetag></D:prop>
+               <D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response></D:multistatus>"#,
+        ];
+
+        for body in bodies {
+            let entries = parse_multistatus(body).unwrap();
+            assert_eq!(entries.len(), 1, "{}", body);
+            let resource = &entries[0].resource;
+            assert_eq!(resource.href, "/dav/a.json", "{}", body);
+            assert_eq!(resource.content_length, Some(12), "{}", body);
+            assert_eq!(resource.etag.as_deref(), Some("\"e1\""), "{}", body);
+        }
+    }
+
+    #[test]
+    fn ignores_elements_outside_dav_namespace() {
+        let body = r#"<D:multistatus xmlns:D="DAV:" xmlns:X="urn:example"><D:response><D:href>/dav/a.json</D:href>
+            <D:propstat><D:prop><X:getcontentlength>99</X:getcontentlength><D:getcontentlength>12</D:getcontentlength></D:prop>
+            <D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response></D:multistatus>"#;
+
+        let entries = parse_multistatus(body).unwrap();
+        assert_eq!(entries[0].resource.content_length, Some(12));
+    }
+
+    #[test]
+    fn parses_depth_one_listing() {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>/dav/releases/v1.2.0/</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype><d:collection/></d:resourcetype><d:getlastmodified>Mon, 05 Jan 2026 08:00:00 GMT</d:getlastmodified></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/dav/releases/latest.json</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype/><d:getcontentlength>512</d:getcontentlength><d:getcontenttype>application/json</d:getcontenttype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/dav/releases/</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+
+        let entries = parse_multistatus(body).unwrap();
+        assert_eq!(entries.len(), 3);
+
+        let directory = find(&entries, "/dav/releases/v1.2.0/");
+        assert!(directory.is_collection);
+        assert_eq!(directory.name(), "v1.2.0");
+        assert_eq!(directory.last_modified.as_deref(), Some("Mon, 05 Jan 2026 08:00:00 GMT"));
+
+        let file = find(&entries, "/dav/releases/latest.json");
+        assert!(!file.is_collection);
+        assert_eq!(file.content_length, Some(512));
+        assert_eq!(file.content_type.as_deref(), Some("application/json"));
+
+        assert!(find(&entries, "/dav/releases/").is_collection);
+    }
+
+    #[test]
+    fn ignores_properties_from_failed_propstat() {
+        let body = r#"<D:multistatus xmlns:D="DAV:"><D:response><D:href>/dav/a.json</D:href>
+            <D:propstat><D:prop><D:getcontentlength>12</D:getcontentlength></D:prop>
+              <D:status>HTTP/1.1 200 OK</D:status></D:propstat>
+            <D:propstat><D:prop><D:getetag>"stale"</D:getetag><D:getcontenttype>text/plain</D:getcontenttype></D:prop>
+              <D:status>HTTP/1.1 404 Not Found</D:status></D:propstat>
+            <D:propstat><D:prop><D:getlastmodified>forbidden</D:getlastmodified></D:prop>
+              <D:status>HTTP/1.1 403 Forbidden</D:status></D:propstat>
+            </D:response></D:multistatus>"#;
+
+        let entries = parse_multistatus(body).unwrap();
+        let resource = &entries[0].resource;
+        assert_eq!(resource.content_length, Some(12));
+        assert_eq!(resource.etag, None, "404 的 propstat 中的属性应被忽略");
+        assert_eq!(resource.content_type, None);
+        assert_eq!(resource.last_modified, None, "403 的 propstat 中的属性应被忽略");
+    }
+
+    #[test]
+    fn reports_failed_responses() {
+        let body = r#"<D:multistatus xmlns:D="DAV:">
+            <D:response><D:href>/dav/a/ok.txt</D:href><D:status>HTTP/1.1 204 No Content</D:status></D:response>
+            <D:response><D:href>/dav/a/locked.txt</D:href><D:status>HTTP/1.1 423 Locked</D:status>
+              <D:responsedescription>文件被锁定</D:responsedescription></D:response>
+            </D:multistatus>"#;
+
+        let entries = parse_multistatus(body).unwrap();
+        assert_eq!(entries[0].status, Some(204));
+        assert_eq!(entries[1].status, Some(423));
+
+        let error = check_multistatus(body, "DELETE").unwrap_err().to_string();
+        assert!(error.contains("/dav/a/locked.txt (423: 文件被锁定)"), "{}", error);
+        assert!(!error.contains("ok.txt"), "{}", error);
+    }
+
+    #[test]
+    fn decodes_percent_encoded_hrefs() {
+        let body = r#"<D:multistatus xmlns:D="DAV:"><D:response>
+            <D:href>https://dav.example.com/dav/%E5%B7%A5%E4%BD%9C%E5%8A%A9%E6%89%8B/My%20Setup%2B1.exe</D:href>
+            <D:propstat><D:prop><D:resourcetype/></D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat>
+            </D:response></D:multistatus>"#;
+
+        let entries = parse_multistatus(body).unwrap();
+        assert_eq!(entries[0].resource.name(), "My Setup+1.exe");
+        assert_eq!(href_path(&entries[0].resource.href), "/dav/工作助手/My Setup+1.exe");
+        assert_eq!(percent_decode("100%"), "100%", "不完整的编码保持原样");
+    }
+
+    #[test]
+    fn href_path_matches_collection_in_any_form() {
+        let collection = href_path("https://dav.example.com/dav/工作助手");
+
+        assert_eq!(href_path("/dav/%E5%B7%A5%E4%BD%9C%E5%8A%A9%E6%89%8B/"), collection);
+        assert_eq!(href_path("https://dav.example.com/dav/%E5%B7%A5%E4%BD%9C%E5%8A%A9%E6%89%8B/"), collection);
+        assert_ne!(href_path("/dav/%E5%B7%A5%E4%BD%9C%E5%8A%A9%E6%89%8B/latest.json"), collection);
+    }
+
+    #[tokio::test]
+    async fn list_excludes_collection_wherever_it_appears() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // 目录本身排在最后，且 href 使用完整地址和百分号编码
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let body = format!(
+            r#"<d:multistatus xmlns:d="DAV:">
+              <d:response><d:href>/dav/%E5%8F%91%E5%B8%83/latest.json</d:href>
+                <d:propstat><d:prop><d:resourcetype/></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>
+              <d:response><d:href>{}/dav/%E5%8F%91%E5%B8%83/</d:href>
+                <d:propstat><d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>
+            </d:multistatus>"#,
+            address
+        );
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let read = stream.read(&mut request).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 207 Multi-Status\r\nContent-Type: application/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request[..read]).to_string()
+        });
+        let http = HttpClient::with_config(http_client::NetworkConfig {
+            retries: 0,
+            proxy: http_client::ProxyConfig::None,
+            ..http_client::NetworkConfig::default()
+        });
+        let client = WebDavClient::new(http, &format!("{}/dav/发布", address), None);
+
+        let resources = client.list("").await.unwrap();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].name(), "latest.json");
+        let request = server.await.unwrap();
+        assert!(request.starts_with("PROPFIND "), "{}", request);
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        assert!(parse_multistatus("<D:multistatus xmlns:D=\"DAV:\"><D:response></D:multistatus>").is_err());
+    }
+}
@@ -0,0 +1,148 @@
+/**
+ * 应用配置API
+ * 负责查询配置文件的加载状态，配置文件损坏时恢复默认配置，以及查看各配置项的来源。
+ * 手动修改配置文件后后端会自动重新加载，并发送 config-changed 或 config-reload-failed 事件。
+ * 可共享的设置（模板、大模型接口、OA 地址）可以导出为配置包分享给团队，或订阅团队的共享配置包
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { open, save } from '@tauri-apps/plugin-dialog';
+import type { Settings } from './settings';
+
+// ==================== 类型定义 ====================
+
+/**
+ * 配置文件加载错误
+ */
+export type ConfigLoadError =
+  | { kind: 'unreadable'; message: string }                   // 无法读取配置文件
+  | { kind: 'invalid'; message: string }                      // 配置文件内容无效
+  | { kind: 'tooNew'; version: number; supported: number }    // 配置文件来自更新版本的应用
+  | { kind: 'migration'; from: number; message: string };     // 迁移旧版本配置失败
+
+/**
+ * 配置层，按优先级从低到高排列
+ */
+export type ConfigLayer =
+  | 'default'       // 内置默认值（含构建时嵌入的值）
+  | 'system'        // 系统配置文件，由IT统一管理
+  | 'user'          // 用户配置文件
+  | 'environment'   // 环境变量
+  | 'commandLine';  // 命令行参数
+
+/**
+ * 配置项的生效值及来源
+ */
+export interface ConfigEntry {
+  path: string;                     // 字段路径，如 general.oaUrl
+  value: unknown;                   // 生效的值，凭据显示为掩码
+  source: {
+    layer: ConfigLayer;
+    origin: string;                 // 配置文件路径、环境变量名或命令行参数
+  };
+}
+
+// ==================== API 函数 ====================
+
+/**
+ * 获取配置文件加载错误
+ * @returns 配置文件可以正常使用时返回 null
+ */
+export async function getConfigLoadError(): Promise<ConfigLoadError | null> {
+  return await invoke<ConfigLoadError | null>('get_config_load_error');
+}
+
+/**
+ * 放弃无法加载的配置文件并恢复默认配置，原文件保留为备份
+ */
+export async function resetConfig(): Promise<void> {
+  await invoke('reset_config');
+}
+
+/**
+ * 列出各配置项的生效值及来源
+ */
+export async function describeConfig(): Promise<ConfigEntry[]> {
+  return await invoke<ConfigEntry[]>('describe_config');
+}
+
+/**
+ * 监听配置文件被修改并重新加载的事件
+ * @returns 取消监听的函数
+ */
+export async function onConfigChanged(callback: () => void): Promise<UnlistenFn> {
+  return await listen('config-changed', () => callback());
+}
+
+/**
+ * 监听配置文件修改无效的事件，此时后端继续使用修改前的配置
+ * @returns 取消监听的函数
+ */
+export async function onConfigReloadFailed(callback: (error: ConfigLoadError) => void): Promise<UnlistenFn> {
+  return await listen<ConfigLoadError>('config-reload-failed', (event) => callback(event.payload));
+}
+
+/**
+ * 选择保存位置并导出配置包，不含令牌、密码和账号
+ * @returns 是否已导出（取消选择时返回 false）
+ */
+export async function exportConfig(): Promise<boolean> {
+  const path = await save({
+    defaultPath: 'workhelper-config.json',
+    filters: [{ name: '配置包', extensions: ['json'] }],
+  });
+  if (!path) {
+    return false;
+  }
+  await invoke('export_config', { path });
+  return true;
+}
+
+/**
+ * 选择配置包文件并导入
+ * @returns 导入后的设置，取消选择时返回 null
+ * @throws SettingsError 配置包中的设置无效时包含各字段的错误信息
+ */
+export async function importConfig(): Promise<Settings | null> {
+  const path = await open({
+    multiple: false,
+    filters: [{ name: '配置包', extensions: ['json'] }],
+  });
+  if (!path) {
+    return null;
+  }
+  return await invoke<Settings>('import_config', { path });
+}
+
+/**
+ * 立即刷新订阅的共享配置
+ * @returns 是否应用了新的配置
+ */
+export async function refreshConfigSubscription(): Promise<boolean> {
+  return await invoke<boolean>('refresh_config_subscription');
+}
+
+/**
+ * 监听订阅的共享配置有更新并已应用的事件
+ * @returns 取消监听的函数
+ */
+export async function onConfigSubscriptionApplied(callback: (source: string) => void): Promise<UnlistenFn> {
+  return await listen<string>('config-subscription-applied', (event) => callback(event.payload));
+}
+
+/**
+ * 生成加载错误的说明文字
+ */
+export function describeConfigLoadError(error: ConfigLoadError): string {
+  switch (error.kind) {
+    case 'unreadable':
+      return `无法读取配置文件：${error.message}`;
+    case 'invalid':
+      return `配置文件内容无效：${error.message}`;
+    case 'tooNew':
+      return `配置文件来自更新版本的应用（版本 ${error.version}，当前支持 ${error.supported}），请升级应用`;
+    case 'migration':
+      return `升级配置文件（版本 ${error.from}）失败：${error.message}`;
+  }
+}
@@ -0,0 +1,52 @@
+/**
+ * Cookie API
+ * 后端代发请求时自动携带并保存服务器设置的 Cookie（按档案保存），
+ * 用于依赖会话 Cookie 的 OA 系统；这里提供查看和清除
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+// ==================== 类型定义 ====================
+
+/**
+ * Cookie 列表项
+ */
+export interface CookieInfo {
+  domain: string;            // 所属域名
+  path: string;              // 路径
+  name: string;              // 名称
+  value?: string;            // 值，HttpOnly Cookie 不返回
+  expires?: number;          // 过期时间（秒），会话 Cookie 为空
+  secure: boolean;           // 是否只通过 HTTPS 发送
+  httpOnly: boolean;         // 是否为 HttpOnly
+}
+
+// ==================== API 函数 ====================
+
+/**
+ * 列出保存的 Cookie
+ * @param host 只列出发送给该服务器的 Cookie，不传时列出全部
+ */
+export async function listCookies(host?: string): Promise<CookieInfo[]> {
+  return await invoke<CookieInfo[]>('list_cookies', { host });
+}
+
+/**
+ * 清除保存的 Cookie
+ * @param host 只清除该服务器的 Cookie，不传时清除全部
+ * @returns 清除的数量
+ */
+export async function clearCookies(host?: string): Promise<number> {
+  return await invoke<number>('clear_cookies', { host });
+}
+
+/**
+ * 地址对应的服务器名，用于按服务器查看或清除 Cookie
+ */
+export function hostOf(url: string): string | undefined {
+  try {
+    return new URL(url).hostname;
+  } catch {
+    return undefined;
+  }
+}
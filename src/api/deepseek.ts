@@ -1,11 +1,18 @@
+// 默认大模型接口地址，其他兼容 OpenAI Chat Completions 接口的服务商可在设置中修改
+export const DEEPSEEK_BASE_URL = 'https://api.deepseek.com';
+
+// 拼接 Chat Completions 接口地址
+const chatCompletionsUrl = (baseUrl: string) => `${baseUrl.replace(/\/+$/, '')}/chat/completions`;
+
 // 使用前端 fetch 处理 DeepSeek API，支持流式响应
 export const chatWithDeepSeek = async (
   messages: any[],
   token: string,
   model = 'deepseek-chat',
-  stream = false
+  stream = false,
+  baseUrl = DEEPSEEK_BASE_URL
 ) => {
-  const response = await fetch('https://api.deepseek.com/v1/chat/completions', {
+  const response = await fetch(chatCompletionsUrl(baseUrl), {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -19,7 +26,7 @@ export const chatWithDeepSeek = async (
   });
 
   if (!response.ok) {
-    throw new Error(`DeepSeek API 请求失败: ${response.status} ${response.statusText}`);
+    throw new Error(`大模型接口请求失败: ${response.status} ${response.statusText}`);
   }
 
   if (stream) {
@@ -34,9 +41,10 @@ export const chatWithDeepSeekStream = async (
   messages: any[],
   token: string,
   model = 'deepseek-chat',
-  onChunk: (text: string) => void
+  onChunk: (text: string) => void,
+  baseUrl = DEEPSEEK_BASE_URL
 ) => {
-  const response = await fetch('https://api.deepseek.com/v1/chat/completions', {
+  const response = await fetch(chatCompletionsUrl(baseUrl), {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -50,7 +58,7 @@ export const chatWithDeepSeekStream = async (
   });
 
   if (!response.ok) {
-    throw new Error(`DeepSeek API 请求失败: ${response.status} ${response.statusText}`);
+    throw new Error(`大模型接口请求失败: ${response.status} ${response.statusText}`);
   }
 
   const reader = response.body?.getReader();
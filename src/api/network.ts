@@ -0,0 +1,51 @@
+/**
+ * 网络设置API
+ * 应用内的HTTP请求共用后端按网络设置创建的客户端：超时、重试、代理和额外信任的CA证书，
+ * 用于需要代理上网或使用内部证书的公司网络
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+// ==================== 类型定义 ====================
+
+/**
+ * 代理设置
+ */
+export type ProxyConfig =
+  | { type: 'system' }                // 使用系统代理（含 HTTP_PROXY、HTTPS_PROXY 环境变量）
+  | { type: 'none' }                  // 不使用代理
+  | {
+      type: 'manual';                 // 手动设置的代理
+      url: string;                    // 代理地址，支持 http://、https://、socks5://、socks5h://
+      username: string;               // 代理用户名，可为空
+      password: string;               // 代理密码
+      noProxy: string;                // 不使用代理的地址，逗号分隔
+    };
+
+/**
+ * 网络设置接口
+ */
+export interface NetworkConfig {
+  connectTimeoutSecs: number;         // 建立连接的超时时间（秒）
+  readTimeoutSecs: number;            // 读取超时（秒）
+  retries: number;                    // 幂等请求失败后的最多重试次数
+  retryDelayMs: number;               // 第一次重试前的等待时间（毫秒），之后每次加倍
+  proxy: ProxyConfig;                 // 代理设置
+  caCertificates: string[];           // 额外信任的CA证书文件（PEM 或 DER）
+}
+
+// ==================== API 函数 ====================
+
+/**
+ * 获取网络设置
+ */
+export async function getNetworkConfig(): Promise<NetworkConfig> {
+  return await invoke<NetworkConfig>('get_network_config');
+}
+
+/**
+ * 保存网络设置，代理地址或CA证书无效时抛出错误
+ */
+export async function saveNetworkConfig(networkConfig: NetworkConfig): Promise<void> {
+  await invoke('save_network_config', { networkConfig });
+}
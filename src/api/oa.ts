@@ -6,7 +6,10 @@
  * 确保开发和生产环境的一致性。
  */
 
-import { oaHttpClient } from '@/utils/tauri-http-client';
+import { oaHttpClient, isOk, parseResponseBody, type HttpResponse } from '@/utils/tauri-http-client';
+import { clearCookies, hostOf } from './cookies';
+import { getSettings, updateSettings } from './settings';
+import { profileStorageKey } from './profiles';
 
 // ==================== 配置常量 ====================
 
@@ -305,14 +308,16 @@ class OAApiClient {
     try {
       console.log(`[OA API] 请求: ${options.method} ${url}`, token ? '(带token)' : '(无token)')
 
-      const result = await this.executeRequest<T>(url, options, headers)
+      const response = await this.executeRequest(url, options, headers)
+      const result = parseResponseBody<T>(response)
 
-      console.log(`[OA API] 响应:`, result)
+      console.log(`[OA API] 响应: ${response.status}`, result)
 
-      // 检查响应中的认证错误
-      this.checkAuthenticationError(result)
+      // 检查认证错误和HTTP错误状态
+      this.checkAuthenticationError(response, result)
+      this.checkResponseStatus(response, result)
 
-      return result as T
+      return result
 
     } catch (error) {
       console.error(`[OA API] 请求失败:`, error)
@@ -325,24 +330,20 @@ class OAApiClient {
    * @param url 请求URL
    * @param options 请求选项
    * @param headers 请求头
-   * @returns Promise<T> 响应数据
+   * @returns Promise<HttpResponse> 完整响应，包含状态码
    */
-  private async executeRequest<T>(url: string, options: RequestInit, headers: Record<string, string>): Promise<T> {
+  private async executeRequest(url: string, options: RequestInit, headers: Record<string, string>): Promise<HttpResponse> {
     const method = (options.method || 'GET') as 'GET' | 'POST' | 'PUT' | 'DELETE'
     const body = options.body as string | undefined
 
-    switch (method) {
-      case 'GET':
-        return await oaHttpClient.get<T>(url, headers)
-      case 'POST':
-        return await oaHttpClient.post<T>(url, body ? JSON.parse(body) : undefined, headers)
-      case 'PUT':
-        return await oaHttpClient.put<T>(url, body ? JSON.parse(body) : undefined, headers)
-      case 'DELETE':
-        return await oaHttpClient.delete<T>(url, headers)
-      default:
-        throw new Error(`不支持的HTTP方法: ${method}`)
+    if (!['GET', 'POST', 'PUT', 'DELETE'].includes(method)) {
+      throw new Error(`不支持的HTTP方法: ${method}`)
     }
+    return await oaHttpClient.send(url, {
+      method,
+      headers,
+      body: body ? JSON.parse(body) : undefined
+    })
   }
 
   /**
@@ -357,15 +358,31 @@ class OAApiClient {
 
   /**
    * 检查认证错误并处理
-   * @param result 响应结果
+   * OA 系统可能以HTTP状态码或响应中的 code 字段返回401
+   * @param response 完整响应
+   * @param result 解析后的响应数据
    */
-  private checkAuthenticationError(result: any): void {
-    if (result && typeof result === 'object' && 'code' in result && result.code === 401) {
-      console.warn('[OA API] 响应中检测到401错误，清除本地token')
+  private checkAuthenticationError(response: HttpResponse, result: any): void {
+    const codeIs401 = result && typeof result === 'object' && 'code' in result && result.code === 401
+    if (response.status === 401 || codeIs401) {
+      console.warn('[OA API] 检测到401错误，清除本地token')
       OATokenManager.clearToken()
       throw new Error('登录已过期，请重新登录')
     }
   }
+
+  /**
+   * 检查HTTP错误状态，优先使用响应中的错误消息
+   * @param response 完整响应
+   * @param result 解析后的响应数据
+   */
+  private checkResponseStatus(response: HttpResponse, result: any): void {
+    if (isOk(response)) {
+      return
+    }
+    const msg = result && typeof result === 'object' && typeof result.msg === 'string' ? result.msg : ''
+    throw new Error(msg || `OA 系统返回错误状态 ${response.status} ${response.statusText}`.trim())
+  }
 }
 
 // ==================== 工具函数 ====================
@@ -392,7 +409,7 @@ export const encodeBase64 = (str: string): string => {
 
 /**
  * OA Token管理工具
- * 负责Token的存储、获取、验证和清除
+ * 负责Token的存储、获取、验证和清除，各配置档案分别保存
  */
 export class OATokenManager {
   private static readonly TOKEN_KEY = 'oa-token'
@@ -405,9 +422,9 @@ export class OATokenManager {
    */
   static saveToken(token: string, expireTime?: number): void {
     try {
-      localStorage.setItem(this.TOKEN_KEY, token)
+      localStorage.setItem(profileStorageKey(this.TOKEN_KEY), token)
       if (expireTime) {
-        localStorage.setItem(this.TOKEN_EXPIRE_KEY, expireTime.toString())
+        localStorage.setItem(profileStorageKey(this.TOKEN_EXPIRE_KEY), expireTime.toString())
       }
       console.log('[OA Token] Token已保存')
     } catch (error) {
@@ -421,8 +438,8 @@ export class OATokenManager {
    */
   static getToken(): string | null {
     try {
-      const token = localStorage.getItem(this.TOKEN_KEY)
-      const expireTime = localStorage.getItem(this.TOKEN_EXPIRE_KEY)
+      const token = localStorage.getItem(profileStorageKey(this.TOKEN_KEY))
+      const expireTime = localStorage.getItem(profileStorageKey(this.TOKEN_EXPIRE_KEY))
 
       // 检查Token是否过期
       if (expireTime && Date.now() > parseInt(expireTime)) {
@@ -439,16 +456,21 @@ export class OATokenManager {
   }
 
   /**
-   * 清除Token和相关信息
+   * 清除Token和相关信息，同时清除 OA 服务器的会话 Cookie
    */
   static clearToken(): void {
     try {
-      localStorage.removeItem(this.TOKEN_KEY)
-      localStorage.removeItem(this.TOKEN_EXPIRE_KEY)
+      localStorage.removeItem(profileStorageKey(this.TOKEN_KEY))
+      localStorage.removeItem(profileStorageKey(this.TOKEN_EXPIRE_KEY))
       console.log('[OA Token] Token已清除')
     } catch (error) {
       console.error('[OA Token] 清除Token失败:', error)
     }
+
+    const host = hostOf(oaHttpClient.getBaseURL())
+    if (host) {
+      clearCookies(host).catch((error) => console.error('[OA Token] 清除Cookie失败:', error))
+    }
   }
 
   /**
@@ -462,22 +484,17 @@ export class OATokenManager {
 
 /**
  * OA账户信息管理工具
- * 负责用户账户信息的存储和管理
+ * 账户信息保存在后端的基础设置中，密码保存在系统钥匙串中
  */
 export class OAAccountManager {
-  private static readonly SETTINGS_KEY = 'githelper-settings'
-
   /**
-   * 保存账户信息到本地存储
+   * 保存账户信息到基础设置
    * @param username 用户名
    * @param password 密码
    */
-  static saveAccount(username: string, password: string): void {
+  static async saveAccount(username: string, password: string): Promise<void> {
     try {
-      const settings = JSON.parse(localStorage.getItem(this.SETTINGS_KEY) || '{}')
-      settings.oaAccount = username
-      settings.oaPassword = password
-      localStorage.setItem(this.SETTINGS_KEY, JSON.stringify(settings))
+      await updateSettings({ oaAccount: username, oaPassword: password })
       console.log('[OA Account] 账户信息已保存')
     } catch (error) {
       console.error('[OA Account] 保存账户信息失败:', error)
@@ -488,12 +505,12 @@ export class OAAccountManager {
    * 获取保存的账户信息
    * @returns 账户信息对象
    */
-  static getAccount(): { username: string; password: string } {
+  static async getAccount(): Promise<{ username: string; password: string }> {
     try {
-      const settings = JSON.parse(localStorage.getItem(this.SETTINGS_KEY) || '{}')
+      const settings = await getSettings()
       return {
-        username: settings.oaAccount || '',
-        password: settings.oaPassword || ''
+        username: settings.oaAccount,
+        password: settings.oaPassword
       }
     } catch (error) {
       console.error('[OA Account] 获取账户信息失败:', error)
@@ -504,12 +521,9 @@ export class OAAccountManager {
   /**
    * 清除保存的账户信息
    */
-  static clearAccount(): void {
+  static async clearAccount(): Promise<void> {
     try {
-      const settings = JSON.parse(localStorage.getItem(this.SETTINGS_KEY) || '{}')
-      delete settings.oaAccount
-      delete settings.oaPassword
-      localStorage.setItem(this.SETTINGS_KEY, JSON.stringify(settings))
+      await updateSettings({ oaAccount: '', oaPassword: '' })
       console.log('[OA Account] 账户信息已清除')
     } catch (error) {
       console.error('[OA Account] 清除账户信息失败:', error)
@@ -0,0 +1,89 @@
+/**
+ * 配置档案API
+ * 每个档案保存一套独立的设置（OA 地址与账号、大模型接口、模板、同步目录）和项目列表，
+ * 切换档案后重新加载页面，使所有组件读取新档案的数据
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+// ==================== 类型定义 ====================
+
+/**
+ * 档案列表项
+ */
+export interface ProfileInfo {
+  name: string;       // 档案名称
+  active: boolean;    // 是否为当前使用的档案
+}
+
+// 默认档案名称，默认档案的本地数据沿用升级前的键名
+export const DEFAULT_PROFILE = 'default';
+
+// 当前档案名称，应用挂载前由 loadActiveProfile 读取
+let activeProfile = DEFAULT_PROFILE;
+
+// ==================== API 函数 ====================
+
+/**
+ * 列出所有档案，当前档案排在最前
+ */
+export async function listProfiles(): Promise<ProfileInfo[]> {
+  return await invoke<ProfileInfo[]>('list_profiles');
+}
+
+/**
+ * 新建档案
+ * @param name 档案名称，只能包含文字、数字、- 和 _
+ * @param copyCurrent 是否复制当前档案的设置，否则使用默认设置
+ */
+export async function createProfile(name: string, copyCurrent: boolean): Promise<void> {
+  await invoke('create_profile', { name, copyCurrent });
+}
+
+/**
+ * 切换档案，切换后重新加载页面
+ */
+export async function switchProfile(name: string): Promise<void> {
+  await invoke('switch_profile', { name });
+  window.location.reload();
+}
+
+/**
+ * 删除档案及其数据，不能删除当前档案和默认档案
+ */
+export async function deleteProfile(name: string): Promise<void> {
+  await invoke('delete_profile', { name });
+  const suffix = `@${name}`;
+  for (const key of Object.keys(localStorage)) {
+    if (key.endsWith(suffix)) {
+      localStorage.removeItem(key);
+    }
+  }
+}
+
+/**
+ * 读取当前档案名称，需在应用挂载前调用
+ */
+export async function loadActiveProfile(): Promise<string> {
+  try {
+    const profiles = await listProfiles();
+    activeProfile = profiles.find((profile) => profile.active)?.name ?? DEFAULT_PROFILE;
+  } catch (error) {
+    console.error('[Profiles] 读取当前档案失败:', error);
+  }
+  return activeProfile;
+}
+
+/**
+ * 当前档案名称
+ */
+export function getActiveProfile(): string {
+  return activeProfile;
+}
+
+/**
+ * 当前档案的 localStorage 键名，默认档案使用原键名，其他档案加上 @档案名 后缀
+ */
+export function profileStorageKey(key: string): string {
+  return activeProfile === DEFAULT_PROFILE ? key : `${key}@${activeProfile}`;
+}
@@ -0,0 +1,117 @@
+/**
+ * 基础设置API
+ * 基础设置（大模型接口与 Token、Git 用户名、OA 地址与账号、日报周报模板）由后端统一保存，每个配置档案各有一套，
+ * 令牌和密码保存在系统钥匙串中；设置修改后后端会向所有窗口广播 settings-changed 事件
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+// ==================== 类型定义 ====================
+
+/**
+ * 基础设置接口
+ */
+export interface Settings {
+  deepseekToken: string;     // 大模型 API Token
+  llmBaseUrl: string;        // 大模型接口地址（兼容 OpenAI Chat Completions 接口）
+  llmModel: string;          // 大模型名称
+  gitUser: string;           // Git 用户名
+  oaUrl: string;             // OA 接口地址
+  oaAccount: string;         // OA 系统账号
+  oaPassword: string;        // OA 系统密码
+  dailyTemplate: string;     // 日报模板
+  weeklyTemplate: string;    // 周报模板
+  bundleSubscription: string; // 订阅的团队共享配置包地址（http(s) 地址或文件路径），为空表示不订阅
+}
+
+/**
+ * 字段校验错误
+ */
+export interface FieldError {
+  field: keyof Settings | string;
+  message: string;
+}
+
+/**
+ * 更新设置失败的原因
+ */
+export type SettingsError =
+  | { kind: 'invalid'; fields: FieldError[] }   // 字段校验未通过
+  | { kind: 'failed'; message: string };        // 读写配置失败
+
+// 旧版本保存设置的 localStorage 键
+const LEGACY_SETTINGS_KEY = 'githelper-settings';
+const LEGACY_CREDENTIALS_KEY = 'oa-saved-credentials';
+
+// ==================== API 函数 ====================
+
+/**
+ * 获取基础设置
+ */
+export async function getSettings(): Promise<Settings> {
+  return await invoke<Settings>('get_settings');
+}
+
+/**
+ * 获取默认的基础设置
+ */
+export async function getDefaultSettings(): Promise<Settings> {
+  return await invoke<Settings>('get_default_settings');
+}
+
+/**
+ * 更新基础设置，只需传入修改的字段
+ * @throws SettingsError 校验失败时包含各字段的错误信息
+ */
+export async function updateSettings(patch: Partial<Settings>): Promise<Settings> {
+  return await invoke<Settings>('update_settings', { patch });
+}
+
+/**
+ * 监听设置修改事件
+ * @returns 取消监听的函数
+ */
+export async function onSettingsChanged(callback: (settings: Settings) => void): Promise<UnlistenFn> {
+  return await listen<Settings>('settings-changed', (event) => callback(event.payload));
+}
+
+/**
+ * 生成更新设置失败的说明文字
+ */
+export function describeSettingsError(error: unknown): string {
+  const settingsError = error as SettingsError;
+  if (settingsError?.kind === 'invalid') {
+    return settingsError.fields.map((field) => field.message).join('；');
+  }
+  if (settingsError?.kind === 'failed') {
+    return settingsError.message;
+  }
+  return String(error);
+}
+
+/**
+ * 将旧版本保存在 localStorage 中的设置导入后端（只执行一次）
+ * 导入成功后删除 localStorage 中的设置和明文凭据
+ */
+export async function importLocalSettings(): Promise<void> {
+  const raw = localStorage.getItem(LEGACY_SETTINGS_KEY);
+  const legacyCredentials = localStorage.getItem(LEGACY_CREDENTIALS_KEY);
+  if (raw === null && legacyCredentials === null) {
+    return;
+  }
+
+  try {
+    const localSettings = JSON.parse(raw || '{}');
+    const credentials = JSON.parse(legacyCredentials || '{}');
+    localSettings.oaAccount = localSettings.oaAccount || credentials.username;
+    localSettings.oaPassword = localSettings.oaPassword || credentials.password;
+
+    await invoke<Settings>('import_local_settings', { localSettings });
+    localStorage.removeItem(LEGACY_SETTINGS_KEY);
+    localStorage.removeItem(LEGACY_CREDENTIALS_KEY);
+    console.log('[Settings] 已将本地设置导入后端');
+  } catch (error) {
+    console.error('[Settings] 导入本地设置失败:', describeSettingsError(error));
+  }
+}
@@ -0,0 +1,117 @@
+/**
+ * 设置同步API
+ * 负责收集需要同步的本地数据，调用后端加密上传或下载，并将云端数据写回本地
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { profileStorageKey } from './profiles';
+
+// ==================== 类型定义 ====================
+
+/**
+ * 同步配置接口
+ */
+export interface SyncConfig {
+  url: string;               // 同步目录的完整WebDAV地址
+  username: string;          // WebDAV用户名
+  password: string;          // WebDAV密码
+  deviceName: string;        // 本机名称
+}
+
+/**
+ * 两边都有修改时的处理方式
+ */
+export type SyncStrategy = 'lastWriter' | 'merge';
+
+/**
+ * 同步结果状态
+ */
+export type SyncStatus = 'upToDate' | 'pushed' | 'pulled' | 'merged' | 'conflict';
+
+/**
+ * 同步结果接口
+ */
+export interface SyncResult {
+  status: SyncStatus;
+  localStorage?: Record<string, string>; // 需要写入本地的数据
+  remoteDevice?: string;                 // 云端数据来自的设备
+  remoteUpdatedAt?: number;              // 云端数据的上传时间（秒）
+}
+
+// 需要同步的 localStorage 键：项目列表（基础设置与模板由后端同步），读写时使用当前档案的键名
+export const SYNC_KEYS = ['githelper-projects'];
+
+// ==================== API 函数 ====================
+
+/**
+ * 收集需要同步的本地数据
+ */
+function collectLocalData(): Record<string, string> {
+  const data: Record<string, string> = {};
+  for (const key of SYNC_KEYS) {
+    const value = localStorage.getItem(profileStorageKey(key));
+    if (value !== null) {
+      data[key] = value;
+    }
+  }
+  return data;
+}
+
+/**
+ * 将云端数据写入本地
+ * @returns 是否写入了数据（写入后需要刷新页面）
+ */
+function applyResult(result: SyncResult): boolean {
+  if (!result.localStorage) {
+    return false;
+  }
+  for (const [key, value] of Object.entries(result.localStorage)) {
+    if (SYNC_KEYS.includes(key)) {
+      localStorage.setItem(profileStorageKey(key), value);
+    }
+  }
+  return true;
+}
+
+/**
+ * 获取同步配置
+ */
+export async function getSyncConfig(): Promise<SyncConfig> {
+  return await invoke<SyncConfig>('get_sync_config');
+}
+
+/**
+ * 保存同步配置
+ */
+export async function saveSyncConfig(syncConfig: SyncConfig): Promise<void> {
+  await invoke('save_sync_config', { syncConfig });
+}
+
+/**
+ * 同步设置
+ * @param passphrase 加密口令
+ * @param strategy 两边都有修改时的处理方式，不传时返回 conflict 由用户选择
+ * @returns Promise<SyncResult> 同步结果，云端数据已写入本地
+ */
+export async function syncSettings(passphrase: string, strategy?: SyncStrategy): Promise<SyncResult> {
+  console.log('☁️ 开始同步设置');
+  const result = await invoke<SyncResult>('sync_settings', {
+    passphrase,
+    localStorage: collectLocalData(),
+    strategy
+  });
+  applyResult(result);
+  console.log('✅ 设置同步完成:', result.status);
+  return result;
+}
+
+/**
+ * 从云端恢复设置，覆盖本地数据
+ * @param passphrase 加密口令
+ */
+export async function restoreSyncedSettings(passphrase: string): Promise<SyncResult> {
+  console.log('☁️ 从云端恢复设置');
+  const result = await invoke<SyncResult>('restore_synced_settings', { passphrase });
+  applyResult(result);
+  return result;
+}
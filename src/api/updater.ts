@@ -15,10 +15,28 @@ export interface VersionInfo {
   releaseDate: string;       // 发布日期
   changelog: string;         // 更新日志内容
   downloadUrl: string;       // 下载链接
+  mirrors: string[];         // 备用下载地址，主地址失败时按顺序尝试
   fileSize: number;          // 文件大小（字节）
   fileSizeFormatted: string; // 格式化的文件大小，如 "25.6 MB"
-  platform: string;         // 平台标识，如 "windows"
+  platform: string;         // 平台标识，如 "windows"、"linux"、"macos"
+  arch: string;              // 架构，如 "x86_64"、"aarch64"
+  fileName: string;          // 安装包文件名
   fileType: string;          // 文件类型，如 "exe" 或 "msi"
+  format: string;            // 安装包格式："nsis" | "msi" | "appimage" | "deb" | "dmg"
+  channel: string;           // 更新通道："stable" | "beta" | "nightly"
+  sha256?: string;           // 完整安装包的SHA-256
+  delta?: DeltaInfo;         // 可用的差分补丁
+}
+
+/**
+ * 差分补丁信息接口
+ */
+export interface DeltaInfo {
+  baseVersion: string;       // 补丁基于的版本
+  url: string;               // 补丁下载地址
+  fileSize: number;          // 补丁大小（字节）
+  sha256: string;            // 补丁文件的SHA-256
+  baseSha256: string;        // 基础安装包的SHA-256
 }
 
 /**
@@ -29,6 +47,10 @@ export interface UpdateCheckResult {
   currentVersion: string;    // 当前版本
   latestVersion?: string;    // 最新版本
   versionInfo?: VersionInfo; // 版本详细信息
+  mandatory: boolean;        // 是否必须更新（低于最低版本或当前版本已被禁用）
+  rolloutEligible: boolean;  // 当前安装是否在灰度推送范围内
+  noArtifactForPlatform: boolean; // 最新版本没有适用于当前平台的安装包
+  platform: string;          // 当前平台标识，例如 "windows-x86_64"
   error?: string;           // 错误信息
 }
 
@@ -54,6 +76,53 @@ export enum DownloadStatus {
   CANCELLED = 'cancelled'          // 下载取消
 }
 
+/**
+ * 安装记录接口
+ */
+export interface InstallRecord {
+  fromVersion: string;      // 安装前的版本
+  toVersion: string;        // 安装的版本
+  fileName: string;         // 安装包文件名
+  fromFileName?: string;    // 安装前版本的安装包文件名
+  installedAt: number;      // 安装时间（Unix时间戳，秒）
+  rollback: boolean;        // 是否为回滚安装
+}
+
+/**
+ * 安装历史接口
+ */
+export interface InstallHistory {
+  currentVersion: string;
+  records: InstallRecord[];
+  rollbackVersions: string[]; // 可回滚到的版本
+}
+
+/**
+ * 回滚建议接口（新版本启动失败时）
+ */
+export interface RollbackSuggestion {
+  failedVersion: string;    // 启动失败的版本
+  rollbackVersion: string;  // 建议回滚到的版本
+}
+
+/**
+ * 后台预下载完成事件接口（update-downloaded 事件）
+ */
+export interface UpdateDownloadedEvent {
+  version: string;          // 版本号
+  filePath: string;         // 更新包本地路径
+}
+
+/**
+ * 下载状态事件接口（download-status 事件）
+ */
+export interface DownloadStatusEvent {
+  downloadId: string;       // 下载ID
+  status: DownloadStatus;   // 最终状态
+  error?: string;           // 错误信息
+  servedBy?: string;        // 实际提供文件的下载地址
+}
+
 /**
  * 下载结果接口
  */
@@ -62,6 +131,7 @@ export interface DownloadResult {
   filePath?: string;        // 下载文件的本地路径 (前端格式)
   file_path?: string;       // 下载文件的本地路径 (后端格式)
   error?: string;          // 错误信息
+  served_by?: string;      // 实际提供文件的下载地址（主地址或镜像）
 }
 
 // ==================== API 函数 ====================
@@ -81,6 +151,10 @@ export async function checkForUpdates(): Promise<UpdateCheckResult> {
     return {
       hasUpdate: false,
       currentVersion: await getCurrentVersion(),
+      mandatory: false,
+      rolloutEligible: false,
+      noArtifactForPlatform: false,
+      platform: '',
       error: error instanceof Error ? error.message : '检查更新失败'
     };
   }
@@ -120,8 +194,12 @@ export async function downloadUpdate(
       try {
         const result = await invoke<DownloadResult>('download_update', {
           downloadUrl: versionInfo.downloadUrl,
-          fileName: `WorkHelper_${versionInfo.version}_x64-setup.exe`,
-          expectedSize: versionInfo.fileSize
+          mirrors: versionInfo.mirrors,
+          fileName: versionInfo.fileName,
+          expectedSize: versionInfo.fileSize,
+          downloadId: versionInfo.version,
+          sha256: versionInfo.sha256,
+          delta: versionInfo.delta
         });
         
         return result;
@@ -133,8 +211,12 @@ export async function downloadUpdate(
       // 不需要进度回调的简单下载
       return await invoke<DownloadResult>('download_update', {
         downloadUrl: versionInfo.downloadUrl,
-        fileName: `WorkHelper_${versionInfo.version}_x64-setup.exe`,
-        expectedSize: versionInfo.fileSize
+        mirrors: versionInfo.mirrors,
+        fileName: versionInfo.fileName,
+        expectedSize: versionInfo.fileSize,
+        downloadId: versionInfo.version,
+        sha256: versionInfo.sha256,
+        delta: versionInfo.delta
       });
     }
   } catch (error) {
@@ -167,13 +249,14 @@ async function listenToDownloadProgress(
 
 /**
  * 取消下载
+ * @param downloadId 下载ID（版本号），不传则取消全部下载
+ * @param keepPartial 是否保留已下载部分以便之后续传，默认保留
  * @returns Promise<boolean> 是否成功取消
  */
-export async function cancelDownload(): Promise<boolean> {
+export async function cancelDownload(downloadId?: string, keepPartial = true): Promise<boolean> {
   try {
     console.log('🛑 取消下载更新包');
-    await invoke('cancel_download');
-    return true;
+    return await invoke<boolean>('cancel_download', { downloadId, keepPartial });
   } catch (error) {
     console.error('❌ 取消下载失败:', error);
     return false;
@@ -183,12 +266,13 @@ export async function cancelDownload(): Promise<boolean> {
 /**
  * 安装更新包并重启应用
  * @param filePath 更新包文件路径
+ * @param version 安装的版本号，用于记录安装历史以便回滚
  * @returns Promise<boolean> 是否成功启动安装
  */
-export async function installUpdateAndRestart(filePath: string): Promise<boolean> {
+export async function installUpdateAndRestart(filePath: string, version?: string): Promise<boolean> {
   try {
     console.log('🔄 开始安装更新并重启应用:', filePath);
-    await invoke('install_update_and_restart', { filePath });
+    await invoke('install_update_and_restart', { filePath, version });
     return true;
   } catch (error) {
     console.error('❌ 安装更新失败:', error);
@@ -199,11 +283,12 @@ export async function installUpdateAndRestart(filePath: string): Promise<boolean
 /**
  * 检查是否已下载指定版本的更新包
  * @param version 版本号
+ * @param fileName 安装包文件名（来自版本信息）
  * @returns Promise<string | null> 如果已下载返回文件路径，否则返回null
  */
-export async function getDownloadedUpdatePath(version: string): Promise<string | null> {
+export async function getDownloadedUpdatePath(version: string, fileName?: string): Promise<string | null> {
   try {
-    const result = await invoke<string | null>('get_downloaded_update_path', { version });
+    const result = await invoke<string | null>('get_downloaded_update_path', { version, fileName });
     return result;
   } catch (error) {
     console.error('❌ 检查已下载更新失败:', error);
@@ -225,3 +310,75 @@ export async function cleanupOldUpdates(): Promise<boolean> {
     return false;
   }
 }
+
+/**
+ * 获取安装历史
+ * @returns Promise<InstallHistory | null> 安装历史，获取失败返回null
+ */
+export async function getInstallHistory(): Promise<InstallHistory | null> {
+  try {
+    return await invoke<InstallHistory>('get_install_history');
+  } catch (error) {
+    console.error('❌ 获取安装历史失败:', error);
+    return null;
+  }
+}
+
+/**
+ * 获取回滚建议（更新后的新版本上次启动失败时存在）
+ * @returns Promise<RollbackSuggestion | null> 回滚建议
+ */
+export async function getRollbackSuggestion(): Promise<RollbackSuggestion | null> {
+  try {
+    return await invoke<RollbackSuggestion | null>('get_rollback_suggestion');
+  } catch (error) {
+    console.error('❌ 获取回滚建议失败:', error);
+    return null;
+  }
+}
+
+/**
+ * 回滚到之前安装的版本，成功时应用会退出并启动安装程序
+ * @param version 目标版本，不传则回滚到上一个版本
+ * @returns Promise<boolean> 是否成功启动回滚
+ */
+export async function rollbackUpdate(version?: string): Promise<boolean> {
+  try {
+    console.log('⏪ 开始回滚版本:', version ?? '上一个版本');
+    await invoke('rollback_update', { version });
+    return true;
+  } catch (error) {
+    console.error('❌ 回滚失败:', error);
+    return false;
+  }
+}
+
+/**
+ * 跳过指定版本，后台检查不再提示该版本
+ * @param version 要跳过的版本号
+ * @returns Promise<boolean> 是否保存成功
+ */
+export async function skipUpdateVersion(version: string): Promise<boolean> {
+  try {
+    await invoke('skip_update_version', { version });
+    return true;
+  } catch (error) {
+    console.error('❌ 跳过版本失败:', error);
+    return false;
+  }
+}
+
+/**
+ * 稍后提醒，在指定时间内后台检查不再提示
+ * @param hours 推迟的小时数，默认24小时
+ * @returns Promise<boolean> 是否保存成功
+ */
+export async function remindUpdateLater(hours?: number): Promise<boolean> {
+  try {
+    await invoke('remind_update_later', { hours });
+    return true;
+  } catch (error) {
+    console.error('❌ 设置稍后提醒失败:', error);
+    return false;
+  }
+}
@@ -19,15 +19,20 @@ import App from './App.vue'
 // Tauri API
 import { invoke } from '@tauri-apps/api/core'
 
+// 配置档案
+import { loadActiveProfile } from './api/profiles'
+
 // ==================== 应用初始化 ====================
 
 /**
  * 创建并挂载 Vue 应用实例
+ * 挂载前先读取当前配置档案，组件按档案读取本地数据
  */
 const app = createApp(App)
-app.mount('#app')
-
-console.log('✅ Vue 应用已成功挂载')
+loadActiveProfile().finally(() => {
+  app.mount('#app')
+  console.log('✅ Vue 应用已成功挂载')
+})
 
 // ==================== 启动画面管理 ====================
 
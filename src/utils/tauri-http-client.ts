@@ -11,6 +11,12 @@
  * - 支持所有HTTP方法（GET、POST、PUT、DELETE）
  * - 自动JSON序列化和反序列化
  * - 完整的错误处理机制
+ *
+ * 后端只允许访问设置中的服务器（OA 地址、大模型接口、更新源），
+ * 并限制请求头和请求体大小，不满足时以 HttpRequestError 拒绝。
+ * send 返回包含状态码、响应头、最终地址和耗时的完整响应，二进制响应体以 base64 返回；
+ * request 及快捷方法只返回解析后的数据，非 2xx 响应视为失败。
+ * Cookie 由后端按服务器保存并自动携带，见 api/cookies
  */
 
 import { invoke } from '@tauri-apps/api/core';
@@ -24,6 +30,94 @@ export interface HttpRequestOptions {
   method?: 'GET' | 'POST' | 'PUT' | 'DELETE';  // HTTP方法
   headers?: Record<string, string>;             // 请求头
   body?: any;                                   // 请求体
+  errorForStatus?: boolean;                     // 非 2xx 响应是否视为失败，send 默认否，request 默认是
+}
+
+/**
+ * 完整的HTTP响应
+ */
+export interface HttpResponse {
+  status: number;                       // HTTP状态码
+  statusText: string;                   // 状态码说明，如 Not Found
+  headers: Record<string, string>;      // 响应头，名称为小写
+  body: string;                         // 响应体
+  bodyEncoding: 'text' | 'base64';      // 响应体编码，二进制内容（如图片）为 base64
+  url: string;                          // 跟随重定向后的最终地址
+  elapsedMs: number;                    // 请求耗时（毫秒）
+}
+
+/**
+ * 请求被拒绝或失败的原因
+ */
+export type HttpRequestError =
+  | { kind: 'invalidUrl'; url: string; message: string }           // 地址无效
+  | { kind: 'schemeNotAllowed'; scheme: string }                  // 只允许 http 和 https
+  | { kind: 'hostNotAllowed'; origin: string; allowed: string[] } // 服务器不在允许访问的列表中
+  | { kind: 'methodNotAllowed'; method: string; origin?: string } // 请求方法不允许，origin 为只允许 GET 的更新服务器
+  | { kind: 'headerNotAllowed'; name: string; message: string }   // 请求头不允许
+  | { kind: 'bodyTooLarge'; size: number; limit: number }         // 请求体过大
+  | { kind: 'failed'; message: string }                           // 发送请求或读取响应失败
+  | { kind: 'status'; response: HttpResponse };                   // 服务器返回了错误状态
+
+/**
+ * 生成请求错误的说明文字
+ */
+export function describeHttpRequestError(error: unknown): string {
+  const requestError = error as HttpRequestError;
+  switch (requestError?.kind) {
+    case 'invalidUrl':
+      return `请求地址 ${requestError.url} 无效：${requestError.message}`;
+    case 'schemeNotAllowed':
+      return `不允许使用 ${requestError.scheme} 协议`;
+    case 'hostNotAllowed':
+      return `不允许访问 ${requestError.origin}，只能访问设置中的 OA、大模型和更新服务器`;
+    case 'methodNotAllowed':
+      return requestError.origin
+        ? `${requestError.origin} 是更新服务器，只允许 GET 请求，不允许 ${requestError.method}`
+        : `不支持的HTTP方法：${requestError.method}`;
+    case 'headerNotAllowed':
+      return `请求头 ${requestError.name} 不允许：${requestError.message}`;
+    case 'bodyTooLarge':
+      return `请求体过大（${requestError.size} 字节），最大允许 ${requestError.limit} 字节`;
+    case 'failed':
+      return requestError.message;
+    case 'status':
+      return `服务器返回错误状态 ${requestError.response.status} ${requestError.response.statusText}`.trim();
+    default:
+      return String(error);
+  }
+}
+
+/**
+ * 状态码是否为 2xx
+ */
+export function isOk(response: HttpResponse): boolean {
+  return response.status >= 200 && response.status < 300;
+}
+
+/**
+ * 解析响应体：JSON 文本解析为对象，其他文本原样返回，二进制内容返回 base64 字符串
+ */
+export function parseResponseBody<T = any>(response: HttpResponse): T {
+  if (response.bodyEncoding === 'base64') {
+    return response.body as unknown as T;
+  }
+  try {
+    return JSON.parse(response.body) as T;
+  } catch {
+    // 如果不是JSON，返回原始字符串
+    return response.body as unknown as T;
+  }
+}
+
+/**
+ * 将二进制响应（如验证码图片）转换为可用于 img 标签的 data URL
+ */
+export function toDataUrl(response: HttpResponse): string {
+  const contentType = response.headers['content-type'] || 'application/octet-stream';
+  return response.bodyEncoding === 'base64'
+    ? `data:${contentType};base64,${response.body}`
+    : `data:${contentType},${encodeURIComponent(response.body)}`;
 }
 
 // ==================== HTTP客户端类 ====================
@@ -33,7 +127,7 @@ export interface HttpRequestOptions {
  * 通过Tauri命令发送HTTP请求，绕过浏览器跨域限制
  */
 export class TauriHttpClient {
-  private readonly baseURL: string;
+  private baseURL: string;
 
   /**
    * 构造函数
@@ -44,16 +138,32 @@ export class TauriHttpClient {
   }
 
   /**
-   * 发送HTTP请求的核心方法
+   * 修改基础URL
+   * @param baseURL 新的基础URL
+   */
+  setBaseURL(baseURL: string): void {
+    this.baseURL = baseURL;
+  }
+
+  /**
+   * 当前的基础URL
+   */
+  getBaseURL(): string {
+    return this.baseURL;
+  }
+
+  /**
+   * 发送HTTP请求并返回完整响应
    * @param url 请求URL
    * @param options 请求选项
-   * @returns Promise<T> 响应数据
+   * @returns Promise<HttpResponse> 完整响应
    */
-  async request<T = any>(url: string, options: HttpRequestOptions = {}): Promise<T> {
+  async send(url: string, options: HttpRequestOptions = {}): Promise<HttpResponse> {
     const {
       method = 'GET',
       headers = {},
-      body
+      body,
+      errorForStatus = false
     } = options;
 
     // 构建完整URL
@@ -69,22 +179,34 @@ export class TauriHttpClient {
       console.log(`[Tauri HTTP] ${method} ${fullUrl}`);
 
       // 调用Tauri命令发送请求
-      const response = await invoke('http_request', {
+      const response = await invoke<HttpResponse>('http_request', {
         url: fullUrl,
         method: method.toUpperCase(),
         headers: requestHeaders,
-        body: requestBody
-      }) as string;
+        body: requestBody,
+        errorForStatus
+      });
 
-      // 解析响应
-      return this.parseResponse<T>(response);
+      console.log(`[Tauri HTTP] ${response.status} ${response.url} (${response.elapsedMs}ms)`);
+      return response;
 
     } catch (error) {
       console.error(`[Tauri HTTP] 请求失败:`, error);
-      throw error;
+      throw new Error(describeHttpRequestError(error), { cause: error });
     }
   }
 
+  /**
+   * 发送HTTP请求并返回解析后的数据，默认非 2xx 响应视为失败
+   * @param url 请求URL
+   * @param options 请求选项
+   * @returns Promise<T> 响应数据
+   */
+  async request<T = any>(url: string, options: HttpRequestOptions = {}): Promise<T> {
+    const response = await this.send(url, { errorForStatus: true, ...options });
+    return parseResponseBody<T>(response);
+  }
+
   /**
    * 构建完整URL
    * @param url 相对或绝对URL
@@ -116,20 +238,6 @@ export class TauriHttpClient {
     return body ? JSON.stringify(body) : undefined;
   }
 
-  /**
-   * 解析响应数据
-   * @param response 原始响应字符串
-   * @returns T 解析后的数据
-   */
-  private parseResponse<T>(response: string): T {
-    try {
-      return JSON.parse(response) as T;
-    } catch {
-      // 如果不是JSON，返回原始字符串
-      return response as unknown as T;
-    }
-  }
-
   // ==================== HTTP方法快捷方式 ====================
 
   /**
@@ -179,7 +287,7 @@ export class TauriHttpClient {
 
 /**
  * OA系统API客户端实例
- * 预配置了OA系统的基础URL
+ * 预配置了默认的OA系统基础URL，启动后按设置中的 OA 地址修改
  */
 export const oaHttpClient = new TauriHttpClient('https://ai.mufengweilai.com/api/oa');
 